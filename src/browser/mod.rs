@@ -2,4 +2,4 @@ pub mod launcher;
 pub mod pool;
 pub mod session;
 
-pub use session::BrowserSession;
+pub use session::{BrowserSession, Tabs};
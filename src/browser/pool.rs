@@ -1,29 +1,77 @@
 use chromiumoxide::page::Page;
+use std::collections::HashSet;
+
+/// Anything the pool can track as a tab — a CDP page in practice.
+pub trait PoolTab: Clone {
+    /// The Chrome target id identifying this tab.
+    fn tab_id(&self) -> &str;
+}
+
+impl PoolTab for Page {
+    fn tab_id(&self) -> &str {
+        self.target_id().as_ref()
+    }
+}
 
 /// Manages multiple browser tabs/pages.
-pub struct TabPool {
-    pages: Vec<Page>,
+pub struct TabPool<P: PoolTab = Page> {
+    pages: Vec<P>,
     active_index: usize,
+    /// Targets Chrome announced that are still waiting to be adopted.
+    pending: HashSet<String>,
 }
 
-impl TabPool {
-    pub fn new(initial_page: Page) -> Self {
+impl<P: PoolTab> TabPool<P> {
+    pub fn new(initial_page: P) -> Self {
         Self {
             pages: vec![initial_page],
             active_index: 0,
+            pending: HashSet::new(),
         }
     }
 
-    pub fn active_page(&self) -> &Page {
-        &self.pages[self.active_index]
+    /// The active page, or `None` once every tab has been closed.
+    pub fn active_page(&self) -> Option<&P> {
+        self.pages.get(self.active_index)
     }
 
-    pub fn add_page(&mut self, page: Page) {
+    /// Add a page and make it active. Returns `false` if the pool already
+    /// tracked it (e.g. the target watcher adopted it first); it is still activated.
+    pub fn add_page(&mut self, page: P) -> bool {
+        self.pending.remove(page.tab_id());
+        let added = self.adopt_page(page.clone());
+        self.select_by_target_id(page.tab_id());
+        added
+    }
+
+    /// Track a page without changing the active tab — used for popups.
+    /// Returns `false` if a page with the same target id is already tracked.
+    pub fn adopt_page(&mut self, page: P) -> bool {
+        if self.contains(page.tab_id()) {
+            return false;
+        }
         self.pages.push(page);
-        self.active_index = self.pages.len() - 1;
+        true
+    }
+
+    /// Note a target Chrome just created so it can be adopted once its page is ready.
+    pub fn expect_page(&mut self, target_id: &str) {
+        if !self.contains(target_id) {
+            self.pending.insert(target_id.to_string());
+        }
     }
 
-    pub fn select_page(&mut self, index: usize) -> Option<&Page> {
+    /// Adopt a page announced via [`expect_page`](Self::expect_page), unless it was
+    /// closed or added by other means in the meantime.
+    pub fn adopt_expected(&mut self, page: P) -> bool {
+        self.pending.remove(page.tab_id()) && self.adopt_page(page)
+    }
+
+    pub fn contains(&self, target_id: &str) -> bool {
+        self.pages.iter().any(|p| p.tab_id() == target_id)
+    }
+
+    pub fn select_page(&mut self, index: usize) -> Option<&P> {
         if index < self.pages.len() {
             self.active_index = index;
             Some(&self.pages[self.active_index])
@@ -32,9 +80,9 @@ impl TabPool {
         }
     }
 
-    pub fn select_by_target_id(&mut self, target_id: &str) -> Option<&Page> {
+    pub fn select_by_target_id(&mut self, target_id: &str) -> Option<&P> {
         for (i, page) in self.pages.iter().enumerate() {
-            if page.target_id().as_ref() == target_id {
+            if page.tab_id() == target_id {
                 self.active_index = i;
                 return Some(page);
             }
@@ -43,12 +91,13 @@ impl TabPool {
     }
 
     pub fn remove_page(&mut self, target_id: &str) -> bool {
-        if let Some(pos) = self
-            .pages
-            .iter()
-            .position(|p| p.target_id().as_ref() == target_id)
-        {
+        self.pending.remove(target_id);
+        if let Some(pos) = self.pages.iter().position(|p| p.tab_id() == target_id) {
             self.pages.remove(pos);
+            // Keep the same tab active when an earlier one goes away
+            if pos < self.active_index {
+                self.active_index -= 1;
+            }
             if self.active_index >= self.pages.len() && !self.pages.is_empty() {
                 self.active_index = self.pages.len() - 1;
             }
@@ -58,7 +107,11 @@ impl TabPool {
         }
     }
 
-    pub fn list_pages(&self) -> &[Page] {
+    pub fn get_page(&self, target_id: &str) -> Option<&P> {
+        self.pages.iter().find(|p| p.tab_id() == target_id)
+    }
+
+    pub fn list_pages(&self) -> &[P] {
         &self.pages
     }
 
@@ -66,3 +119,113 @@ impl TabPool {
         self.pages.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl PoolTab for String {
+        fn tab_id(&self) -> &str {
+            self
+        }
+    }
+
+    fn pool(ids: &[&str]) -> TabPool<String> {
+        let mut pool = TabPool::new(ids[0].to_string());
+        for id in &ids[1..] {
+            pool.adopt_page(id.to_string());
+        }
+        pool
+    }
+
+    fn active(pool: &TabPool<String>) -> Option<&str> {
+        pool.active_page().map(|p| p.as_str())
+    }
+
+    #[test]
+    fn test_add_page_activates_and_dedupes() {
+        let mut pool = pool(&["a"]);
+        assert!(pool.add_page("b".into()));
+        assert_eq!(active(&pool), Some("b"));
+        pool.select_page(0);
+        // Already adopted by the watcher: not added twice, but still activated
+        assert!(!pool.add_page("b".into()));
+        assert_eq!(pool.page_count(), 2);
+        assert_eq!(active(&pool), Some("b"));
+    }
+
+    #[test]
+    fn test_adopt_page_keeps_active_tab() {
+        let mut pool = pool(&["a"]);
+        assert!(pool.adopt_page("popup".into()));
+        assert!(!pool.adopt_page("popup".into()));
+        assert_eq!(active(&pool), Some("a"));
+        assert_eq!(pool.page_count(), 2);
+    }
+
+    #[test]
+    fn test_remove_earlier_page_keeps_active_tab() {
+        let mut pool = pool(&["a", "b", "c"]);
+        pool.select_by_target_id("c");
+        assert!(pool.remove_page("a"));
+        assert_eq!(active(&pool), Some("c"));
+        assert!(pool.remove_page("b"));
+        assert_eq!(active(&pool), Some("c"));
+        assert!(!pool.remove_page("b"));
+    }
+
+    #[test]
+    fn test_remove_active_page_falls_back() {
+        let mut pool = pool(&["a", "b", "c"]);
+        pool.select_by_target_id("c");
+        pool.remove_page("c");
+        assert_eq!(active(&pool), Some("b"));
+        pool.select_by_target_id("a");
+        pool.remove_page("a");
+        assert_eq!(active(&pool), Some("b"));
+        pool.remove_page("b");
+        assert_eq!(active(&pool), None);
+        assert_eq!(pool.page_count(), 0);
+    }
+
+    #[test]
+    fn test_select_and_lookup() {
+        let mut pool = pool(&["a", "b"]);
+        assert_eq!(pool.select_page(1).map(|p| p.as_str()), Some("b"));
+        assert!(pool.select_page(2).is_none());
+        assert_eq!(active(&pool), Some("b"));
+        assert!(pool.select_by_target_id("zzz").is_none());
+        assert!(pool.contains("a"));
+        assert_eq!(pool.get_page("a").map(|p| p.as_str()), Some("a"));
+        assert_eq!(pool.list_pages(), ["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn test_expected_page_is_adopted_once() {
+        let mut pool = pool(&["a"]);
+        pool.expect_page("popup");
+        assert!(pool.adopt_expected("popup".into()));
+        assert!(!pool.adopt_expected("popup".into()));
+        assert_eq!(pool.page_count(), 2);
+        assert_eq!(active(&pool), Some("a"));
+    }
+
+    #[test]
+    fn test_closed_before_adoption_is_skipped() {
+        let mut pool = pool(&["a"]);
+        pool.expect_page("popup");
+        assert!(!pool.remove_page("popup"));
+        assert!(!pool.adopt_expected("popup".into()));
+        assert!(!pool.contains("popup"));
+    }
+
+    #[test]
+    fn test_unannounced_page_is_not_adopted() {
+        let mut pool = pool(&["a"]);
+        assert!(!pool.adopt_expected("stray".into()));
+        // Tracked pages are never re-announced
+        pool.expect_page("a");
+        assert!(!pool.adopt_expected("a".into()));
+        assert_eq!(pool.page_count(), 1);
+    }
+}
@@ -1,25 +1,45 @@
 use anyhow::{Context, Result};
 use chromiumoxide::browser::{Browser, BrowserConfig};
+use chromiumoxide::cdp::browser_protocol::target::{
+    EventTargetCreated, EventTargetDestroyed, SessionId, SetAutoAttachParams, TargetId,
+};
+use chromiumoxide::cdp::events::{CdpEvent, CdpEventMessage};
+use chromiumoxide::cdp::js_protocol::runtime::RunIfWaitingForDebuggerParams;
 use chromiumoxide::page::Page;
+use chromiumoxide::types::Message;
+use chromiumoxide::{Connection, Method};
 use futures::StreamExt;
-use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use tokio::sync::{Mutex, OnceCell};
 
 use super::pool::TabPool;
+use crate::tools::emulation::Emulation;
+use crate::tools::javascript::{self, ConsoleLog};
+use crate::tools::network::{self, NetworkLog};
+use crate::tools::route::{self, Routes};
+use crate::tools::snapshot::RefCounter;
 
 /// Manages the CDP browser connection and page lifecycle.
 pub struct BrowserSession {
-    browser: Browser,
+    browser: Arc<Browser>,
     _handler_task: tokio::task::JoinHandle<()>,
     pub pool: Arc<Mutex<TabPool>>,
     headless: bool,
+    setup: TabSetup,
     /// Unique temp dir for this Chrome instance — cleaned up on drop.
     _user_data_dir: tempfile::TempDir,
 }
 
 impl BrowserSession {
     /// Launch a new browser and establish CDP connection.
-    pub async fn launch(headless: bool) -> Result<Self> {
+    pub async fn launch(
+        headless: bool,
+        console_log: ConsoleLog,
+        network_log: NetworkLog,
+        routes: Routes,
+        emulation: Emulation,
+    ) -> Result<Self> {
         let user_data_dir = tempfile::tempdir().context("Failed to create temp dir for Chrome")?;
 
         let mut builder = BrowserConfig::builder().user_data_dir(user_data_dir.path());
@@ -62,35 +82,173 @@ impl BrowserSession {
             .await
             .context("Failed to create initial page")?;
 
-        let pool = Arc::new(Mutex::new(TabPool::new(page)));
+        let pool = Arc::new(Mutex::new(TabPool::new(page.clone())));
+        let browser = Arc::new(browser);
 
         tracing::info!("Browser session started (headless: {})", headless);
 
-        Ok(Self {
+        let session = Self {
             browser,
             _handler_task: handler_task,
             pool,
             headless,
+            setup: TabSetup::new(console_log, network_log, routes, emulation),
             _user_data_dir: user_data_dir,
-        })
+        };
+        session.setup.attach(&page).await;
+        session.watch_targets().await?;
+        if let Err(e) = session.pause_new_targets().await {
+            tracing::warn!("New tabs will start without waiting for listeners: {:#}", e);
+        }
+        Ok(session)
+    }
+
+    /// Keep the pool in sync with Chrome: adopt popups (`window.open`, `target=_blank`)
+    /// and drop tabs the page closed itself. Holds only a weak browser reference so
+    /// dropping the session still shuts Chrome down.
+    async fn watch_targets(&self) -> Result<()> {
+        let mut created = self
+            .browser
+            .event_listener::<EventTargetCreated>()
+            .await
+            .context("Failed to listen for new targets")?;
+        let mut destroyed = self
+            .browser
+            .event_listener::<EventTargetDestroyed>()
+            .await
+            .context("Failed to listen for closed targets")?;
+        let browser = Arc::downgrade(&self.browser);
+        let pool = self.pool.clone();
+        let setup = self.setup.clone();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    Some(event) = created.next() => {
+                        if event.target_info.r#type != "page" {
+                            continue;
+                        }
+                        let target_id = event.target_info.target_id.clone();
+                        pool.lock().await.expect_page(target_id.as_ref());
+                        // Adopt off the loop so close events keep flowing while the
+                        // page attaches; a tab closed meanwhile is no longer expected.
+                        let browser = browser.clone();
+                        let pool = pool.clone();
+                        let setup = setup.clone();
+                        tokio::spawn(async move {
+                            adopt_target(&browser, &pool, &setup, target_id).await;
+                        });
+                    }
+                    Some(event) = destroyed.next() => {
+                        pool.lock().await.remove_page(event.target_id.as_ref());
+                        setup.forget(event.target_id.as_ref());
+                    }
+                    else => break,
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Hold every new tab paused until its listeners are attached, so the first
+    /// requests of popups and new tabs are already captured, routed and throttled.
+    /// Runs on a connection of its own: the tab must be resumed through the session
+    /// that auto-attached to it, which chromiumoxide does not expose.
+    async fn pause_new_targets(&self) -> Result<()> {
+        let mut conn = Connection::<CdpEventMessage>::connect(self.browser.websocket_address())
+            .await
+            .context("Failed to open a second CDP connection")?;
+        let auto_attach = SetAutoAttachParams::builder()
+            .auto_attach(true)
+            .wait_for_debugger_on_start(true)
+            .flatten(true)
+            .build()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        conn.submit_command(
+            auto_attach.identifier(),
+            None,
+            serde_json::to_value(auto_attach)?,
+        )?;
+
+        let browser = Arc::downgrade(&self.browser);
+        let pool = self.pool.clone();
+        let setup = self.setup.clone();
+        let (resume_tx, mut resume_rx) = tokio::sync::mpsc::unbounded_channel::<SessionId>();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    message = conn.next() => {
+                        let event = match message {
+                            Some(Ok(Message::Event(event))) => event,
+                            Some(Ok(Message::Response(response))) => {
+                                if let Some(error) = response.error {
+                                    tracing::warn!("Auto-attach command failed: {}", error.message);
+                                }
+                                continue;
+                            }
+                            Some(Err(e)) => {
+                                tracing::debug!("Ignoring CDP message: {}", e);
+                                continue;
+                            }
+                            None => break,
+                        };
+                        let CdpEvent::TargetAttachedToTarget(event) = event.params else {
+                            continue;
+                        };
+                        // Tabs that were already open are attached without pausing
+                        if !event.waiting_for_debugger {
+                            continue;
+                        }
+                        let session_id = event.session_id.clone();
+                        if event.target_info.r#type != "page" {
+                            let _ = resume_tx.send(session_id);
+                            continue;
+                        }
+                        let target_id = event.target_info.target_id.clone();
+                        pool.lock().await.expect_page(target_id.as_ref());
+                        let browser = browser.clone();
+                        let pool = pool.clone();
+                        let setup = setup.clone();
+                        let resume_tx = resume_tx.clone();
+                        tokio::spawn(async move {
+                            adopt_target(&browser, &pool, &setup, target_id).await;
+                            let _ = resume_tx.send(session_id);
+                        });
+                    }
+                    Some(session_id) = resume_rx.recv() => {
+                        if let Err(e) = resume(&mut conn, session_id) {
+                            tracing::warn!("Failed to resume new tab: {:#}", e);
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// A cloneable handle for tab operations that can outlive the session lock.
+    pub fn tabs(&self) -> Tabs {
+        Tabs {
+            browser: self.browser.clone(),
+            pool: self.pool.clone(),
+            setup: self.setup.clone(),
+        }
     }
 
     /// Get the currently active page.
     pub async fn active_page(&self) -> Result<Page> {
-        let pool = self.pool.lock().await;
-        Ok(pool.active_page().clone())
+        self.tabs().active_page().await
     }
 
     /// Create a new tab/page.
     pub async fn new_page(&self, url: &str) -> Result<Page> {
-        let page = self
-            .browser
-            .new_page(url)
-            .await
-            .context("Failed to create new page")?;
-        let mut pool = self.pool.lock().await;
-        pool.add_page(page.clone());
-        Ok(page)
+        self.tabs().new_page(url).await
+    }
+
+    /// Close a tab's Chrome target and drop it from the pool.
+    pub async fn close_page(&self, target_id: &str) -> Result<()> {
+        self.tabs().close_page(target_id).await
     }
 
     /// Close the browser.
@@ -104,3 +262,253 @@ impl BrowserSession {
         self.headless
     }
 }
+
+/// Tab operations shared by the tab tools and `run_script`, which needs them
+/// without holding the server's session lock for the whole script.
+#[derive(Clone)]
+pub struct Tabs {
+    browser: Arc<Browser>,
+    pool: Arc<Mutex<TabPool>>,
+    setup: TabSetup,
+}
+
+impl Tabs {
+    /// Request routes applied to every tab.
+    pub fn routes(&self) -> &Routes {
+        &self.setup.routes
+    }
+
+    /// Network and CPU throttling applied to every tab.
+    pub fn emulation(&self) -> &Emulation {
+        &self.setup.emulation
+    }
+
+    /// Snapshot ref numbering, kept per tab across the documents it shows.
+    pub fn ref_counter(&self) -> &RefCounter {
+        &self.setup.ref_counter
+    }
+
+    /// Get the currently active page.
+    pub async fn active_page(&self) -> Result<Page> {
+        let pool = self.pool.lock().await;
+        pool.active_page()
+            .cloned()
+            .context("No open tabs — open one with new_tab")
+    }
+
+    /// All tracked pages with the index of the active one.
+    pub async fn pages(&self) -> (Vec<Page>, usize) {
+        let pool = self.pool.lock().await;
+        let active = pool
+            .active_page()
+            .and_then(|active| {
+                pool.list_pages()
+                    .iter()
+                    .position(|p| p.target_id() == active.target_id())
+            })
+            .unwrap_or(0);
+        (pool.list_pages().to_vec(), active)
+    }
+
+    /// Look up a tracked tab without changing which one is active.
+    pub async fn get_page(&self, target_id: &str) -> Result<Page> {
+        let pool = self.pool.lock().await;
+        pool.get_page(target_id)
+            .cloned()
+            .with_context(|| format!("Tab '{}' is no longer open", target_id))
+    }
+
+    /// Create a new tab/page and make it active. The tab starts blank and only
+    /// navigates once its listeners are attached, so the first load is captured,
+    /// routed and throttled like any other.
+    pub async fn new_page(&self, url: &str) -> Result<Page> {
+        let page = self
+            .browser
+            .new_page("about:blank")
+            .await
+            .context("Failed to create new page")?;
+        // The target watcher may have adopted it already; listeners are attached once.
+        self.pool.lock().await.add_page(page.clone());
+        self.setup.attach(&page).await;
+        if url != "about:blank" {
+            page.goto(url)
+                .await
+                .with_context(|| format!("Failed to open {}", url))?;
+        }
+        Ok(page)
+    }
+
+    /// Make the tab with `target_id` active and bring it to the front.
+    pub async fn select_by_target_id(&self, target_id: &str) -> Result<Page> {
+        let page = self
+            .pool
+            .lock()
+            .await
+            .select_by_target_id(target_id)
+            .cloned()
+            .with_context(|| format!("Tab '{}' not found", target_id))?;
+        page.bring_to_front().await.context("Failed to activate tab")?;
+        Ok(page)
+    }
+
+    /// Make the tab at `index` (as listed by list_tabs) active and bring it to the front.
+    pub async fn select_index(&self, index: usize) -> Result<Page> {
+        let page = {
+            let mut pool = self.pool.lock().await;
+            let count = pool.page_count();
+            pool.select_page(index)
+                .cloned()
+                .with_context(|| format!("Tab index {} out of range ({} open)", index, count))?
+        };
+        page.bring_to_front().await.context("Failed to activate tab")?;
+        Ok(page)
+    }
+
+    /// Close a tab's Chrome target and drop it from the pool.
+    pub async fn close_page(&self, target_id: &str) -> Result<()> {
+        let page = {
+            let pool = self.pool.lock().await;
+            pool.get_page(target_id)
+                .cloned()
+                .with_context(|| format!("Tab '{}' not found", target_id))?
+        };
+        page.close().await.context("Failed to close tab")?;
+        self.pool.lock().await.remove_page(target_id);
+        self.setup.forget(target_id);
+        Ok(())
+    }
+}
+
+/// Everything a tab gets when it enters the pool, shared by the initial tab,
+/// `new_page` and the target watchers.
+#[derive(Clone)]
+struct TabSetup {
+    console_log: ConsoleLog,
+    network_log: NetworkLog,
+    routes: Routes,
+    emulation: Emulation,
+    ref_counter: RefCounter,
+    /// Per-tab latch so each tab is set up once, however many paths reach it.
+    attached: Arc<std::sync::Mutex<HashMap<String, Arc<OnceCell<()>>>>>,
+}
+
+impl TabSetup {
+    fn new(
+        console_log: ConsoleLog,
+        network_log: NetworkLog,
+        routes: Routes,
+        emulation: Emulation,
+    ) -> Self {
+        Self {
+            console_log,
+            network_log,
+            routes,
+            emulation,
+            ref_counter: RefCounter::new(),
+            attached: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Attach listeners to `page` unless already done; callers racing the first
+    /// attach wait until it has finished.
+    async fn attach(&self, page: &Page) {
+        let latch = self
+            .attached
+            .lock()
+            .unwrap()
+            .entry(page.target_id().as_ref().to_string())
+            .or_default()
+            .clone();
+        latch
+            .get_or_init(|| {
+                attach_listeners(
+                    page,
+                    &self.console_log,
+                    &self.network_log,
+                    &self.routes,
+                    &self.emulation,
+                )
+            })
+            .await;
+    }
+
+    /// Drop what was kept for a tab that has closed.
+    fn forget(&self, target_id: &str) {
+        self.attached.lock().unwrap().remove(target_id);
+        self.ref_counter.forget(target_id);
+    }
+}
+
+/// Adopt a target Chrome announced and attach its listeners, unless it was
+/// closed in the meantime.
+async fn adopt_target(
+    browser: &Weak<Browser>,
+    pool: &Mutex<TabPool>,
+    setup: &TabSetup,
+    target_id: TargetId,
+) {
+    let Some(page) = wait_for_page(browser, target_id).await else {
+        return;
+    };
+    let tracked = {
+        let mut pool = pool.lock().await;
+        if pool.adopt_expected(page.clone()) {
+            tracing::info!("Adopted tab {}", page.target_id().as_ref());
+        }
+        pool.contains(page.target_id().as_ref())
+    };
+    if tracked {
+        setup.attach(&page).await;
+    }
+}
+
+/// Let a tab paused on auto-attach start loading.
+fn resume(conn: &mut Connection<CdpEventMessage>, session_id: SessionId) -> Result<()> {
+    let params = RunIfWaitingForDebuggerParams::default();
+    conn.submit_command(
+        params.identifier(),
+        Some(session_id),
+        serde_json::to_value(params)?,
+    )?;
+    Ok(())
+}
+
+/// Wire up per-page CDP listeners (console capture, network capture once enabled,
+/// request routing) and throttling for a page entering the pool.
+async fn attach_listeners(
+    page: &Page,
+    console_log: &ConsoleLog,
+    network_log: &NetworkLog,
+    routes: &Routes,
+    emulation: &Emulation,
+) {
+    if let Err(e) = javascript::start_listening(page, console_log.clone()).await {
+        tracing::warn!("Failed to attach console listener: {}", e);
+    }
+    if network_log.is_enabled().await {
+        if let Err(e) = network::start_listening(page, network_log.clone()).await {
+            tracing::warn!("Failed to attach network listener: {}", e);
+        }
+    }
+    if let Err(e) = route::start_intercepting(page, routes.clone()).await {
+        tracing::warn!("Failed to attach request routing: {}", e);
+    }
+    if let Err(e) = emulation.attach(page).await {
+        tracing::warn!("Failed to apply network emulation: {}", e);
+    }
+}
+
+/// A freshly created target only gets a `Page` once chromiumoxide has attached a
+/// session to it, so poll briefly instead of failing on the first miss.
+async fn wait_for_page(browser: &Weak<Browser>, target_id: TargetId) -> Option<Page> {
+    for _ in 0..20 {
+        let browser = browser.upgrade()?;
+        if let Ok(page) = browser.get_page(target_id.clone()).await {
+            return Some(page);
+        }
+        drop(browser);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    tracing::warn!("Gave up adopting tab {}", target_id.as_ref());
+    None
+}
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use chromiumoxide::page::Page;
 
+use crate::interaction::{keyboard, mouse};
 use crate::selectors::SelectorType;
 
 #[derive(Debug, Clone)]
@@ -34,17 +35,65 @@ pub fn selector_to_js(selector: &str, selector_type: &SelectorType) -> Result<St
     })
 }
 
+/// Return the viewport coordinates of an element's center, scrolling it into
+/// view first when `scroll` is set.
+pub async fn element_center(
+    page: &Page,
+    selector: &str,
+    selector_type: &SelectorType,
+    scroll: bool,
+) -> Result<(f64, f64)> {
+    crate::interaction::wait::wait_for_selector(page, selector, selector_type, 5000).await?;
+
+    let selector_js = selector_to_js(selector, selector_type)?;
+    let js = format!(
+        r#"(() => {{
+            const el = {selector_js};
+            if (!el) return {{ error: 'Element not found: ' + {sel_str} }};
+            if ({scroll}) {{
+                el.scrollIntoView({{ block: 'center', inline: 'center', behavior: 'instant' }});
+            }}
+            const rect = el.getBoundingClientRect();
+            if (rect.width === 0 && rect.height === 0) {{
+                return {{ error: 'Element has zero size' }};
+            }}
+            return {{ x: rect.left + rect.width / 2, y: rect.top + rect.height / 2 }};
+        }})()"#,
+        selector_js = selector_js,
+        sel_str = serde_json::to_string(selector)?,
+        scroll = scroll
+    );
+
+    let result: serde_json::Value = page
+        .evaluate(js.as_str())
+        .await
+        .context("Failed to locate element")?
+        .into_value()
+        .context("Failed to parse element position")?;
+
+    if let Some(error) = result.get("error").and_then(|e| e.as_str()) {
+        anyhow::bail!("{}", error);
+    }
+
+    Ok((
+        result["x"].as_f64().unwrap_or(0.0),
+        result["y"].as_f64().unwrap_or(0.0),
+    ))
+}
+
 /// Hybrid click strategy:
 /// 1. Resolve selector to element
 /// 2. Scroll into view
 /// 3. Get bounding box
 /// 4. Check visibility and obstruction
-/// 5. Try mouse events if visible, fall back to JS click
+/// 5. Dispatch real CDP mouse events at the element's center if visible, fall back to JS click
 pub async fn hybrid_click(
     page: &Page,
     selector: &str,
     selector_type: &SelectorType,
     button: &str,
+    click_count: u32,
+    modifiers: &[String],
 ) -> Result<ClickResult> {
     // Auto-wait for element to appear
     crate::interaction::wait::wait_for_selector(page, selector, selector_type, 5000).await?;
@@ -103,71 +152,62 @@ pub async fn hybrid_click(
 
     let visible = check_result["visible"].as_bool().unwrap_or(false);
     let unobscured = check_result["unobscured"].as_bool().unwrap_or(false);
-    let _x = check_result["x"].as_f64().unwrap_or(0.0);
-    let _y = check_result["y"].as_f64().unwrap_or(0.0);
+    let x = check_result["x"].as_f64().unwrap_or(0.0);
+    let y = check_result["y"].as_f64().unwrap_or(0.0);
 
     // Wait a moment for scroll/layout to settle
     tokio::time::sleep(std::time::Duration::from_millis(50)).await;
 
     if visible && unobscured {
-        // Step 6: Try CDP mouse events
-        let _mouse_button = match button {
-            "right" => "right",
-            "middle" => "middle",
-            _ => "left",
-        };
-
-        let click_js = format!(
-            r#"(() => {{
-                // Use CDP-style mouse events via JS as a proxy
-                const el = {selector_js};
-                const rect = el.getBoundingClientRect();
-                const x = rect.left + rect.width / 2;
-                const y = rect.top + rect.height / 2;
-
-                // Dispatch mouse events in sequence
-                const opts = {{ bubbles: true, cancelable: true, clientX: x, clientY: y, button: {button_num} }};
-                el.dispatchEvent(new MouseEvent('mousemove', opts));
-                el.dispatchEvent(new MouseEvent('mousedown', opts));
-                el.dispatchEvent(new MouseEvent('mouseup', opts));
-                el.dispatchEvent(new MouseEvent('click', opts));
-                return true;
-            }})()"#,
-            selector_js = selector_js,
-            button_num = match button {
-                "right" => 2,
-                "middle" => 1,
-                _ => 0,
+        // Step 5: Trusted CDP mouse input at the element's center
+        let flags = keyboard::modifier_flags(modifiers);
+        match mouse::click_at(
+            page,
+            x,
+            y,
+            mouse::parse_button(button),
+            click_count as i64,
+            flags,
+        )
+        .await
+        {
+            Ok(()) => {
+                return Ok(ClickResult {
+                    success: true,
+                    method_used: "cdp_mouse".to_string(),
+                })
             }
-        );
-
-        page.evaluate(click_js.as_str())
-            .await
-            .context("Failed to dispatch mouse events")?;
-
-        Ok(ClickResult {
-            success: true,
-            method_used: "mouse_event".to_string(),
-        })
-    } else {
-        // Step 7: Fall back to JS click
-        let js_click = format!(
-            r#"(() => {{
-                const el = {selector_js};
-                if (!el) throw new Error('Element not found');
-                el.click();
+            Err(e) => tracing::debug!("CDP mouse click failed, falling back to JS: {:#}", e),
+        }
+    }
+
+    // Step 6: Fall back to JS click (synthetic, for hidden/obscured elements)
+    let js_click = format!(
+        r#"(() => {{
+            const el = {selector_js};
+            if (!el) throw new Error('Element not found');
+            const button = {button_json};
+            if (button === 'right') {{
+                el.dispatchEvent(new MouseEvent('contextmenu', {{ bubbles: true, cancelable: true, button: 2 }}));
                 return true;
-            }})()"#,
-            selector_js = selector_js
-        );
+            }}
+            for (let i = 0; i < {click_count}; i++) el.click();
+            if ({click_count} >= 2) {{
+                el.dispatchEvent(new MouseEvent('dblclick', {{ bubbles: true, cancelable: true, detail: 2 }}));
+            }}
+            return true;
+        }})()"#,
+        selector_js = selector_js,
+        button_json = serde_json::to_string(button)?,
+        click_count = click_count.max(1)
+    );
 
-        page.evaluate(js_click.as_str())
-            .await
-            .context("Failed to JS click")?;
+    page.evaluate(js_click.as_str())
+        .await
+        .context("Failed to JS click")?;
 
-        Ok(ClickResult {
-            success: true,
-            method_used: "js_click".to_string(),
-        })
-    }
+    Ok(ClickResult {
+        success: true,
+        method_used: "js_click".to_string(),
+    })
 }
@@ -1,15 +1,367 @@
 use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::input::{
+    DispatchKeyEventParams, DispatchKeyEventType, InsertTextParams,
+};
 use chromiumoxide::page::Page;
 
 use crate::selectors::SelectorType;
 
-/// Type text into an element by focusing it and dispatching key events.
+/// A key on the US keyboard layout, as `Input.dispatchKeyEvent` expects it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyDefinition {
+    /// DOM `KeyboardEvent.key` value ("a", "Enter", "F5").
+    pub key: String,
+    /// DOM `KeyboardEvent.code` value ("KeyA", "Enter", "Numpad5").
+    pub code: String,
+    /// Windows virtual key code (`keyCode`/`which`).
+    pub key_code: i64,
+    /// Text the key inserts, if any.
+    pub text: Option<String>,
+    /// 0 = standard, 1 = left, 2 = right, 3 = numpad.
+    pub location: i64,
+}
+
+impl KeyDefinition {
+    fn new(key: &str, code: &str, key_code: i64, text: Option<&str>, location: i64) -> Self {
+        Self {
+            key: key.to_string(),
+            code: code.to_string(),
+            key_code,
+            text: text.map(str::to_string),
+            location,
+        }
+    }
+}
+
+/// Non-character keys: (key, code, keyCode, text, location).
+/// When two entries share a `key` the first one wins for lookup by key;
+/// the others stay reachable by `code` (e.g. "NumpadEnter", "ShiftRight").
+const SPECIAL_KEYS: &[(&str, &str, i64, Option<&str>, i64)] = &[
+    ("Enter", "Enter", 13, Some("\r"), 0),
+    ("Tab", "Tab", 9, None, 0),
+    ("Escape", "Escape", 27, None, 0),
+    ("Backspace", "Backspace", 8, None, 0),
+    ("Delete", "Delete", 46, None, 0),
+    ("Insert", "Insert", 45, None, 0),
+    ("Home", "Home", 36, None, 0),
+    ("End", "End", 35, None, 0),
+    ("PageUp", "PageUp", 33, None, 0),
+    ("PageDown", "PageDown", 34, None, 0),
+    ("ArrowUp", "ArrowUp", 38, None, 0),
+    ("ArrowDown", "ArrowDown", 40, None, 0),
+    ("ArrowLeft", "ArrowLeft", 37, None, 0),
+    ("ArrowRight", "ArrowRight", 39, None, 0),
+    (" ", "Space", 32, Some(" "), 0),
+    ("CapsLock", "CapsLock", 20, None, 0),
+    ("NumLock", "NumLock", 144, None, 0),
+    ("ScrollLock", "ScrollLock", 145, None, 0),
+    ("Pause", "Pause", 19, None, 0),
+    ("PrintScreen", "PrintScreen", 44, None, 0),
+    ("ContextMenu", "ContextMenu", 93, None, 0),
+    // Modifiers
+    ("Shift", "ShiftLeft", 16, None, 1),
+    ("Shift", "ShiftRight", 16, None, 2),
+    ("Control", "ControlLeft", 17, None, 1),
+    ("Control", "ControlRight", 17, None, 2),
+    ("Alt", "AltLeft", 18, None, 1),
+    ("Alt", "AltRight", 18, None, 2),
+    ("Meta", "MetaLeft", 91, None, 1),
+    ("Meta", "MetaRight", 92, None, 2),
+    // Numpad
+    ("0", "Numpad0", 96, Some("0"), 3),
+    ("1", "Numpad1", 97, Some("1"), 3),
+    ("2", "Numpad2", 98, Some("2"), 3),
+    ("3", "Numpad3", 99, Some("3"), 3),
+    ("4", "Numpad4", 100, Some("4"), 3),
+    ("5", "Numpad5", 101, Some("5"), 3),
+    ("6", "Numpad6", 102, Some("6"), 3),
+    ("7", "Numpad7", 103, Some("7"), 3),
+    ("8", "Numpad8", 104, Some("8"), 3),
+    ("9", "Numpad9", 105, Some("9"), 3),
+    ("*", "NumpadMultiply", 106, Some("*"), 3),
+    ("+", "NumpadAdd", 107, Some("+"), 3),
+    ("-", "NumpadSubtract", 109, Some("-"), 3),
+    (".", "NumpadDecimal", 110, Some("."), 3),
+    ("/", "NumpadDivide", 111, Some("/"), 3),
+    ("Enter", "NumpadEnter", 13, Some("\r"), 3),
+];
+
+/// Punctuation keys: (unshifted, shifted, code, keyCode).
+const PUNCTUATION_KEYS: &[(char, char, &str, i64)] = &[
+    ('`', '~', "Backquote", 192),
+    ('-', '_', "Minus", 189),
+    ('=', '+', "Equal", 187),
+    ('[', '{', "BracketLeft", 219),
+    (']', '}', "BracketRight", 221),
+    ('\\', '|', "Backslash", 220),
+    (';', ':', "Semicolon", 186),
+    ('\'', '"', "Quote", 222),
+    (',', '<', "Comma", 188),
+    ('.', '>', "Period", 190),
+    ('/', '?', "Slash", 191),
+];
+
+/// Characters produced by Shift + Digit0..Digit9.
+const SHIFTED_DIGITS: [char; 10] = [')', '!', '@', '#', '$', '%', '^', '&', '*', '('];
+
+/// Map common shorthand to the DOM key name.
+fn canonical_key_name(name: &str) -> &str {
+    match name {
+        "Space" | "Spacebar" => " ",
+        "Esc" => "Escape",
+        "Return" => "Enter",
+        "Del" => "Delete",
+        "Up" => "ArrowUp",
+        "Down" => "ArrowDown",
+        "Left" => "ArrowLeft",
+        "Right" => "ArrowRight",
+        "Ctrl" | "ctrl" | "control" => "Control",
+        "Cmd" | "Command" | "cmd" | "command" | "meta" | "Win" => "Meta",
+        "Option" | "alt" => "Alt",
+        "shift" => "Shift",
+        other => other,
+    }
+}
+
+/// Whether typing `c` on the US layout needs Shift held (uppercase letters and
+/// the shifted symbols on digit and punctuation keys).
+fn needs_shift(c: char) -> bool {
+    c.is_ascii_uppercase()
+        || SHIFTED_DIGITS.contains(&c)
+        || PUNCTUATION_KEYS.iter().any(|(_, shifted, ..)| *shifted == c)
+}
+
+/// Definition for a single printable character on the US layout.
+fn char_definition(c: char) -> Option<KeyDefinition> {
+    let text = c.to_string();
+    if c.is_ascii_alphabetic() {
+        let upper = c.to_ascii_uppercase();
+        return Some(KeyDefinition {
+            key: text.clone(),
+            code: format!("Key{}", upper),
+            key_code: upper as i64,
+            text: Some(text),
+            location: 0,
+        });
+    }
+    if let Some(d) = c.to_digit(10) {
+        return Some(KeyDefinition {
+            key: text.clone(),
+            code: format!("Digit{}", d),
+            key_code: 48 + d as i64,
+            text: Some(text),
+            location: 0,
+        });
+    }
+    if let Some(d) = SHIFTED_DIGITS.iter().position(|&s| s == c) {
+        return Some(KeyDefinition {
+            key: text.clone(),
+            code: format!("Digit{}", d),
+            key_code: 48 + d as i64,
+            text: Some(text),
+            location: 0,
+        });
+    }
+    PUNCTUATION_KEYS
+        .iter()
+        .find(|(plain, shifted, _, _)| *plain == c || *shifted == c)
+        .map(|(_, _, code, key_code)| KeyDefinition {
+            key: text.clone(),
+            code: code.to_string(),
+            key_code: *key_code,
+            text: Some(text),
+            location: 0,
+        })
+}
+
+/// Look up a key by DOM key name ("Enter", "a", "F5", "ArrowDown", "Shift") or by
+/// DOM code ("KeyA", "Digit1", "Numpad5", "NumpadEnter", "ControlRight").
+pub fn key_definition(name: &str) -> Option<KeyDefinition> {
+    let name = canonical_key_name(name);
+
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c == ' ' {
+            return Some(KeyDefinition::new(" ", "Space", 32, Some(" "), 0));
+        }
+        return char_definition(c);
+    }
+
+    // F1..F24
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<i64>().ok()) {
+        if (1..=24).contains(&n) {
+            return Some(KeyDefinition::new(name, name, 111 + n, None, 0));
+        }
+    }
+
+    if let Some(&(key, code, key_code, text, location)) =
+        SPECIAL_KEYS.iter().find(|(key, ..)| *key == name)
+    {
+        return Some(KeyDefinition::new(key, code, key_code, text, location));
+    }
+
+    // Lookup by code
+    if let Some(&(key, code, key_code, text, location)) =
+        SPECIAL_KEYS.iter().find(|(_, code, ..)| *code == name)
+    {
+        return Some(KeyDefinition::new(key, code, key_code, text, location));
+    }
+    if let Some(letter) = name.strip_prefix("Key") {
+        let mut chars = letter.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            if c.is_ascii_uppercase() {
+                return char_definition(c.to_ascii_lowercase());
+            }
+        }
+    }
+    if let Some(digit) = name.strip_prefix("Digit") {
+        let mut chars = digit.chars();
+        if let (Some(c @ '0'..='9'), None) = (chars.next(), chars.next()) {
+            return char_definition(c);
+        }
+    }
+    PUNCTUATION_KEYS
+        .iter()
+        .find(|(_, _, code, _)| *code == name)
+        .and_then(|(plain, ..)| char_definition(*plain))
+}
+
+/// The key produced by holding Shift on top of `def` (letters uppercase, digits and
+/// punctuation switch to their shifted symbol).
+fn shifted(def: &KeyDefinition) -> KeyDefinition {
+    let mut chars = def.key.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return def.clone();
+    };
+    if def.location == 3 {
+        return def.clone();
+    }
+    let shifted_char = if c.is_ascii_lowercase() {
+        Some(c.to_ascii_uppercase())
+    } else if let Some(d) = c.to_digit(10) {
+        Some(SHIFTED_DIGITS[d as usize])
+    } else {
+        PUNCTUATION_KEYS
+            .iter()
+            .find(|(plain, ..)| *plain == c)
+            .map(|(_, shifted, ..)| *shifted)
+    };
+    match shifted_char {
+        Some(s) => KeyDefinition {
+            key: s.to_string(),
+            text: Some(s.to_string()),
+            ..def.clone()
+        },
+        None => def.clone(),
+    }
+}
+
+/// CDP modifier bitmask for a list of modifier names (Alt=1, Ctrl=2, Meta=4, Shift=8).
+pub fn modifier_flags(modifiers: &[String]) -> i64 {
+    modifiers.iter().fold(0, |flags, m| {
+        flags
+            | match m.to_ascii_lowercase().as_str() {
+                "alt" | "option" => 1,
+                "ctrl" | "control" => 2,
+                "meta" | "command" | "cmd" | "win" => 4,
+                "shift" => 8,
+                _ => 0,
+            }
+    })
+}
+
+/// Split a combo like "Control+Shift+K" into its key and modifier names.
+/// A lone "+" (or a trailing "++") is treated as the plus key.
+fn split_combo(key: &str) -> (&str, Vec<String>) {
+    if key.len() <= 1 || !key.contains('+') {
+        return (key, Vec::new());
+    }
+    let (mods, last) = if let Some(prefix) = key.strip_suffix("++") {
+        (prefix, "+")
+    } else {
+        match key.rsplit_once('+') {
+            Some((mods, last)) => (mods, last),
+            None => return (key, Vec::new()),
+        }
+    };
+    let modifiers = mods
+        .split('+')
+        .filter(|m| !m.is_empty())
+        .map(str::to_string)
+        .collect();
+    (last, modifiers)
+}
+
+async fn dispatch_key(
+    page: &Page,
+    event_type: DispatchKeyEventType,
+    def: &KeyDefinition,
+    modifiers: i64,
+    text: Option<&str>,
+) -> Result<()> {
+    let mut params = DispatchKeyEventParams::new(event_type);
+    params.key = Some(def.key.clone());
+    params.code = Some(def.code.clone());
+    params.windows_virtual_key_code = Some(def.key_code);
+    params.native_virtual_key_code = Some(def.key_code);
+    params.modifiers = Some(modifiers);
+    if def.location != 0 {
+        params.location = Some(def.location);
+    }
+    if def.location == 3 {
+        params.is_keypad = Some(true);
+    }
+    if let Some(text) = text {
+        params.text = Some(text.to_string());
+        params.unmodified_text = Some(text.to_string());
+    }
+    page.execute(params)
+        .await
+        .with_context(|| format!("Failed to dispatch key event for '{}'", def.key))?;
+    Ok(())
+}
+
+/// Press and release one key with trusted `Input.dispatchKeyEvent` events.
+async fn press_definition(page: &Page, def: &KeyDefinition, modifiers: i64) -> Result<()> {
+    // Ctrl/Alt/Meta chords are shortcuts, not text input
+    let text = if modifiers & (1 | 2 | 4) == 0 {
+        def.text.as_deref()
+    } else {
+        None
+    };
+    let down_type = if text.is_some() {
+        DispatchKeyEventType::KeyDown
+    } else {
+        DispatchKeyEventType::RawKeyDown
+    };
+    dispatch_key(page, down_type, def, modifiers, text).await?;
+    dispatch_key(page, DispatchKeyEventType::KeyUp, def, modifiers, None).await
+}
+
+/// Press a shifted character the way a typist does: Shift goes down first and
+/// comes back up after the key, so pages see the Shift key events too.
+async fn press_shifted(page: &Page, def: &KeyDefinition) -> Result<()> {
+    let shift = key_definition("Shift").expect("modifier keys are in the table");
+    dispatch_key(page, DispatchKeyEventType::RawKeyDown, &shift, 8, None).await?;
+    let result = press_definition(page, def, 8).await;
+    dispatch_key(page, DispatchKeyEventType::KeyUp, &shift, 0, None).await?;
+    result
+}
+
+/// Type text into an element: focus it, then insert the text at the caret.
+///
+/// By default the text goes in with a single `Input.insertText` (fast, fires
+/// `beforeinput`/`input`). With `per_character`, every character is a full
+/// keydown/keypress/input/keyup sequence, for autocompletes, input masks and rich
+/// editors that listen to key events.
 pub async fn type_text(
     page: &Page,
     selector: &str,
     selector_type: &SelectorType,
     text: &str,
     clear_first: bool,
+    per_character: bool,
+    delay_ms: u64,
 ) -> Result<()> {
     // Auto-wait for element to appear
     crate::interaction::wait::wait_for_selector(page, selector, selector_type, 5000).await?;
@@ -22,10 +374,34 @@ pub async fn type_text(
             if (!el) throw new Error('Element not found: ' + {sel_str});
             el.scrollIntoView({{ block: 'center', behavior: 'instant' }});
             el.focus();
+            const isField = el.tagName === 'INPUT' || el.tagName === 'TEXTAREA';
             if ({clear}) {{
-                el.value = '';
+                if (isField) {{
+                    const proto = el.tagName === 'TEXTAREA'
+                        ? window.HTMLTextAreaElement.prototype
+                        : window.HTMLInputElement.prototype;
+                    const nativeSetter = Object.getOwnPropertyDescriptor(proto, 'value')?.set;
+                    if (nativeSetter) nativeSetter.call(el, '');
+                    else el.value = '';
+                }} else if (el.isContentEditable) {{
+                    el.textContent = '';
+                }}
                 el.dispatchEvent(new Event('input', {{ bubbles: true }}));
             }}
+            // Put the caret at the end so typed text appends
+            if (isField) {{
+                try {{
+                    const end = el.value.length;
+                    el.setSelectionRange(end, end);
+                }} catch (e) {{}}
+            }} else if (el.isContentEditable) {{
+                const range = document.createRange();
+                range.selectNodeContents(el);
+                range.collapse(false);
+                const sel = window.getSelection();
+                sel.removeAllRanges();
+                sel.addRange(range);
+            }}
             return true;
         }})()"#,
         selector_js = selector_js,
@@ -37,99 +413,176 @@ pub async fn type_text(
         .await
         .context("Failed to focus element")?;
 
-    // Type each character
-    let type_js = format!(
-        r#"(() => {{
-            const el = {selector_js};
-            const text = {text};
-            // Set value directly and dispatch events
-            if (el.tagName === 'INPUT' || el.tagName === 'TEXTAREA') {{
-                const nativeInputValueSetter = Object.getOwnPropertyDescriptor(
-                    window.HTMLInputElement.prototype, 'value'
-                )?.set || Object.getOwnPropertyDescriptor(
-                    window.HTMLTextAreaElement.prototype, 'value'
-                )?.set;
-                if (nativeInputValueSetter) {{
-                    nativeInputValueSetter.call(el, el.value + text);
-                }} else {{
-                    el.value += text;
-                }}
-                el.dispatchEvent(new Event('input', {{ bubbles: true }}));
-                el.dispatchEvent(new Event('change', {{ bubbles: true }}));
-            }} else {{
-                // For contenteditable elements
-                document.execCommand('insertText', false, text);
-            }}
-            return true;
-        }})()"#,
-        selector_js = selector_js,
-        text = serde_json::to_string(text)?
-    );
+    if !per_character {
+        page.execute(InsertTextParams::new(text))
+            .await
+            .context("Failed to type text")?;
+        return Ok(());
+    }
 
-    page.evaluate(type_js.as_str())
-        .await
-        .context("Failed to type text")?;
+    for c in text.chars() {
+        let def = match c {
+            '\n' | '\r' => key_definition("Enter"),
+            '\t' => key_definition("Tab"),
+            _ => char_definition(c),
+        };
+        match def {
+            Some(def) if needs_shift(c) => press_shifted(page, &def).await?,
+            Some(def) => press_definition(page, &def, 0).await?,
+            // Characters outside the US layout (accents, emoji, CJK) have no key
+            None => {
+                page.execute(InsertTextParams::new(c.to_string()))
+                    .await
+                    .context("Failed to type text")?;
+            }
+        }
+        if delay_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+        }
+    }
 
     Ok(())
 }
 
-/// Press a key (Enter, Tab, ArrowDown, etc.).
+/// Press a key (Enter, Tab, ArrowDown, F5, "Control+a", etc.) with trusted key events.
+///
+/// Modifiers are held down around the key press, so pages observe real chords.
 pub async fn press_key(page: &Page, key: &str, modifiers: &[String]) -> Result<()> {
-    let key_code = key_to_code(key);
-    let js = format!(
-        r#"(() => {{
-            const el = document.activeElement || document.body;
-            const opts = {{
-                key: {key},
-                code: {code},
-                keyCode: {key_code},
-                which: {key_code},
-                bubbles: true,
-                cancelable: true,
-                ctrlKey: {ctrl},
-                shiftKey: {shift},
-                altKey: {alt},
-                metaKey: {meta}
-            }};
-            el.dispatchEvent(new KeyboardEvent('keydown', opts));
-            el.dispatchEvent(new KeyboardEvent('keypress', opts));
-            el.dispatchEvent(new KeyboardEvent('keyup', opts));
-            return true;
-        }})()"#,
-        key = serde_json::to_string(key)?,
-        code = serde_json::to_string(&key_code.0)?,
-        key_code = key_code.1,
-        ctrl = modifiers.iter().any(|m| m == "ctrl" || m == "control"),
-        shift = modifiers.iter().any(|m| m == "shift"),
-        alt = modifiers.iter().any(|m| m == "alt"),
-        meta = modifiers.iter().any(|m| m == "meta" || m == "command"),
-    );
+    let (key, mut all_modifiers) = split_combo(key);
+    all_modifiers.extend(modifiers.iter().cloned());
 
-    page.evaluate(js.as_str())
-        .await
-        .context("Failed to press key")?;
-    Ok(())
+    let def = key_definition(key).with_context(|| format!("Unknown key: '{}'", key))?;
+    let flags = modifier_flags(&all_modifiers);
+
+    let mut held = Vec::new();
+    let mut held_flags = 0;
+    for (name, flag) in [("Control", 2), ("Alt", 1), ("Meta", 4), ("Shift", 8)] {
+        if flags & flag != 0 {
+            let modifier_def = key_definition(name).expect("modifier keys are in the table");
+            held_flags |= flag;
+            dispatch_key(
+                page,
+                DispatchKeyEventType::RawKeyDown,
+                &modifier_def,
+                held_flags,
+                None,
+            )
+            .await?;
+            held.push((modifier_def, flag));
+        }
+    }
+
+    let def = if flags & 8 != 0 { shifted(&def) } else { def };
+    let result = press_definition(page, &def, flags).await;
+
+    // Always release modifiers, even if the key itself failed
+    for (modifier_def, flag) in held.iter().rev() {
+        held_flags &= !flag;
+        dispatch_key(
+            page,
+            DispatchKeyEventType::KeyUp,
+            modifier_def,
+            held_flags,
+            None,
+        )
+        .await?;
+    }
+
+    result
 }
 
-fn key_to_code(key: &str) -> (String, u32) {
-    match key {
-        "Enter" => ("Enter".into(), 13),
-        "Tab" => ("Tab".into(), 9),
-        "Escape" => ("Escape".into(), 27),
-        "Backspace" => ("Backspace".into(), 8),
-        "Delete" => ("Delete".into(), 46),
-        "ArrowUp" => ("ArrowUp".into(), 38),
-        "ArrowDown" => ("ArrowDown".into(), 40),
-        "ArrowLeft" => ("ArrowLeft".into(), 37),
-        "ArrowRight" => ("ArrowRight".into(), 39),
-        "Home" => ("Home".into(), 36),
-        "End" => ("End".into(), 35),
-        "PageUp" => ("PageUp".into(), 33),
-        "PageDown" => ("PageDown".into(), 34),
-        "Space" | " " => ("Space".into(), 32),
-        _ => (
-            format!("Key{}", key.to_uppercase()),
-            key.chars().next().map(|c| c as u32).unwrap_or(0),
-        ),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_definition_named_keys() {
+        let enter = key_definition("Enter").unwrap();
+        assert_eq!((enter.code.as_str(), enter.key_code), ("Enter", 13));
+        assert_eq!(enter.text.as_deref(), Some("\r"));
+
+        let tab = key_definition("Tab").unwrap();
+        assert_eq!(tab.key_code, 9);
+        assert!(tab.text.is_none());
+
+        assert_eq!(key_definition("Space").unwrap().code, "Space");
+        assert_eq!(key_definition("Esc").unwrap().key, "Escape");
+    }
+
+    #[test]
+    fn test_key_definition_function_and_numpad_keys() {
+        let f5 = key_definition("F5").unwrap();
+        assert_eq!((f5.code.as_str(), f5.key_code), ("F5", 116));
+        assert_eq!(key_definition("F24").unwrap().key_code, 135);
+        assert!(key_definition("F25").is_none());
+
+        let num5 = key_definition("Numpad5").unwrap();
+        assert_eq!((num5.key.as_str(), num5.key_code, num5.location), ("5", 101, 3));
+        let num_enter = key_definition("NumpadEnter").unwrap();
+        assert_eq!((num_enter.key.as_str(), num_enter.location), ("Enter", 3));
+    }
+
+    #[test]
+    fn test_key_definition_characters() {
+        let a = key_definition("a").unwrap();
+        assert_eq!((a.code.as_str(), a.key_code), ("KeyA", 65));
+        let upper = key_definition("A").unwrap();
+        assert_eq!((upper.code.as_str(), upper.text.as_deref()), ("KeyA", Some("A")));
+        let bang = key_definition("!").unwrap();
+        assert_eq!((bang.code.as_str(), bang.key_code), ("Digit1", 49));
+        let question = key_definition("?").unwrap();
+        assert_eq!((question.code.as_str(), question.key_code), ("Slash", 191));
+        assert_eq!(key_definition("KeyZ").unwrap().key, "z");
+        assert_eq!(key_definition("Quote").unwrap().key, "'");
+        assert!(key_definition("é").is_none());
+    }
+
+    #[test]
+    fn test_modifier_keys_have_locations() {
+        let shift = key_definition("Shift").unwrap();
+        assert_eq!((shift.code.as_str(), shift.location), ("ShiftLeft", 1));
+        let right_ctrl = key_definition("ControlRight").unwrap();
+        assert_eq!((right_ctrl.key.as_str(), right_ctrl.location), ("Control", 2));
+    }
+
+    #[test]
+    fn test_shifted_definitions() {
+        assert_eq!(shifted(&key_definition("a").unwrap()).key, "A");
+        assert_eq!(shifted(&key_definition("2").unwrap()).key, "@");
+        assert_eq!(shifted(&key_definition("/").unwrap()).key, "?");
+        assert_eq!(shifted(&key_definition("Tab").unwrap()).key, "Tab");
+    }
+
+    #[test]
+    fn test_modifier_flags() {
+        let mods = vec!["ctrl".to_string(), "Shift".to_string()];
+        assert_eq!(modifier_flags(&mods), 2 | 8);
+        let mods = vec!["command".to_string(), "alt".to_string()];
+        assert_eq!(modifier_flags(&mods), 4 | 1);
+        assert_eq!(modifier_flags(&[]), 0);
+        let mods = ["option", "CTRL", "SHIFT", "META"].map(String::from);
+        assert_eq!(modifier_flags(&mods), 1 | 2 | 8 | 4);
+        assert_eq!(modifier_flags(&["ALT".to_string()]), 1);
+    }
+
+    #[test]
+    fn test_needs_shift() {
+        assert!(needs_shift('A'));
+        assert!(needs_shift('@'));
+        assert!(needs_shift('?'));
+        assert!(!needs_shift('a'));
+        assert!(!needs_shift('2'));
+        assert!(!needs_shift('/'));
+    }
+
+    #[test]
+    fn test_split_combo() {
+        assert_eq!(split_combo("Enter"), ("Enter", vec![]));
+        assert_eq!(split_combo("+"), ("+", vec![]));
+        assert_eq!(
+            split_combo("Control+Shift+K"),
+            ("K", vec!["Control".to_string(), "Shift".to_string()])
+        );
+        assert_eq!(split_combo("Control++"), ("+", vec!["Control".to_string()]));
     }
 }
@@ -1,4 +1,5 @@
 pub mod click;
 pub mod keyboard;
+pub mod mouse;
 pub mod scroll;
 pub mod wait;
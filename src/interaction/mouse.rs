@@ -0,0 +1,287 @@
+use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::input::{
+    DispatchDragEventParams, DispatchDragEventType, DispatchMouseEventParams,
+    DispatchMouseEventType, DragData, EventDragIntercepted, MouseButton, SetInterceptDragsParams,
+};
+use chromiumoxide::page::Page;
+use futures::StreamExt;
+use std::time::Duration;
+
+/// Map a button name (left, right, middle, back, forward) to its CDP value.
+pub fn parse_button(name: &str) -> MouseButton {
+    match name {
+        "right" => MouseButton::Right,
+        "middle" => MouseButton::Middle,
+        "back" => MouseButton::Back,
+        "forward" => MouseButton::Forward,
+        _ => MouseButton::Left,
+    }
+}
+
+/// The `buttons` bitmask reported while `button` is held down.
+pub fn buttons_mask(button: &MouseButton) -> i64 {
+    match button {
+        MouseButton::Left => 1,
+        MouseButton::Right => 2,
+        MouseButton::Middle => 4,
+        MouseButton::Back => 8,
+        MouseButton::Forward => 16,
+        MouseButton::None => 0,
+    }
+}
+
+fn mouse_event(
+    event_type: DispatchMouseEventType,
+    x: f64,
+    y: f64,
+    modifiers: i64,
+) -> DispatchMouseEventParams {
+    let mut params = DispatchMouseEventParams::new(event_type, x, y);
+    params.modifiers = Some(modifiers);
+    params
+}
+
+/// The button a move reports while `buttons` are held: the first one pressed
+/// in left, right, middle, back, forward order.
+fn held_button(buttons: i64) -> MouseButton {
+    [
+        MouseButton::Left,
+        MouseButton::Right,
+        MouseButton::Middle,
+        MouseButton::Back,
+        MouseButton::Forward,
+    ]
+    .into_iter()
+    .find(|button| buttons & buttons_mask(button) != 0)
+    .unwrap_or(MouseButton::None)
+}
+
+/// Move the real (trusted) mouse pointer to viewport coordinates. `buttons` is
+/// the mask of buttons currently held, so pages see a drag rather than a hover.
+pub async fn move_to(page: &Page, x: f64, y: f64, buttons: i64, modifiers: i64) -> Result<()> {
+    let mut params = mouse_event(DispatchMouseEventType::MouseMoved, x, y, modifiers);
+    if buttons != 0 {
+        params.button = Some(held_button(buttons));
+        params.buttons = Some(buttons);
+    }
+    page.execute(params)
+        .await
+        .context("Failed to dispatch mouseMoved")?;
+    Ok(())
+}
+
+/// Press (`down = true`) or release a mouse button at viewport coordinates.
+/// `held` is the mask of buttons already down; returns the mask afterwards.
+pub async fn button_at(
+    page: &Page,
+    x: f64,
+    y: f64,
+    button: MouseButton,
+    down: bool,
+    held: i64,
+    modifiers: i64,
+) -> Result<i64> {
+    let (event_type, buttons) = if down {
+        (DispatchMouseEventType::MousePressed, held | buttons_mask(&button))
+    } else {
+        (DispatchMouseEventType::MouseReleased, held & !buttons_mask(&button))
+    };
+    let mut params = mouse_event(event_type, x, y, modifiers);
+    params.button = Some(button);
+    params.buttons = Some(buttons);
+    params.click_count = Some(1);
+    page.execute(params)
+        .await
+        .context("Failed to dispatch mouse button event")?;
+    Ok(buttons)
+}
+
+/// Scroll with the mouse wheel at viewport coordinates (positive `delta_y` scrolls down).
+pub async fn wheel(
+    page: &Page,
+    x: f64,
+    y: f64,
+    delta_x: f64,
+    delta_y: f64,
+    modifiers: i64,
+) -> Result<()> {
+    let mut params = mouse_event(DispatchMouseEventType::MouseWheel, x, y, modifiers);
+    params.delta_x = Some(delta_x);
+    params.delta_y = Some(delta_y);
+    page.execute(params)
+        .await
+        .context("Failed to dispatch mouseWheel")?;
+    Ok(())
+}
+
+/// Click at viewport coordinates with `Input.dispatchMouseEvent`.
+///
+/// For `click_count > 1` each press/release pair carries an increasing click count,
+/// which is what Chrome needs to fire `dblclick` (and triple-click selection).
+pub async fn click_at(
+    page: &Page,
+    x: f64,
+    y: f64,
+    button: MouseButton,
+    click_count: i64,
+    modifiers: i64,
+) -> Result<()> {
+    move_to(page, x, y, 0, modifiers).await?;
+
+    for count in 1..=click_count.max(1) {
+        let mut press = mouse_event(DispatchMouseEventType::MousePressed, x, y, modifiers);
+        press.button = Some(button.clone());
+        press.buttons = Some(buttons_mask(&button));
+        press.click_count = Some(count);
+        page.execute(press)
+            .await
+            .context("Failed to dispatch mousePressed")?;
+
+        let mut release = mouse_event(DispatchMouseEventType::MouseReleased, x, y, modifiers);
+        release.button = Some(button.clone());
+        release.buttons = Some(0);
+        release.click_count = Some(count);
+        page.execute(release)
+            .await
+            .context("Failed to dispatch mouseReleased")?;
+    }
+
+    Ok(())
+}
+
+/// Points along a straight line from `from` to `to`, excluding the start and
+/// ending exactly on `to`.
+fn path_points(from: (f64, f64), to: (f64, f64), steps: u32) -> Vec<(f64, f64)> {
+    let steps = steps.max(1);
+    (1..=steps)
+        .map(|i| {
+            let t = i as f64 / steps as f64;
+            (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+        })
+        .collect()
+}
+
+/// How the drag was delivered to the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragMethod {
+    /// Plain pointer events (sliders, sortable lists built on mousemove).
+    Mouse,
+    /// Chrome started a native HTML5 drag; `dragenter`/`dragover`/`drop` were
+    /// delivered with `Input.dispatchDragEvent`.
+    Html5,
+}
+
+/// Drag with the left button from one viewport point to another.
+///
+/// The pointer moves in `steps` intermediate `mouseMoved` events. Drags are
+/// intercepted via `Input.setInterceptDrags`, so if the page starts an HTML5
+/// drag-and-drop the drop is completed with `Input.dispatchDragEvent` on the
+/// target point.
+pub async fn drag(
+    page: &Page,
+    from: (f64, f64),
+    to: (f64, f64),
+    steps: u32,
+    modifiers: i64,
+) -> Result<DragMethod> {
+    let mut intercepted = page
+        .event_listener::<EventDragIntercepted>()
+        .await
+        .context("Failed to listen for drag events")?;
+    page.execute(SetInterceptDragsParams::new(true))
+        .await
+        .context("Failed to enable drag interception")?;
+
+    let result = drag_inner(page, &mut intercepted, from, to, steps, modifiers).await;
+
+    if let Err(e) = page.execute(SetInterceptDragsParams::new(false)).await {
+        tracing::debug!("Failed to disable drag interception: {}", e);
+    }
+    result
+}
+
+async fn drag_inner(
+    page: &Page,
+    intercepted: &mut (impl futures::Stream<Item = std::sync::Arc<EventDragIntercepted>> + Unpin),
+    from: (f64, f64),
+    to: (f64, f64),
+    steps: u32,
+    modifiers: i64,
+) -> Result<DragMethod> {
+    move_to(page, from.0, from.1, 0, modifiers).await?;
+
+    let mut press = mouse_event(DispatchMouseEventType::MousePressed, from.0, from.1, modifiers);
+    press.button = Some(MouseButton::Left);
+    press.buttons = Some(1);
+    press.click_count = Some(1);
+    page.execute(press)
+        .await
+        .context("Failed to dispatch mousePressed")?;
+
+    let mut drag_data: Option<DragData> = None;
+    for (x, y) in path_points(from, to, steps) {
+        let mut step = mouse_event(DispatchMouseEventType::MouseMoved, x, y, modifiers);
+        step.button = Some(MouseButton::Left);
+        step.buttons = Some(1);
+        page.execute(step)
+            .await
+            .context("Failed to dispatch mouseMoved")?;
+
+        // Chrome reports a native drag start shortly after the first move past the threshold
+        if let Ok(Some(event)) =
+            tokio::time::timeout(Duration::from_millis(25), intercepted.next()).await
+        {
+            drag_data = Some(event.data.clone());
+            break;
+        }
+    }
+
+    let method = match drag_data {
+        Some(data) => {
+            for event_type in [
+                DispatchDragEventType::DragEnter,
+                DispatchDragEventType::DragOver,
+                DispatchDragEventType::Drop,
+            ] {
+                let mut params = DispatchDragEventParams::new(event_type, to.0, to.1, data.clone());
+                params.modifiers = Some(modifiers);
+                page.execute(params)
+                    .await
+                    .context("Failed to dispatch drag event")?;
+            }
+            DragMethod::Html5
+        }
+        None => DragMethod::Mouse,
+    };
+
+    let mut release = mouse_event(DispatchMouseEventType::MouseReleased, to.0, to.1, modifiers);
+    release.button = Some(MouseButton::Left);
+    release.buttons = Some(0);
+    release.click_count = Some(1);
+    page.execute(release)
+        .await
+        .context("Failed to dispatch mouseReleased")?;
+
+    Ok(method)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_points_end_on_target() {
+        let points = path_points((0.0, 0.0), (100.0, 50.0), 4);
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[0], (25.0, 12.5));
+        assert_eq!(points[3], (100.0, 50.0));
+        assert_eq!(path_points((0.0, 0.0), (10.0, 10.0), 0), vec![(10.0, 10.0)]);
+    }
+
+    #[test]
+    fn test_held_button() {
+        assert_eq!(held_button(1), MouseButton::Left);
+        assert_eq!(held_button(2 | 4), MouseButton::Right);
+        assert_eq!(held_button(0), MouseButton::None);
+    }
+}
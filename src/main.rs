@@ -1,4 +1,5 @@
 use clap::Parser;
+use std::path::PathBuf;
 use rmcp::transport::stdio;
 use rmcp::ServiceExt;
 
@@ -9,6 +10,28 @@ struct Cli {
     /// Run Chrome with a visible window (default: headless)
     #[arg(long)]
     headed: bool,
+
+    /// Directory for exported files such as HAR archives (default: <tmp>/remix-browser)
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// Serve responses from a recorded HAR file instead of the network
+    #[arg(long, value_name = "FILE")]
+    replay_har: Option<String>,
+
+    /// With --replay-har, let requests missing from the HAR go to the network instead of failing
+    #[arg(long, requires = "replay_har")]
+    replay_fallback: bool,
+
+    /// Block requests in every tab. Comma-separated profiles (no-media, no-analytics,
+    /// text-only), resource types (image, font, ...), and URL patterns
+    #[arg(long, value_name = "LIST")]
+    block_resources: Option<String>,
+
+    /// What follows each tool result: the full page snapshot, only the lines that
+    /// changed since the last one on the same URL, or nothing (full, diff, off)
+    #[arg(long, value_name = "MODE", default_value = "full")]
+    auto_snapshot: String,
 }
 
 #[tokio::main]
@@ -30,7 +53,19 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Starting remix-browser MCP server (headless: {})", headless);
 
-    let server = remix_browser::server::RemixBrowserServer::new(headless);
+    let auto_snapshot =
+        remix_browser::tools::snapshot::AutoSnapshotMode::parse(&cli.auto_snapshot)?;
+    let mut server =
+        remix_browser::server::RemixBrowserServer::new(headless).with_auto_snapshot(auto_snapshot);
+    if let Some(output_dir) = cli.output_dir {
+        server = server.with_output_dir(output_dir);
+    }
+    if let Some(ref har) = cli.replay_har {
+        server.start_har_replay(har, cli.replay_fallback).await?;
+    }
+    if let Some(ref spec) = cli.block_resources {
+        server.start_blocking(spec).await?;
+    }
     let service = server.clone().serve(stdio()).await?;
 
     // Wait for MCP service to finish OR a termination signal — whichever comes first
@@ -4,6 +4,8 @@ use std::collections::HashMap;
 pub enum ResolveRefError {
     InvalidFormat(String),
     NotFound(String),
+    /// The ref was issued, but its element has since left the page.
+    Stale(String),
 }
 
 impl std::fmt::Display for ResolveRefError {
@@ -15,6 +17,11 @@ impl std::fmt::Display for ResolveRefError {
                 selector
             ),
             Self::NotFound(ref_id) => write!(f, "Ref '{}' not found, call snapshot again.", ref_id),
+            Self::Stale(ref_id) => write!(
+                f,
+                "Ref '{}' is stale: its element was removed from the page. Call snapshot again.",
+                ref_id
+            ),
         }
     }
 }
@@ -107,6 +114,15 @@ mod tests {
         assert_eq!(err, ResolveRefError::NotFound("e77".to_string()));
     }
 
+    #[test]
+    fn test_stale_ref_error_message() {
+        let err = ResolveRefError::Stale("e5".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Ref 'e5' is stale: its element was removed from the page. Call snapshot again."
+        );
+    }
+
     #[test]
     fn test_resolve_selector_invalid_explicit_ref() {
         let refs = HashMap::new();
@@ -2,13 +2,15 @@ use rmcp::model::*;
 use rmcp::tool;
 use rmcp::{Error as McpError, ServerHandler};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::browser::BrowserSession;
-use crate::selectors::r#ref::{resolve_selector, ResolveRefError};
+use crate::browser::{BrowserSession, Tabs};
+use crate::selectors::r#ref::{parse_ref, resolve_selector, ResolveRefError};
 use crate::tools::{
-    dom, interaction, javascript, navigation, network, page, screenshot, script, snapshot,
+    block, dom, emulation, har, interaction, javascript, navigation, network, page, route,
+    screenshot, script, snapshot, websocket,
 };
 
 const SERVER_INSTRUCTIONS: &str = "remix-browser provides headless Chrome browser automation via CDP. \
@@ -26,6 +28,21 @@ Only use page.wait() after navigate or when waiting for server-side effects (e.g
 Strategy: do the first action with a short script to learn the UI, then batch remaining repetitive work \
 into a single run_script with a loop. Use granular tools (click, type_text, etc.) only for 1-2 simple follow-up actions.";
 
+/// The instructions, with a note when auto-snapshots are not the default full ones.
+fn server_instructions(mode: snapshot::AutoSnapshotMode) -> String {
+    let note = match mode {
+        snapshot::AutoSnapshotMode::Full => "",
+        snapshot::AutoSnapshotMode::Diff => {
+            " On this server, once a page has been shown, later tools on the same URL append only \
+            \"Page changes\": '- ' removed, '+ ' added and '~ ' changed lines. Call snapshot for the full list."
+        }
+        snapshot::AutoSnapshotMode::Off => {
+            " On this server auto-snapshots are OFF: call snapshot (or page.snapshot() in run_script) to get refs."
+        }
+    };
+    format!("{}{}", SERVER_INSTRUCTIONS, note)
+}
+
 fn format_navigation_response(
     result: &navigation::NavigateResult,
     snapshot_text: Option<&str>,
@@ -33,11 +50,12 @@ fn format_navigation_response(
     match snapshot_text {
         Some(snapshot_text) => {
             format!(
-                "Navigated to {} — {}\n\n{}",
-                result.title, result.url, snapshot_text
+                "Navigated to {}\n\n{}",
+                result.summary(),
+                snapshot_text
             )
         }
-        None => format!("Navigated to {} — {}", result.title, result.url),
+        None => format!("Navigated to {}", result.summary()),
     }
 }
 
@@ -47,8 +65,15 @@ pub struct RemixBrowserServer {
     session: Arc<Mutex<Option<BrowserSession>>>,
     console_log: javascript::ConsoleLog,
     network_log: network::NetworkLog,
+    routes: route::Routes,
+    emulation: emulation::Emulation,
     snapshot_refs: Arc<Mutex<HashMap<String, String>>>,
+    auto_snapshot_mode: snapshot::AutoSnapshotMode,
+    /// Last auto-snapshot as (tab id and URL, text), the baseline for diffs.
+    last_snapshot: Arc<Mutex<Option<(String, String)>>>,
     headless: bool,
+    /// Directory for exported files such as HAR archives.
+    output_dir: PathBuf,
 }
 
 impl RemixBrowserServer {
@@ -57,11 +82,47 @@ impl RemixBrowserServer {
             session: Arc::new(Mutex::new(None)),
             console_log: javascript::ConsoleLog::new(),
             network_log: network::NetworkLog::new(),
+            routes: route::Routes::new(),
+            emulation: emulation::Emulation::new(),
             snapshot_refs: Arc::new(Mutex::new(HashMap::new())),
+            auto_snapshot_mode: snapshot::AutoSnapshotMode::default(),
+            last_snapshot: Arc::new(Mutex::new(None)),
             headless,
+            output_dir: har::default_output_dir(),
         }
     }
 
+    /// Write exported files to `output_dir` instead of the default temp location.
+    pub fn with_output_dir(mut self, output_dir: PathBuf) -> Self {
+        self.output_dir = output_dir;
+        self
+    }
+
+    /// Choose what follows each tool result: the full snapshot, only what changed, or nothing.
+    pub fn with_auto_snapshot(mut self, mode: snapshot::AutoSnapshotMode) -> Self {
+        self.auto_snapshot_mode = mode;
+        self
+    }
+
+    /// Serve requests from a recorded HAR file in every tab, starting with the first.
+    pub async fn start_har_replay(&self, path: &str, fallback: bool) -> anyhow::Result<()> {
+        let replay = route::HarReplay::load(path, fallback)?;
+        tracing::info!("Replaying {} responses from {}", replay.status().entries, path);
+        self.routes.set_replay(Some(replay)).await;
+        Ok(())
+    }
+
+    /// Block resources in every tab from a `--block-resources` list of profiles,
+    /// resource types, and URL patterns.
+    pub async fn start_blocking(&self, spec: &str) -> anyhow::Result<()> {
+        let rules = block::BlockRules::parse(spec)?;
+        if rules.is_empty() {
+            anyhow::bail!("--block-resources needs at least one profile, type, or pattern");
+        }
+        self.routes.set_blocking(Some(rules)).await;
+        Ok(())
+    }
+
     /// Explicitly shut down the browser session, killing Chrome.
     pub async fn shutdown(&self) {
         let session_to_close = {
@@ -74,6 +135,7 @@ impl RemixBrowserServer {
             }
         }
         self.clear_snapshot_refs().await;
+        *self.last_snapshot.lock().await = None;
     }
 
     /// Ensure the browser is launched, return a reference to the session.
@@ -81,7 +143,15 @@ impl RemixBrowserServer {
         let mut session = self.session.lock().await;
         if session.is_none() {
             tracing::info!("Launching browser (headless: {})", self.headless);
-            let s = BrowserSession::launch(self.headless).await.map_err(|e| {
+            let s = BrowserSession::launch(
+                self.headless,
+                self.console_log.clone(),
+                self.network_log.clone(),
+                self.routes.clone(),
+                self.emulation.clone(),
+            )
+            .await
+            .map_err(|e| {
                 McpError::internal_error(format!("Failed to launch browser: {}", e), None)
             })?;
             *session = Some(s);
@@ -108,17 +178,11 @@ impl RemixBrowserServer {
             .map_err(|e| McpError::internal_error(format!("{:#}", e), None))
     }
 
-    async fn with_session<F, Fut, T>(&self, f: F) -> Result<T, McpError>
-    where
-        F: FnOnce(&BrowserSession) -> Fut,
-        Fut: std::future::Future<Output = anyhow::Result<T>>,
-    {
+    /// A handle on the session's tabs that does not hold the session lock.
+    async fn tabs(&self) -> Result<Tabs, McpError> {
         self.ensure_browser().await?;
         let session = self.session.lock().await;
-        let session_ref = session.as_ref().unwrap();
-        f(session_ref)
-            .await
-            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))
+        Ok(session.as_ref().unwrap().tabs())
     }
 
     fn text_result(msg: impl Into<String>) -> Result<CallToolResult, McpError> {
@@ -131,10 +195,10 @@ impl RemixBrowserServer {
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
-    fn image_result(base64_data: String) -> Result<CallToolResult, McpError> {
+    fn image_result(shot: screenshot::Screenshot) -> Result<CallToolResult, McpError> {
         Ok(CallToolResult::success(vec![Content::image(
-            base64_data,
-            "image/png",
+            shot.data,
+            shot.mime_type,
         )]))
     }
 
@@ -142,8 +206,11 @@ impl RemixBrowserServer {
         self.snapshot_refs.lock().await.clear();
     }
 
+    /// Record refs from a snapshot. Refs are pinned to their elements, so earlier
+    /// ones stay known (and turn stale once their element goes) until the page
+    /// changes; `clear_snapshot_refs` forgets them.
     async fn set_snapshot_refs(&self, refs: HashMap<String, String>) {
-        *self.snapshot_refs.lock().await = refs;
+        self.snapshot_refs.lock().await.extend(refs);
     }
 
     async fn normalize_selector(&self, selector: &str) -> Result<String, McpError> {
@@ -158,44 +225,133 @@ impl RemixBrowserServer {
         }
     }
 
-    async fn auto_snapshot(&self) -> String {
-        match self.with_page(|page| async move {
-            let params = snapshot::SnapshotParams { selector: None };
-            snapshot::snapshot_with_refs(&page, &params).await
-        }).await {
-            Ok(snap) => {
+    /// Snapshot the active page and record its refs. A whole-page snapshot becomes
+    /// the baseline for diffs; a scoped or accessibility one clears it, as the
+    /// next auto-snapshot cannot be compared against it.
+    async fn take_snapshot(&self, params: snapshot::SnapshotParams) -> Result<String, McpError> {
+        let baseline = params.is_page_snapshot();
+        let result = async {
+            let counter = self.tabs().await?.ref_counter().clone();
+            self.with_page(|page| async move {
+                let snap = snapshot::snapshot_with_refs(&page, &params, &counter).await?;
+                let url = page.url().await?.unwrap_or_default();
+                Ok((format!("{} {}", page.target_id().as_ref(), url), snap))
+            })
+            .await
+        }
+        .await;
+        match result {
+            Ok((page_key, snap)) => {
                 self.set_snapshot_refs(snap.refs).await;
-                snap.text
+                *self.last_snapshot.lock().await = baseline.then(|| (page_key, snap.text.clone()));
+                Ok(snap.text)
+            }
+            Err(e) => {
+                *self.last_snapshot.lock().await = None;
+                Err(e)
+            }
+        }
+    }
+
+    /// Snapshot the whole active page, making it the baseline for diffs.
+    async fn full_snapshot(&self) -> String {
+        self.take_snapshot(Default::default())
+            .await
+            .unwrap_or_else(|_| "Snapshot unavailable".to_string())
+    }
+
+    /// Page state to append to a tool result. In diff mode only the lines that
+    /// changed are shown while the tab stays on the same URL.
+    async fn auto_snapshot(&self) -> String {
+        let previous = match self.auto_snapshot_mode {
+            snapshot::AutoSnapshotMode::Off => return String::new(),
+            snapshot::AutoSnapshotMode::Full => None,
+            snapshot::AutoSnapshotMode::Diff => self.last_snapshot.lock().await.clone(),
+        };
+        let text = self.full_snapshot().await;
+        let current_key = self.last_snapshot.lock().await.as_ref().map(|(key, _)| key.clone());
+        if let Some((previous_key, previous_text)) = previous {
+            if current_key.as_ref() == Some(&previous_key) {
+                let changes = snapshot::diff_snapshots(&previous_text, &text);
+                if changes.is_empty() {
+                    return "\n\nPage state: unchanged".to_string();
+                }
+                // A diff as long as the page itself is no saving
+                if changes.len() < text.lines().count() {
+                    return format!("\n\nPage changes:\n{}", changes.join("\n"));
+                }
             }
-            Err(_) => "Snapshot unavailable".to_string(),
         }
+        format!("\n\nPage state:\n{}", text)
+    }
+
+    /// Describe exceptions the page threw since `mark`, for appending to an interaction result.
+    async fn page_errors_since(&self, mark: u64) -> String {
+        let errors = self.console_log.exceptions_since(mark).await;
+        if errors.is_empty() {
+            return String::new();
+        }
+        let mut out = String::from("\n\nPage errors:");
+        for e in &errors {
+            out.push_str(&format!("\n- {}", e.text));
+            if let (Some(url), Some(line)) = (&e.url, e.line) {
+                out.push_str(&format!(" ({}:{})", url, line));
+            }
+        }
+        out
     }
 
     async fn normalize_selector_with_recovery(&self, selector: &str) -> Result<String, McpError> {
+        let resolved = self.resolve_selector_with_recovery(selector).await?;
+        // A ref always names the element it was issued for; if that element is
+        // gone, fail rather than wait for something that will never appear.
+        if let Some(ref_id) = parse_ref(selector) {
+            let check_id = ref_id.clone();
+            let attached = self
+                .with_page(|page| async move { snapshot::ref_attached(&page, &check_id).await })
+                .await?;
+            if !attached {
+                return Err(self.ref_error_with_snapshot(ResolveRefError::Stale(ref_id)).await);
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Like `normalize_selector_with_recovery`, but lets a ref whose element was
+    /// removed through, for waiting on elements to go away.
+    async fn resolve_selector_with_recovery(&self, selector: &str) -> Result<String, McpError> {
         let result = {
             let refs = self.snapshot_refs.lock().await;
             resolve_selector(selector, &refs)
         };
         match result {
             Ok(resolved) => Ok(resolved),
-            Err(ResolveRefError::NotFound(ref_id)) => {
-                // Auto-recovery: take fresh snapshot
-                let snap_text = self.auto_snapshot().await;
-                Err(McpError::internal_error(
-                    format!("Ref '{}' not found — page may have changed.\n\nCurrent page state:\n{}", ref_id, snap_text),
-                    None,
-                ))
-            }
+            Err(err @ ResolveRefError::NotFound(_)) => Err(self.ref_error_with_snapshot(err).await),
             Err(err) => Err(McpError::internal_error(format!("{}", err), None)),
         }
     }
+
+    /// Report an unusable ref together with a fresh snapshot (auto-recovery).
+    async fn ref_error_with_snapshot(&self, err: ResolveRefError) -> McpError {
+        let message = match err {
+            ResolveRefError::NotFound(ref_id) => {
+                format!("Ref '{}' not found — page may have changed.", ref_id)
+            }
+            err => err.to_string(),
+        };
+        let snap_text = self.full_snapshot().await;
+        McpError::internal_error(
+            format!("{}\n\nCurrent page state:\n{}", message, snap_text),
+            None,
+        )
+    }
 }
 
 #[tool(tool_box)]
 impl ServerHandler for RemixBrowserServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some(SERVER_INSTRUCTIONS.into()),
+            instructions: Some(server_instructions(self.auto_snapshot_mode)),
             capabilities: ServerCapabilities::builder().enable_tools().build(),
             ..Default::default()
         }
@@ -218,27 +374,27 @@ impl RemixBrowserServer {
             .with_page(|page| async move { navigation::navigate(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Navigated to {} — {}\n\nPage state:\n{}", result.title, result.url, snap_text))
+        Self::text_result(format!("Navigated to {}{}", result.summary(), snap_text))
     }
 
-    #[tool(description = "Go back in browser history.")]
+    #[tool(description = "Go back in browser history. Waits for the page to load (or the same-document navigation) and errors if there is no previous entry.")]
     async fn go_back(&self) -> Result<CallToolResult, McpError> {
         self.clear_snapshot_refs().await;
         let result = self
             .with_page(|page| async move { navigation::go_back(&page).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Navigated back to {} — {}\n\nPage state:\n{}", result.title, result.url, snap_text))
+        Self::text_result(format!("Navigated back to {}{}", result.summary(), snap_text))
     }
 
-    #[tool(description = "Go forward in browser history.")]
+    #[tool(description = "Go forward in browser history. Waits for the page to load (or the same-document navigation) and errors if there is no next entry.")]
     async fn go_forward(&self) -> Result<CallToolResult, McpError> {
         self.clear_snapshot_refs().await;
         let result = self
             .with_page(|page| async move { navigation::go_forward(&page).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Navigated forward to {} — {}\n\nPage state:\n{}", result.title, result.url, snap_text))
+        Self::text_result(format!("Navigated forward to {}{}", result.summary(), snap_text))
     }
 
     #[tool(description = "Reload the current page.")]
@@ -248,7 +404,15 @@ impl RemixBrowserServer {
             .with_page(|page| async move { navigation::reload(&page).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Reloaded {} — {}\n\nPage state:\n{}", result.title, result.url, snap_text))
+        Self::text_result(format!("Reloaded {}{}", result.summary(), snap_text))
+    }
+
+    #[tool(description = "List the tab's session history entries {index, url, title, current}.")]
+    async fn get_history(&self) -> Result<CallToolResult, McpError> {
+        let result = self
+            .with_page(|page| async move { navigation::get_history(&page).await })
+            .await?;
+        Self::json_result(result)
     }
 
     #[tool(description = "Get current page URL, title, and viewport size.")]
@@ -290,6 +454,21 @@ impl RemixBrowserServer {
         Self::text_result(result)
     }
 
+    #[tool(
+        description = "Get an element's viewport bounding box {x, y, width, height, center_x, center_y, in_viewport} for use with click_at, move_mouse, wheel, and drag."
+    )]
+    async fn get_bounding_box(
+        &self,
+        #[tool(aggr)] params: dom::GetBoundingBoxParams,
+    ) -> Result<CallToolResult, McpError> {
+        let mut params = params;
+        params.selector = self.normalize_selector_with_recovery(&params.selector).await?;
+        let result = self
+            .with_page(|page| async move { dom::get_bounding_box(&page, &params).await })
+            .await?;
+        Self::json_result(result)
+    }
+
     #[tool(description = "Get HTML content of an element or the entire page.")]
     async fn get_html(
         &self,
@@ -302,17 +481,14 @@ impl RemixBrowserServer {
     }
 
     #[tool(
-        description = "Get a compact snapshot of interactive elements on the page. Returns indexed elements with stable refs like [ref=e0]. Use ref=eN selectors with click/type_text/get_text/wait_for."
+        description = "Get a compact snapshot of interactive elements on the page. Returns indexed elements with stable refs like [ref=e0]. Use ref=eN selectors with click/type_text/get_text/wait_for. mode='accessibility' builds it from Chrome's accessibility tree (computed roles, names, and states) instead of the DOM."
     )]
     async fn snapshot(
         &self,
         #[tool(aggr)] params: snapshot::SnapshotParams,
     ) -> Result<CallToolResult, McpError> {
-        let result = self
-            .with_page(|page| async move { snapshot::snapshot_with_refs(&page, &params).await })
-            .await?;
-        self.set_snapshot_refs(result.refs).await;
-        Self::text_result(result.text)
+        let text = self.take_snapshot(params).await?;
+        Self::text_result(text)
     }
 
     #[tool(description = "Wait for an element to appear, become visible, or be hidden.")]
@@ -321,22 +497,22 @@ impl RemixBrowserServer {
         #[tool(aggr)] params: dom::WaitForParams,
     ) -> Result<CallToolResult, McpError> {
         let mut params = params;
-        params.selector = self.normalize_selector_with_recovery(&params.selector).await?;
+        params.selector = self.resolve_selector_with_recovery(&params.selector).await?;
         let found = self
             .with_page(|page| async move { dom::wait_for(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
         if found {
-            Self::text_result(format!("Element found\n\nPage state:\n{}", snap_text))
+            Self::text_result(format!("Element found{}", snap_text))
         } else {
-            Self::text_result(format!("Element not found (timeout)\n\nPage state:\n{}", snap_text))
+            Self::text_result(format!("Element not found (timeout){}", snap_text))
         }
     }
 
     // ── Interaction ─────────────────────────────────────────────────────
 
     #[tool(
-        description = "Click an element. Uses hybrid strategy: mouse events with JS fallback for obscured elements."
+        description = "Click an element. Uses hybrid strategy: trusted CDP mouse events at the element's center, with JS fallback for hidden or obscured elements. Supports right/middle button, double-click (click_count: 2), and modifier keys."
     )]
     async fn click(
         &self,
@@ -344,37 +520,105 @@ impl RemixBrowserServer {
     ) -> Result<CallToolResult, McpError> {
         let mut params = params;
         params.selector = self.normalize_selector_with_recovery(&params.selector).await?;
+        let error_mark = self.console_log.exception_mark();
         let result = self
             .with_page(|page| async move { interaction::do_click(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Clicked element ({})\n\nPage state:\n{}", result.method_used, snap_text))
+        let errors = self.page_errors_since(error_mark).await;
+        Self::text_result(format!("Clicked element ({}){}{}", result.method_used, errors, snap_text))
     }
 
-    #[tool(description = "Type text into an element.")]
+    #[tool(description = "Type text into an element with trusted input. Set per_character for key-by-key typing.")]
     async fn type_text(
         &self,
         #[tool(aggr)] params: interaction::TypeTextParams,
     ) -> Result<CallToolResult, McpError> {
         let mut params = params;
         params.selector = self.normalize_selector_with_recovery(&params.selector).await?;
+        let error_mark = self.console_log.exception_mark();
         self.with_page(|page| async move { interaction::type_text(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Typed text into element\n\nPage state:\n{}", snap_text))
+        let errors = self.page_errors_since(error_mark).await;
+        Self::text_result(format!("Typed text into element{}{}", errors, snap_text))
     }
 
-    #[tool(description = "Hover over an element.")]
+    #[tool(description = "Hover over an element by moving the real mouse pointer to it (triggers CSS :hover).")]
     async fn hover(
         &self,
         #[tool(aggr)] params: interaction::HoverParams,
     ) -> Result<CallToolResult, McpError> {
         let mut params = params;
         params.selector = self.normalize_selector_with_recovery(&params.selector).await?;
+        let error_mark = self.console_log.exception_mark();
         self.with_page(|page| async move { interaction::hover(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Hovered over element\n\nPage state:\n{}", snap_text))
+        let errors = self.page_errors_since(error_mark).await;
+        Self::text_result(format!("Hovered over element{}{}", errors, snap_text))
+    }
+
+    #[tool(description = "Drag from one element or point to another with real mouse events. Handles HTML5 drag-and-drop (kanban boards, sortable lists) and pointer-driven widgets (slider handles).")]
+    async fn drag(
+        &self,
+        #[tool(aggr)] params: interaction::DragParams,
+    ) -> Result<CallToolResult, McpError> {
+        let mut params = params;
+        if let Some(from) = params.from.take() {
+            params.from = Some(self.normalize_selector_with_recovery(&from).await?);
+        }
+        if let Some(to) = params.to.take() {
+            params.to = Some(self.normalize_selector_with_recovery(&to).await?);
+        }
+        let error_mark = self.console_log.exception_mark();
+        let result = self
+            .with_page(|page| async move { interaction::drag(&page, &params).await })
+            .await?;
+        let snap_text = self.auto_snapshot().await;
+        let errors = self.page_errors_since(error_mark).await;
+        Self::text_result(format!(
+            "Dragged from ({:.0}, {:.0}) to ({:.0}, {:.0}) ({}){}{}",
+            result.from.0, result.from.1, result.to.0, result.to.1, result.method_used, errors, snap_text
+        ))
+    }
+
+    #[tool(description = "Click at viewport coordinates with real mouse events. For canvas, map, and chart UIs without DOM targets; use get_bounding_box to aim relative to an element.")]
+    async fn click_at(
+        &self,
+        #[tool(aggr)] params: interaction::ClickAtParams,
+    ) -> Result<CallToolResult, McpError> {
+        let (x, y) = (params.x, params.y);
+        let error_mark = self.console_log.exception_mark();
+        self.with_page(|page| async move { interaction::click_at(&page, &params).await })
+            .await?;
+        let snap_text = self.auto_snapshot().await;
+        let errors = self.page_errors_since(error_mark).await;
+        Self::text_result(format!("Clicked at ({}, {}){}{}", x, y, errors, snap_text))
+    }
+
+    #[tool(description = "Move the mouse pointer to viewport coordinates (hover without a selector).")]
+    async fn move_mouse(
+        &self,
+        #[tool(aggr)] params: interaction::MoveMouseParams,
+    ) -> Result<CallToolResult, McpError> {
+        let (x, y) = (params.x, params.y);
+        self.with_page(|page| async move { interaction::move_mouse(&page, &params).await })
+            .await?;
+        let snap_text = self.auto_snapshot().await;
+        Self::text_result(format!("Moved mouse to ({}, {}){}", x, y, snap_text))
+    }
+
+    #[tool(description = "Dispatch a mouse wheel event at viewport coordinates (scroll or zoom canvas and map UIs).")]
+    async fn wheel(
+        &self,
+        #[tool(aggr)] params: interaction::WheelParams,
+    ) -> Result<CallToolResult, McpError> {
+        let (x, y) = (params.x, params.y);
+        self.with_page(|page| async move { interaction::wheel(&page, &params).await })
+            .await?;
+        let snap_text = self.auto_snapshot().await;
+        Self::text_result(format!("Wheel at ({}, {}){}", x, y, snap_text))
     }
 
     #[tool(description = "Select an option from a <select> element.")]
@@ -384,10 +628,12 @@ impl RemixBrowserServer {
     ) -> Result<CallToolResult, McpError> {
         let mut params = params;
         params.selector = self.normalize_selector_with_recovery(&params.selector).await?;
+        let error_mark = self.console_log.exception_mark();
         self.with_page(|page| async move { interaction::select_option(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Selected option\n\nPage state:\n{}", snap_text))
+        let errors = self.page_errors_since(error_mark).await;
+        Self::text_result(format!("Selected option{}{}", errors, snap_text))
     }
 
     #[tool(description = "Set the value of any form control (input, textarea, select, checkbox, slider). Smarter than type_text — auto-detects control type.")]
@@ -397,23 +643,27 @@ impl RemixBrowserServer {
     ) -> Result<CallToolResult, McpError> {
         let mut params = params;
         params.selector = self.normalize_selector_with_recovery(&params.selector).await?;
+        let error_mark = self.console_log.exception_mark();
         let result = self
             .with_page(|page| async move { interaction::fill(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("{}\n\nPage state:\n{}", result, snap_text))
+        let errors = self.page_errors_since(error_mark).await;
+        Self::text_result(format!("{}{}{}", result, errors, snap_text))
     }
 
-    #[tool(description = "Press a keyboard key (Enter, Tab, ArrowDown, etc.).")]
+    #[tool(description = "Press a keyboard key (Enter, Tab, ArrowDown, F5, etc.) or a combo like Control+a.")]
     async fn press_key(
         &self,
         #[tool(aggr)] params: interaction::PressKeyParams,
     ) -> Result<CallToolResult, McpError> {
         let key = params.key.clone();
+        let error_mark = self.console_log.exception_mark();
         self.with_page(|page| async move { interaction::press_key(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Pressed {}\n\nPage state:\n{}", key, snap_text))
+        let errors = self.page_errors_since(error_mark).await;
+        Self::text_result(format!("Pressed {}{}{}", key, errors, snap_text))
     }
 
     #[tool(description = "Scroll the page or scroll an element into view.")]
@@ -426,21 +676,34 @@ impl RemixBrowserServer {
         self.with_page(|page| async move { interaction::do_scroll(&page, &params).await })
             .await?;
         let snap_text = self.auto_snapshot().await;
-        Self::text_result(format!("Scrolled {} {}px\n\nPage state:\n{}", direction, amount, snap_text))
+        Self::text_result(format!("Scrolled {} {}px{}", direction, amount, snap_text))
     }
 
     // ── Visual ──────────────────────────────────────────────────────────
 
     #[tool(
-        description = "Take a screenshot of the page, viewport, or a specific element. Returns base64-encoded image."
+        description = "Take a screenshot of the page, viewport, a clip rectangle, or a specific element. Supports png/jpeg/webp with quality, and scale or max_width downscaling (use max_width for full_page captures to save tokens). annotate=true labels interactive elements with their [ref=eN] ids. Returns base64-encoded image."
     )]
     async fn screenshot(
         &self,
         #[tool(aggr)] params: screenshot::ScreenshotParams,
     ) -> Result<CallToolResult, McpError> {
-        let result = self
-            .with_page(|page| async move { screenshot::screenshot(&page, &params).await })
+        let mut params = params;
+        if let Some(ref selector) = params.selector {
+            params.selector = Some(self.normalize_selector_with_recovery(selector).await?);
+        }
+        let counter = self.tabs().await?.ref_counter().clone();
+        let mut result = self
+            .with_page(|page| async move { screenshot::screenshot(&page, &params, &counter).await })
             .await?;
+        if let Some(snap) = result.annotations.take() {
+            // The labels in the image are only useful if the same refs resolve afterwards
+            self.set_snapshot_refs(snap.refs).await;
+            return Ok(CallToolResult::success(vec![
+                Content::text(format!("Annotated refs:\n{}", snap.text)),
+                Content::image(result.data, result.mime_type),
+            ]));
+        }
         Self::image_result(result)
     }
 
@@ -463,7 +726,7 @@ impl RemixBrowserServer {
         Self::text_result(text)
     }
 
-    #[tool(description = "Read console log entries. Can filter by level and pattern.")]
+    #[tool(description = "Read console log entries captured from every tab. Can filter by level, pattern, and tab.")]
     async fn read_console(
         &self,
         #[tool(aggr)] params: javascript::ReadConsoleParams,
@@ -476,25 +739,32 @@ impl RemixBrowserServer {
 
     // ── Network ─────────────────────────────────────────────────────────
 
-    #[tool(description = "Enable network request/response capture.")]
+    #[tool(
+        description = "Enable network request/response capture in every tab, including tabs and popups opened later. Each entry is tagged with the tab_id that issued it. Calling it again only updates the URL patterns."
+    )]
     async fn network_enable(
         &self,
         #[tool(aggr)] params: network::NetworkEnableParams,
     ) -> Result<CallToolResult, McpError> {
-        network::network_enable(&self.network_log, &params)
+        let tabs = self.tabs().await?;
+        let (pages, _) = tabs.pages().await;
+        let count = network::network_enable(&pages, &self.network_log, &params)
             .await
             .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
 
-        // Wire up CDP event listeners on the active page
-        let log = self.network_log.clone();
-        self.with_page(|page| async move { network::start_listening(&page, log).await })
-            .await?;
+        Self::text_result(format!("Network capture enabled in {} tab(s)", count))
+    }
 
-        Self::text_result("Network capture enabled")
+    #[tool(
+        description = "Stop network capture in every tab. Already captured entries stay available to get_network_log and export_har."
+    )]
+    async fn network_disable(&self) -> Result<CallToolResult, McpError> {
+        let stopped = self.network_log.disable().await;
+        Self::text_result(format!("Network capture disabled in {} tab(s)", stopped))
     }
 
     #[tool(
-        description = "Get captured network requests. Filter by URL pattern, method, or status code."
+        description = "Get captured network requests. Filter by URL pattern, method, status code, or tab_id, or set failed_only for requests that errored, were canceled, or were blocked."
     )]
     async fn get_network_log(
         &self,
@@ -506,6 +776,144 @@ impl RemixBrowserServer {
         Self::json_result(result)
     }
 
+    #[tool(
+        description = "Get captured WebSocket frames and Server-Sent Events (EventSource) messages, with connection open/close/error events. Requires network_enable. Filter by connection URL, connection_id, direction ('sent' or 'received'), or a payload regex."
+    )]
+    async fn get_websocket_log(
+        &self,
+        #[tool(aggr)] params: websocket::GetWebSocketLogParams,
+    ) -> Result<CallToolResult, McpError> {
+        let result = websocket::get_websocket_log(&self.network_log.websockets, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        Self::json_result(result)
+    }
+
+    #[tool(
+        description = "Get the response body of a captured request by its request_id from get_network_log. Text is truncated to max_bytes; binary bodies are returned base64-encoded."
+    )]
+    async fn get_response_body(
+        &self,
+        #[tool(aggr)] params: network::GetResponseBodyParams,
+    ) -> Result<CallToolResult, McpError> {
+        let tabs = self.tabs().await?;
+        let result = network::get_response_body(&tabs, &self.network_log, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        Self::json_result(result)
+    }
+
+    #[tool(
+        description = "Export captured network traffic as a HAR 1.2 file (opens in Chrome DevTools and other HAR viewers). Written to the server's output directory; returns the file path."
+    )]
+    async fn export_har(
+        &self,
+        #[tool(aggr)] params: har::ExportHarParams,
+    ) -> Result<CallToolResult, McpError> {
+        let tabs = self.tabs().await?;
+        let (pages, _) = tabs.pages().await;
+        let result = har::export_har(&pages, &self.network_log, &self.output_dir, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        Self::text_result(format!(
+            "Exported {} requests across {} pages ({} bodies) to {}",
+            result.entries, result.pages, result.bodies, result.path
+        ))
+    }
+
+    #[tool(
+        description = "Intercept requests whose URL matches url_pattern in every tab: fulfill them with a mocked status/headers/body (inline or from body_file), abort them with a network error, or continue them with a modified URL, method, headers, or post data. The newest matching route wins."
+    )]
+    async fn route(
+        &self,
+        #[tool(aggr)] params: route::RouteParams,
+    ) -> Result<CallToolResult, McpError> {
+        let tabs = self.tabs().await?;
+        let (pages, _) = tabs.pages().await;
+        let route = route::add_route(&pages, &self.routes, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        Self::json_result(route)
+    }
+
+    #[tool(description = "Remove request routes by id, by url_pattern, or all of them.")]
+    async fn unroute(
+        &self,
+        #[tool(aggr)] params: route::UnrouteParams,
+    ) -> Result<CallToolResult, McpError> {
+        let tabs = self.tabs().await?;
+        let (pages, _) = tabs.pages().await;
+        let removed = route::remove_routes(&pages, &self.routes, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        Self::text_result(format!("Removed {} route(s)", removed))
+    }
+
+    #[tool(
+        description = "Serve responses from a recorded HAR file instead of the network, in every tab. Requests missing from the HAR, or recorded without a complete body, fail as blocked unless fallback is set. Explicit routes still win. Use stop: true to go back online."
+    )]
+    async fn replay_har(
+        &self,
+        #[tool(aggr)] params: route::ReplayHarParams,
+    ) -> Result<CallToolResult, McpError> {
+        let tabs = self.tabs().await?;
+        let (pages, _) = tabs.pages().await;
+        let status = route::replay_har(&pages, &self.routes, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        match status {
+            Some(status) => Self::text_result(format!(
+                "Replaying {} responses from {} (unmatched requests {})",
+                status.entries,
+                status.path,
+                if status.fallback { "go to the network" } else { "are blocked" }
+            )),
+            None => Self::text_result("HAR replay stopped"),
+        }
+    }
+
+    #[tool(
+        description = "Block requests by resource type and URL pattern in every tab to speed up page loads. Profiles: no-media, no-analytics, text-only. Blocked requests show up in get_network_log with blocked_reason 'client'. Replaces earlier rules; clear: true stops blocking."
+    )]
+    async fn block_resources(
+        &self,
+        #[tool(aggr)] params: block::BlockResourcesParams,
+    ) -> Result<CallToolResult, McpError> {
+        let tabs = self.tabs().await?;
+        let (pages, _) = tabs.pages().await;
+        let rules = block::block_resources(&pages, &self.routes, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        match rules {
+            Some(rules) => Self::text_result(format!(
+                "Blocking resource types [{}] and {} URL pattern(s)",
+                rules.resource_types.join(", "),
+                rules.url_patterns.len()
+            )),
+            None => Self::text_result("Resource blocking cleared"),
+        }
+    }
+
+    #[tool(
+        description = "Throttle the network and CPU in every tab, including tabs opened later. Presets: slow-3g, fast-3g, offline, none. Custom latency (ms) and download/upload (kbit/s) override the preset. cpu_throttling is a slowdown factor (4 = 4x slower, 1 = full speed). Returns the active conditions."
+    )]
+    async fn emulate_network(
+        &self,
+        #[tool(aggr)] params: emulation::EmulateNetworkParams,
+    ) -> Result<CallToolResult, McpError> {
+        let tabs = self.tabs().await?;
+        let (pages, _) = tabs.pages().await;
+        let state = emulation::emulate_network(&pages, &self.emulation, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        Self::json_result(state)
+    }
+
+    #[tool(description = "List active request routes with their id, pattern, action, and hit count.")]
+    async fn list_routes(&self) -> Result<CallToolResult, McpError> {
+        Self::json_result(self.routes.list().await)
+    }
+
     // ── Tabs ────────────────────────────────────────────────────────────
 
     #[tool(description = "Open a new browser tab.")]
@@ -514,10 +922,7 @@ impl RemixBrowserServer {
         #[tool(aggr)] params: page::NewTabParams,
     ) -> Result<CallToolResult, McpError> {
         self.clear_snapshot_refs().await;
-        self.ensure_browser().await?;
-        let session = self.session.lock().await;
-        let session_ref = session.as_ref().unwrap();
-        let tab_id = page::new_tab(session_ref, &params)
+        let tab_id = page::new_tab(&self.tabs().await?, &params)
             .await
             .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
         Self::text_result(format!("Opened new tab: {}", tab_id))
@@ -529,21 +934,32 @@ impl RemixBrowserServer {
         #[tool(aggr)] params: page::CloseTabParams,
     ) -> Result<CallToolResult, McpError> {
         self.clear_snapshot_refs().await;
-        self.ensure_browser().await?;
-        let session = self.session.lock().await;
-        let session_ref = session.as_ref().unwrap();
-        page::close_tab(session_ref, &params)
+        page::close_tab(&self.tabs().await?, &params)
             .await
             .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
         Self::text_result("Closed tab")
     }
 
-    #[tool(description = "List all open browser tabs.")]
+    #[tool(description = "Switch the active tab by tab_id or index (from list_tabs). Later tools act on that tab.")]
+    async fn switch_tab(
+        &self,
+        #[tool(aggr)] params: page::SwitchTabParams,
+    ) -> Result<CallToolResult, McpError> {
+        self.clear_snapshot_refs().await;
+        let tabs = self.tabs().await?;
+        let tab = page::switch_tab(&tabs, &params)
+            .await
+            .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
+        let snap_text = self.auto_snapshot().await;
+        Self::text_result(format!(
+            "Switched to tab {} — {} — {}{}",
+            tab.tab_id, tab.title, tab.url, snap_text
+        ))
+    }
+
+    #[tool(description = "List all open browser tabs with their index, id, URL, title, and which one is active.")]
     async fn list_tabs(&self) -> Result<CallToolResult, McpError> {
-        self.ensure_browser().await?;
-        let session = self.session.lock().await;
-        let session_ref = session.as_ref().unwrap();
-        let result = page::list_tabs(session_ref)
+        let result = page::list_tabs(&self.tabs().await?)
             .await
             .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
         Self::json_result(result)
@@ -560,14 +976,22 @@ impl RemixBrowserServer {
         \n\n**Strategy**: First do 1 action with a short script to learn the UI selectors, \
         then batch remaining repetitive work into a single run_script with a loop.\
         \n\nAvailable API:\n\
-        - page.navigate(url), page.back(), page.forward(), page.reload()\n\
-        - page.click(selector, {type:'text'}), page.type(selector, text, {clear:true})\n\
+        - page.navigate(url, {wait_until:'networkidle', timeout_ms:30000, fail_on_http_error:true}), page.back(), page.forward(), page.reload(), page.history()\n\
+        - page.tabs() → [{index, tab_id, url, title, active}], page.newTab(url), page.switchTab(tab_id or index), page.closeTab(tab_id?) — e.g. for OAuth popups\n\
+        - page.click(selector, {type:'text', button:'right', click_count:2, modifiers:['shift']}), page.type(selector, text, {clear:true, per_character:true, delay_ms:50})\n\
         - page.fill(selector, value, {type:'text'}) — set any form control value (input, select, checkbox, range)\n\
-        - page.press(key, {modifiers:['ctrl']}), page.hover(selector), page.select(selector, value)\n\
+        - page.press(key, {modifiers:['ctrl']}) or page.press('Control+Shift+K'), page.hover(selector), page.select(selector, value)\n\
+        - page.drag(from, to, {steps:20}) — from/to are selectors or {x, y} points\n\
+        - page.clickAt(x, y, {button:'left', click_count:1}), page.boundingBox(selector) → {x, y, width, height, center_x, center_y}\n\
+        - page.mouse.move(x, y), page.mouse.down(x, y), page.mouse.up(x, y), page.mouse.click(x, y), page.mouse.wheel(x, y, dx, dy)\n\
         - page.scroll(direction, {amount:500}), page.wait(ms), page.waitFor(selector, {timeout:5000})\n\
-        - page.snapshot(), page.screenshot(), page.getText(selector), page.getHtml()\n\
+        - page.snapshot(selector?, {mode:'accessibility'}), page.screenshot({format:'jpeg', quality:70, max_width:800, selector, clip:{x,y,width,height}, annotate:true}), page.getText(selector), page.getHtml()\n\
         - page.findElements(selector), page.js(expr), console.log(...)\n\
-        - page.readConsole(), page.enableNetwork(), page.getNetworkLog()\n\
+        - page.readConsole(), page.enableNetwork() (all tabs), page.disableNetwork(), page.getNetworkLog({include_headers:true, failed_only:true, tab_id}), page.getResponseBody(request_id, {max_bytes:100000})\n\
+        - page.getWebSocketLog({url_pattern, connection_id, direction:'received', payload_regex:'price', limit:100}) → WebSocket/SSE messages\n\
+        - page.exportHar(filename, {include_bodies:true}) → {path, entries, pages, bodies}\n\
+        - page.setNetwork({preset:'slow-3g'} | {offline:true} | {latency:200, download:1500, upload:750, cpu_throttling:4}) → active conditions, applied to every tab\n\
+        - page.route(pattern, {status:500, body:'{}'} | {action:'abort', error_reason:'Failed'} | {action:'continue', headers:{...}}) → {id, ...} (static options only, no handler callback), page.unroute(idOrPattern), page.routes()\n\
         - page.waitForNetworkIdle({timeout:30000, idle:500})\n\
         \n\nRef selectors work inside scripts: after page.snapshot(), use [ref=eN] with click/type/getText/etc.\n\
         [ref=eN] patterns also auto-resolve inside page.js() expressions.\n\
@@ -597,11 +1021,17 @@ impl RemixBrowserServer {
         };
         let console_log = self.console_log.clone();
         let network_log = self.network_log.clone();
-        let (result, screenshot_contents, script_refs) = self
-            .with_page(|page| async move {
-                script::run_script(&page, &params, &console_log, &network_log, current_refs).await
-            })
-            .await?;
+        let tabs = self.tabs().await?;
+        let (result, screenshot_contents, script_refs) = script::run_script_with_tabs(
+            &tabs,
+            &params,
+            &console_log,
+            &network_log,
+            &self.output_dir,
+            current_refs,
+        )
+        .await
+        .map_err(|e| McpError::internal_error(format!("{:#}", e), None))?;
 
         // Auto-snapshot after script (reuse helper)
         let snap_text = self.auto_snapshot().await;
@@ -613,7 +1043,7 @@ impl RemixBrowserServer {
             }
         }
 
-        let output_text = format!("{}\n\nPage state:\n{}", result.format_output(), snap_text);
+        let output_text = format!("{}{}", result.format_output(), snap_text);
         let mut contents: Vec<Content> = vec![Content::text(output_text)];
         contents.extend(screenshot_contents);
         Ok(CallToolResult::success(contents))
@@ -628,6 +1058,10 @@ mod tests {
         navigation::NavigateResult {
             url: "https://example.com".to_string(),
             title: "Example".to_string(),
+            status: None,
+            mime_type: None,
+            redirects: Vec::new(),
+            error: None,
         }
     }
 
@@ -638,6 +1072,16 @@ mod tests {
         assert!(SERVER_INSTRUCTIONS.contains("batch remaining repetitive work"));
     }
 
+    #[test]
+    fn test_server_instructions_describe_auto_snapshot_mode() {
+        assert_eq!(
+            server_instructions(snapshot::AutoSnapshotMode::Full),
+            SERVER_INSTRUCTIONS
+        );
+        assert!(server_instructions(snapshot::AutoSnapshotMode::Diff).contains("Page changes"));
+        assert!(server_instructions(snapshot::AutoSnapshotMode::Off).contains("OFF"));
+    }
+
     #[test]
     fn test_format_navigation_response_with_snapshot() {
         let text = format_navigation_response(&sample_nav_result(), Some("[0] [ref=e0] button"));
@@ -651,6 +1095,23 @@ mod tests {
         assert_eq!(text, "Navigated to Example — https://example.com");
     }
 
+    #[test]
+    fn test_format_navigation_response_reports_status_and_redirects() {
+        let mut result = sample_nav_result();
+        result.status = Some(404);
+        result.mime_type = Some("text/html".to_string());
+        result.redirects = vec![navigation::RedirectHop {
+            url: "http://example.com".to_string(),
+            status: 301,
+        }];
+        let text = format_navigation_response(&result, None);
+        assert_eq!(
+            text,
+            "Navigated to Example — https://example.com (HTTP 404, text/html, redirected from http://example.com (301))"
+        );
+        assert!(result.is_error());
+    }
+
     #[tokio::test]
     async fn test_normalize_selector_resolves_snapshot_ref() {
         let server = RemixBrowserServer::new(true);
@@ -676,4 +1137,28 @@ mod tests {
 
         assert!(format!("{}", err).contains("Ref 'e99' not found, call snapshot again."));
     }
+
+    #[tokio::test]
+    async fn test_page_errors_since_lists_new_exceptions() {
+        let server = RemixBrowserServer::new(true);
+        let mark = server.console_log.exception_mark();
+        assert_eq!(server.page_errors_since(mark).await, "");
+
+        server
+            .console_log
+            .add(javascript::ConsoleEntry {
+                level: "exception".to_string(),
+                text: "Uncaught TypeError: boom".to_string(),
+                url: Some("https://example.com/app.js".to_string()),
+                line: Some(12),
+                ..Default::default()
+            })
+            .await;
+
+        let errors = server.page_errors_since(mark).await;
+        assert_eq!(
+            errors,
+            "\n\nPage errors:\n- Uncaught TypeError: boom (https://example.com/app.js:12)"
+        );
+    }
 }
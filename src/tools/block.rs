@@ -0,0 +1,233 @@
+use anyhow::Result;
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::tools::route::{self, Routes};
+
+/// CDP resource types, lowercased.
+const RESOURCE_TYPES: &[&str] = &[
+    "document",
+    "stylesheet",
+    "image",
+    "media",
+    "font",
+    "script",
+    "texttrack",
+    "xhr",
+    "fetch",
+    "prefetch",
+    "eventsource",
+    "websocket",
+    "manifest",
+    "signedexchange",
+    "ping",
+    "cspviolationreport",
+    "preflight",
+    "other",
+];
+
+/// Well-known analytics, tag manager, and ad hosts.
+const ANALYTICS_PATTERNS: &[&str] = &[
+    "google-analytics.com",
+    "googletagmanager.com",
+    "doubleclick.net",
+    "googlesyndication.com",
+    "connect.facebook.net",
+    "segment.com",
+    "segment.io",
+    "hotjar.com",
+    "mixpanel.com",
+    "amplitude.com",
+    "fullstory.com",
+    "clarity.ms",
+    "newrelic.com",
+    "nr-data.net",
+];
+
+/// Built-in profiles: (name, resource types, include analytics patterns).
+const PROFILES: &[(&str, &[&str], bool)] = &[
+    ("no-media", &["image", "media"], false),
+    ("no-analytics", &[], true),
+    ("text-only", &["image", "media", "font"], true),
+];
+
+/// Requests to fail before they reach the network.
+#[derive(Debug, Serialize)]
+pub struct BlockRules {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub profiles: Vec<String>,
+    pub resource_types: Vec<String>,
+    pub url_patterns: Vec<String>,
+    /// Requests blocked so far.
+    #[serde(serialize_with = "serialize_count")]
+    pub blocked: AtomicU32,
+}
+
+fn serialize_count<S: serde::Serializer>(count: &AtomicU32, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u32(count.load(Ordering::Relaxed))
+}
+
+impl BlockRules {
+    fn new(
+        profiles: &[String],
+        resource_types: &[String],
+        url_patterns: &[String],
+    ) -> Result<Self> {
+        let mut rules = Self {
+            profiles: Vec::new(),
+            resource_types: Vec::new(),
+            url_patterns: Vec::new(),
+            blocked: AtomicU32::new(0),
+        };
+        for name in profiles {
+            let Some((name, types, analytics)) = PROFILES
+                .iter()
+                .find(|(p, _, _)| p.eq_ignore_ascii_case(name))
+            else {
+                anyhow::bail!(
+                    "Unknown block profile '{}'. Available: {}",
+                    name,
+                    profile_names()
+                );
+            };
+            rules.profiles.push(name.to_string());
+            rules
+                .resource_types
+                .extend(types.iter().map(|t| t.to_string()));
+            if *analytics {
+                rules
+                    .url_patterns
+                    .extend(ANALYTICS_PATTERNS.iter().map(|p| p.to_string()));
+            }
+        }
+        for resource_type in resource_types {
+            let lower = resource_type.to_ascii_lowercase();
+            if !RESOURCE_TYPES.contains(&lower.as_str()) {
+                anyhow::bail!(
+                    "Unknown resource type '{}'. Use one of: {}",
+                    resource_type,
+                    RESOURCE_TYPES.join(", ")
+                );
+            }
+            rules.resource_types.push(lower);
+        }
+        rules.url_patterns.extend(url_patterns.iter().cloned());
+        rules.resource_types.sort();
+        rules.resource_types.dedup();
+        rules.url_patterns.sort();
+        rules.url_patterns.dedup();
+        Ok(rules)
+    }
+
+    /// Parse a comma-separated list (the `--block-resources` CLI value) where each
+    /// item is a profile name, a resource type, or otherwise a URL pattern.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (mut profiles, mut types, mut patterns) = (Vec::new(), Vec::new(), Vec::new());
+        for item in spec.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+            let lower = item.to_ascii_lowercase();
+            if PROFILES.iter().any(|(p, _, _)| *p == lower) {
+                profiles.push(lower);
+            } else if RESOURCE_TYPES.contains(&lower.as_str()) {
+                types.push(lower);
+            } else {
+                patterns.push(item.to_string());
+            }
+        }
+        Self::new(&profiles, &types, &patterns)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resource_types.is_empty() && self.url_patterns.is_empty()
+    }
+
+    /// Whether to block a request, counting it if so.
+    pub fn check(&self, url: &str, resource_type: &str) -> bool {
+        let blocked = self
+            .resource_types
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(resource_type))
+            || self.url_patterns.iter().any(|p| route::url_matches(p, url));
+        if blocked {
+            self.blocked.fetch_add(1, Ordering::Relaxed);
+        }
+        blocked
+    }
+}
+
+fn profile_names() -> String {
+    PROFILES
+        .iter()
+        .map(|(name, _, _)| *name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct BlockResourcesParams {
+    #[schemars(
+        description = "Built-in profile: no-media (images, video, audio), no-analytics (trackers and ad hosts), or text-only (both, plus web fonts)"
+    )]
+    pub profile: Option<String>,
+    #[schemars(
+        description = "Resource types to block, e.g. ['image', 'font', 'media', 'stylesheet', 'script']"
+    )]
+    pub resource_types: Option<Vec<String>>,
+    #[schemars(
+        description = "URL patterns to block: globs with * (e.g. '*.mp4') or plain substrings (e.g. 'ads.example.com')"
+    )]
+    pub url_patterns: Option<Vec<String>>,
+    #[schemars(description = "Stop blocking")]
+    pub clear: Option<bool>,
+}
+
+/// Replace the blocking rules for `pages` and every tab opened later. Returns the
+/// new rules, or `None` when blocking was cleared.
+pub async fn block_resources(
+    pages: &[Page],
+    routes: &Routes,
+    params: &BlockResourcesParams,
+) -> Result<Option<std::sync::Arc<BlockRules>>> {
+    let rules = if params.clear.unwrap_or(false) {
+        None
+    } else {
+        let profiles: Vec<String> = params.profile.iter().cloned().collect();
+        let rules = BlockRules::new(
+            &profiles,
+            params.resource_types.as_deref().unwrap_or_default(),
+            params.url_patterns.as_deref().unwrap_or_default(),
+        )?;
+        if rules.is_empty() {
+            anyhow::bail!("Provide a profile, resource_types, or url_patterns (or clear: true)");
+        }
+        Some(rules)
+    };
+    routes.set_blocking(rules).await;
+    route::sync_pages(pages, routes).await?;
+    Ok(routes.blocking().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        let rules = BlockRules::parse("no-media, font, *.mp4,ads.example.com").unwrap();
+        assert_eq!(rules.profiles, vec!["no-media"]);
+        assert_eq!(rules.resource_types, vec!["font", "image", "media"]);
+        assert_eq!(rules.url_patterns, vec!["*.mp4", "ads.example.com"]);
+    }
+
+    #[test]
+    fn test_text_only_profile() {
+        let rules = BlockRules::new(&["text-only".into()], &[], &[]).unwrap();
+        assert!(rules.check("https://x.test/logo.png", "Image"));
+        assert!(rules.check("https://www.google-analytics.com/g/collect", "Ping"));
+        assert!(!rules.check("https://x.test/app.js", "Script"));
+        assert_eq!(rules.blocked.load(Ordering::Relaxed), 2);
+
+        assert!(BlockRules::new(&["everything".into()], &[], &[]).is_err());
+        assert!(BlockRules::new(&[], &["pictures".into()], &[]).is_err());
+    }
+}
@@ -65,6 +65,56 @@ pub async fn get_text(page: &Page, params: &GetTextParams) -> Result<String> {
     Ok(result)
 }
 
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetBoundingBoxParams {
+    #[schemars(description = "Selector for the element to measure")]
+    pub selector: String,
+    #[schemars(description = "Type of selector: css, text, or xpath")]
+    pub selector_type: Option<SelectorType>,
+    #[schemars(description = "Scroll the element into view first so the coordinates are clickable (default: true)")]
+    pub scroll_into_view: Option<bool>,
+}
+
+/// Viewport-relative box of an element, in CSS pixels. Feed `center_x`/`center_y`
+/// (or an offset from `x`/`y`) to the coordinate tools (click_at, move_mouse, wheel).
+pub async fn get_bounding_box(
+    page: &Page,
+    params: &GetBoundingBoxParams,
+) -> Result<serde_json::Value> {
+    let selector_type = params.selector_type.clone().unwrap_or_default();
+    let (selector, selector_type) = selectors::normalize_selector_type(&params.selector, selector_type);
+    // Auto-wait for element to appear
+    crate::interaction::wait::wait_for_selector(page, &selector, &selector_type, 5000).await?;
+    let selector_js = crate::interaction::click::selector_to_js(&selector, &selector_type)?;
+
+    let js = format!(
+        r#"(() => {{
+            const el = {selector_js};
+            if (!el) throw new Error('Element not found: ' + {sel_str});
+            if ({scroll}) el.scrollIntoView({{ block: 'center', inline: 'center', behavior: 'instant' }});
+            const r = el.getBoundingClientRect();
+            return {{
+                x: r.left,
+                y: r.top,
+                width: r.width,
+                height: r.height,
+                center_x: r.left + r.width / 2,
+                center_y: r.top + r.height / 2,
+                in_viewport: r.bottom > 0 && r.right > 0 && r.top < innerHeight && r.left < innerWidth
+            }};
+        }})()"#,
+        selector_js = selector_js,
+        sel_str = serde_json::to_string(&selector)?,
+        scroll = params.scroll_into_view.unwrap_or(true)
+    );
+
+    page.evaluate(js)
+        .await
+        .context("Failed to get bounding box")?
+        .into_value()
+        .context("Failed to parse bounding box")
+}
+
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct GetHtmlParams {
     #[schemars(description = "Selector to get HTML from (default: entire page)")]
@@ -0,0 +1,281 @@
+use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::emulation::SetCpuThrottlingRateParams;
+// emulateNetworkConditions is deprecated in favour of emulateNetworkConditionsByRule,
+// which older Chrome builds do not support yet.
+#[allow(deprecated)]
+use chromiumoxide::cdp::browser_protocol::network::{EmulateNetworkConditionsParams, EnableParams};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Throttled network conditions. Throughput is in kbit/s; `None` means unthrottled.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NetworkConditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    pub offline: bool,
+    pub latency: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload: Option<f64>,
+}
+
+impl NetworkConditions {
+    /// Presets matching Chrome DevTools' throttling menu.
+    fn preset(name: &str) -> Result<Option<Self>> {
+        let (offline, latency, download, upload) = match name.to_ascii_lowercase().as_str() {
+            "none" | "online" | "no-throttling" => return Ok(None),
+            "offline" => (true, 0.0, None, None),
+            "slow-3g" | "slow 3g" => (false, 2000.0, Some(400.0), Some(400.0)),
+            "fast-3g" | "fast 3g" => (false, 562.5, Some(1440.0), Some(675.0)),
+            other => anyhow::bail!(
+                "Unknown network preset '{}'. Use slow-3g, fast-3g, offline, or none.",
+                other
+            ),
+        };
+        Ok(Some(Self {
+            preset: Some(name.to_ascii_lowercase()),
+            offline,
+            latency,
+            download,
+            upload,
+        }))
+    }
+
+    #[allow(deprecated)]
+    fn to_cdp(&self) -> EmulateNetworkConditionsParams {
+        // CDP wants bytes per second, with -1 for no limit
+        let throughput = |kbps: Option<f64>| kbps.map_or(-1.0, |k| k * 1000.0 / 8.0);
+        EmulateNetworkConditionsParams::new(
+            self.offline,
+            self.latency,
+            throughput(self.download),
+            throughput(self.upload),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EmulationState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkConditions>,
+    /// CPU slowdown factor; `None` runs at full speed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_throttling: Option<f64>,
+}
+
+/// Network and CPU conditions shared by every tab, including ones opened later.
+#[derive(Debug, Clone, Default)]
+pub struct Emulation {
+    state: Arc<Mutex<EmulationState>>,
+}
+
+impl Emulation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn state(&self) -> EmulationState {
+        self.state.lock().await.clone()
+    }
+
+    /// Apply the current conditions to a page entering the pool.
+    pub async fn attach(&self, page: &Page) -> Result<()> {
+        let state = self.state().await;
+        if state != EmulationState::default() {
+            apply(page, &state).await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct EmulateNetworkParams {
+    #[schemars(
+        description = "Network preset: slow-3g, fast-3g, offline, or none to stop throttling. Other network fields override the preset."
+    )]
+    pub preset: Option<String>,
+    #[schemars(description = "Simulate being offline")]
+    pub offline: Option<bool>,
+    #[schemars(description = "Added round-trip latency in milliseconds")]
+    pub latency: Option<f64>,
+    #[schemars(description = "Download throughput in kbit/s")]
+    pub download: Option<f64>,
+    #[schemars(description = "Upload throughput in kbit/s")]
+    pub upload: Option<f64>,
+    #[schemars(
+        description = "CPU slowdown factor, e.g. 4 for a 4x slower CPU; 1 for full speed. Left unchanged when omitted."
+    )]
+    pub cpu_throttling: Option<f64>,
+}
+
+impl EmulateNetworkParams {
+    fn touches_network(&self) -> bool {
+        self.preset.is_some()
+            || self.offline.is_some()
+            || self.latency.is_some()
+            || self.download.is_some()
+            || self.upload.is_some()
+    }
+}
+
+/// Work out the new state: network fields replace the network conditions as a
+/// whole, while the CPU rate only changes when given.
+fn next_state(current: &EmulationState, params: &EmulateNetworkParams) -> Result<EmulationState> {
+    let mut state = current.clone();
+    if params.touches_network() {
+        let base = match params.preset {
+            Some(ref name) => NetworkConditions::preset(name)?,
+            None => None,
+        };
+        let mut network = base.clone().unwrap_or(NetworkConditions {
+            preset: None,
+            offline: false,
+            latency: 0.0,
+            download: None,
+            upload: None,
+        });
+        let overridden = params.offline.is_some()
+            || params.latency.is_some()
+            || params.download.is_some()
+            || params.upload.is_some();
+        if let Some(offline) = params.offline {
+            network.offline = offline;
+        }
+        if let Some(latency) = params.latency {
+            network.latency = latency.max(0.0);
+        }
+        if let Some(download) = params.download {
+            network.download = (download > 0.0).then_some(download);
+        }
+        if let Some(upload) = params.upload {
+            network.upload = (upload > 0.0).then_some(upload);
+        }
+        let unthrottled = !network.offline
+            && network.latency == 0.0
+            && network.download.is_none()
+            && network.upload.is_none();
+        state.network = if base.is_none() && (!overridden || unthrottled) {
+            None
+        } else {
+            Some(network)
+        };
+    }
+    if let Some(rate) = params.cpu_throttling {
+        if rate < 1.0 {
+            anyhow::bail!("cpu_throttling must be at least 1 (1 = full speed)");
+        }
+        state.cpu_throttling = (rate > 1.0).then_some(rate);
+    }
+    Ok(state)
+}
+
+#[allow(deprecated)]
+async fn apply(page: &Page, state: &EmulationState) -> Result<()> {
+    // Network emulation only takes effect with the Network domain enabled
+    page.execute(EnableParams::default())
+        .await
+        .context("Failed to enable network domain")?;
+    let network = match state.network {
+        Some(ref network) => network.to_cdp(),
+        None => EmulateNetworkConditionsParams::new(false, 0.0, -1.0, -1.0),
+    };
+    page.execute(network)
+        .await
+        .context("Failed to emulate network conditions")?;
+    page.execute(SetCpuThrottlingRateParams::new(
+        state.cpu_throttling.unwrap_or(1.0),
+    ))
+    .await
+    .context("Failed to set CPU throttling")?;
+    Ok(())
+}
+
+/// Apply new conditions to every open tab, then make them the shared conditions.
+/// If a tab rejects them, the tabs already changed go back to the old conditions
+/// and the shared state is left as it was.
+pub async fn emulate_network(
+    pages: &[Page],
+    emulation: &Emulation,
+    params: &EmulateNetworkParams,
+) -> Result<EmulationState> {
+    // Held throughout so a tab attaching meanwhile waits for the outcome
+    let mut state = emulation.state.lock().await;
+    let next = next_state(&state, params)?;
+    for (applied, page) in pages.iter().enumerate() {
+        if let Err(e) = apply(page, &next).await {
+            for page in &pages[..applied] {
+                let _ = apply(page, &state).await;
+            }
+            return Err(e.context(format!(
+                "Failed to emulate network on tab {}",
+                page.target_id().as_ref()
+            )));
+        }
+    }
+    *state = next.clone();
+    Ok(next)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_presets_and_overrides() {
+        let params = EmulateNetworkParams {
+            preset: Some("Slow-3G".into()),
+            latency: Some(100.0),
+            ..Default::default()
+        };
+        let state = next_state(&EmulationState::default(), &params).unwrap();
+        let network = state.network.clone().unwrap();
+        assert_eq!(network.latency, 100.0);
+        assert_eq!(network.download, Some(400.0));
+        // 400 kbit/s → 50,000 bytes/s, as in DevTools
+        assert_eq!(network.to_cdp().download_throughput, 50_000.0);
+
+        // CPU-only changes keep the network conditions
+        let params = EmulateNetworkParams {
+            cpu_throttling: Some(4.0),
+            ..Default::default()
+        };
+        let state = next_state(&state, &params).unwrap();
+        assert_eq!(state.cpu_throttling, Some(4.0));
+        assert_eq!(state.network, Some(network));
+
+        let params = EmulateNetworkParams {
+            preset: Some("none".into()),
+            cpu_throttling: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            next_state(&state, &params).unwrap(),
+            EmulationState::default()
+        );
+    }
+
+    #[test]
+    fn test_custom_conditions_without_preset() {
+        let params = EmulateNetworkParams {
+            offline: Some(true),
+            ..Default::default()
+        };
+        let state = next_state(&EmulationState::default(), &params).unwrap();
+        assert!(state.network.unwrap().offline);
+
+        let bad = EmulateNetworkParams {
+            preset: Some("5g".into()),
+            ..Default::default()
+        };
+        assert!(next_state(&EmulationState::default(), &bad).is_err());
+        let bad = EmulateNetworkParams {
+            cpu_throttling: Some(0.5),
+            ..Default::default()
+        };
+        assert!(next_state(&EmulationState::default(), &bad).is_err());
+    }
+}
@@ -0,0 +1,611 @@
+use anyhow::{Context, Result};
+use chromiumoxide::page::Page;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+use crate::tools::network::{self, NetworkEntry, NetworkLog, ResponseBody};
+
+/// Where exported files go when the server is not given `--output-dir`.
+pub fn default_output_dir() -> PathBuf {
+    std::env::temp_dir().join("remix-browser")
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ExportHarParams {
+    #[schemars(
+        description = "File name inside the server's output directory (default: network-<timestamp>.har)"
+    )]
+    pub filename: Option<String>,
+    #[schemars(
+        description = "Fetch response bodies from the browser and embed them (default: false). Only bodies of pages that are still open are available."
+    )]
+    pub include_bodies: Option<bool>,
+    #[schemars(
+        description = "Per-body size cap in bytes when include_bodies is set (default: 100000)"
+    )]
+    pub max_body_bytes: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HarExport {
+    pub path: String,
+    pub entries: usize,
+    pub pages: usize,
+    pub bodies: usize,
+}
+
+/// Write every logged request to a HAR 1.2 file in `output_dir`. `pages` are the
+/// open tabs; response bodies are fetched from the tab that made each request.
+pub async fn export_har(
+    pages: &[Page],
+    network_log: &NetworkLog,
+    output_dir: &Path,
+    params: &ExportHarParams,
+) -> Result<HarExport> {
+    let path = output_path(output_dir, params.filename.as_deref())?;
+    let entries = network_log.get_log(None, None, None).await;
+
+    let mut bodies = HashMap::new();
+    if params.include_bodies.unwrap_or(false) {
+        // Redirect hops share the final response's request id but have no body
+        for entry in entries
+            .iter()
+            .filter(|e| !e.is_failed() && e.redirect_url.is_none())
+        {
+            let Some(page) = pages
+                .iter()
+                .find(|p| p.target_id().as_ref() == entry.tab_id)
+            else {
+                continue;
+            };
+            match network::fetch_response_body(page, entry, params.max_body_bytes).await {
+                Ok(body) => {
+                    bodies.insert(entry.request_id.clone(), body);
+                }
+                Err(e) => tracing::debug!("No body for {}: {:#}", entry.url, e),
+            }
+        }
+    }
+
+    let har = build_har(&entries, &bodies);
+    let page_count = har["log"]["pages"].as_array().map_or(0, |p| p.len());
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(&har)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    Ok(HarExport {
+        path: path.display().to_string(),
+        entries: entries.len(),
+        pages: page_count,
+        bodies: bodies.len(),
+    })
+}
+
+/// Resolve `filename` inside `output_dir`, refusing paths that would escape it.
+fn output_path(output_dir: &Path, filename: Option<&str>) -> Result<PathBuf> {
+    let filename = match filename {
+        Some(name) => name.to_string(),
+        None => {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            format!("network-{}.har", now.as_millis())
+        }
+    };
+    let relative = Path::new(&filename);
+    let is_plain = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    if filename.is_empty() || !is_plain {
+        anyhow::bail!(
+            "filename must be a relative path inside the output directory, got '{}'",
+            filename
+        );
+    }
+    Ok(output_dir.join(relative))
+}
+
+/// Assemble the HAR document. Each top-level navigation starts a page (redirect
+/// hops stay on the page their chain started); requests are attributed to the
+/// latest page of their tab.
+fn build_har(entries: &[NetworkEntry], bodies: &HashMap<String, ResponseBody>) -> Value {
+    let mut pages = Vec::new();
+    let mut current_page: HashMap<&str, String> = HashMap::new();
+    let mut har_entries = Vec::new();
+    // Request ids whose latest logged hop was a redirect
+    let mut redirecting: HashSet<&str> = HashSet::new();
+
+    for entry in entries {
+        let continues_redirect = match entry.redirect_url {
+            Some(_) => !redirecting.insert(&entry.request_id),
+            None => redirecting.remove(entry.request_id.as_str()),
+        };
+        let new_page = entry.navigation && !continues_redirect;
+        if new_page || !current_page.contains_key(entry.tab_id.as_str()) {
+            let id = format!("page_{}", pages.len() + 1);
+            pages.push(json!({
+                "startedDateTime": iso8601(entry.started_at),
+                "id": id,
+                "title": entry.url,
+                "pageTimings": {},
+            }));
+            current_page.insert(&entry.tab_id, id);
+        }
+        let body = bodies
+            .get(&entry.request_id)
+            .filter(|_| entry.redirect_url.is_none());
+        let mut har_entry = har_entry(entry, body);
+        har_entry["pageref"] = json!(current_page[entry.tab_id.as_str()]);
+        har_entries.push(har_entry);
+    }
+
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "remix-browser", "version": env!("CARGO_PKG_VERSION") },
+            "pages": pages,
+            "entries": har_entries,
+        }
+    })
+}
+
+fn har_entry(entry: &NetworkEntry, body: Option<&ResponseBody>) -> Value {
+    let http_version = entry
+        .http_version
+        .as_deref()
+        .map(str::to_ascii_uppercase)
+        .unwrap_or_default();
+    let request_headers = har_headers(entry.request_headers.as_ref());
+    let response_headers = har_headers(entry.headers.as_ref());
+
+    let mut request = json!({
+        "method": entry.method,
+        "url": entry.url,
+        "httpVersion": http_version,
+        "cookies": [],
+        "headers": request_headers,
+        "queryString": query_string(&entry.url),
+        "headersSize": -1,
+        "bodySize": entry.post_data.as_ref().map_or(0, |d| d.len()),
+    });
+    if let Some(ref text) = entry.post_data {
+        request["postData"] = json!({
+            "mimeType": header_value(&request_headers, "content-type").unwrap_or_default(),
+            "text": text,
+        });
+    }
+
+    let mut content = json!({
+        "size": body.map_or(entry.encoded_size.unwrap_or(0) as usize, |b| b.size),
+        "mimeType": entry.mime_type.as_deref().unwrap_or("x-unknown"),
+    });
+    if let Some(body) = body {
+        content["text"] = json!(body.body);
+        if body.base64_encoded {
+            content["encoding"] = json!("base64");
+        }
+        if body.truncated {
+            content["comment"] = json!(format!("Truncated from {} bytes", body.size));
+        }
+    }
+
+    let mut response = json!({
+        "status": entry.status,
+        "statusText": entry.status_text.as_deref().unwrap_or(""),
+        "httpVersion": http_version,
+        "cookies": [],
+        "headers": response_headers,
+        "content": content,
+        "redirectURL": entry
+            .redirect_url
+            .clone()
+            .or_else(|| header_value(&response_headers, "location"))
+            .unwrap_or_default(),
+        "headersSize": -1,
+        "bodySize": entry.encoded_size.map_or(-1, |s| s as i64),
+    });
+    if let Some(ref error) = entry.error {
+        // Same custom field Chrome DevTools uses for failed requests
+        response["_error"] = json!(error);
+    }
+
+    // HAR requires send/wait/receive to be non-negative; -1 is allowed elsewhere
+    let timings = match entry.timing {
+        Some(ref t) => json!({
+            "blocked": t.blocked,
+            "dns": t.dns,
+            "connect": t.connect,
+            "ssl": t.ssl,
+            "send": t.send.max(0.0),
+            "wait": t.wait.max(0.0),
+            "receive": t.receive.max(0.0),
+        }),
+        None => json!({ "send": 0, "wait": entry.timing_ms.max(0.0), "receive": 0 }),
+    };
+
+    let mut har_entry = json!({
+        "startedDateTime": iso8601(entry.started_at),
+        "time": entry.timing_ms.max(0.0),
+        "request": request,
+        "response": response,
+        "cache": {},
+        "timings": timings,
+    });
+    if let Some(ref resource_type) = entry.resource_type {
+        har_entry["_resourceType"] = json!(resource_type.to_ascii_lowercase());
+    }
+    har_entry
+}
+
+/// CDP headers are a `{name: value}` object with repeated headers joined by newlines.
+fn har_headers(headers: Option<&Value>) -> Vec<Value> {
+    let Some(Value::Object(map)) = headers else {
+        return Vec::new();
+    };
+    map.iter()
+        .flat_map(|(name, value)| {
+            let value = value
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| value.to_string());
+            value
+                .split('\n')
+                .map(|v| json!({ "name": name, "value": v }))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn header_value(headers: &[Value], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .find(|h| {
+            h["name"]
+                .as_str()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        })
+        .and_then(|h| h["value"].as_str().map(str::to_string))
+}
+
+fn query_string(url: &str) -> Vec<Value> {
+    let Some((_, query)) = url.split('#').next().unwrap_or("").split_once('?') else {
+        return Vec::new();
+    };
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            json!({ "name": name, "value": value })
+        })
+        .collect()
+}
+
+/// A recorded response from a HAR file, ready to be served again.
+#[derive(Debug, Clone)]
+pub struct RecordedResponse {
+    pub status: u32,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Responses from a HAR file keyed by method and URL. Repeated requests for the
+/// same key are answered in recorded order, repeating the last one.
+#[derive(Debug)]
+pub struct HarArchive {
+    responses: HashMap<(String, String), Vec<RecordedResponse>>,
+    cursors: std::sync::Mutex<HashMap<(String, String), usize>>,
+    entries: usize,
+}
+
+impl HarArchive {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read HAR file {}", path.display()))?;
+        let har: Value = serde_json::from_str(&text)
+            .with_context(|| format!("{} is not valid JSON", path.display()))?;
+        Self::from_json(&har).with_context(|| format!("Failed to load {}", path.display()))
+    }
+
+    pub fn from_json(har: &Value) -> Result<Self> {
+        use base64::Engine;
+
+        let entries = har["log"]["entries"]
+            .as_array()
+            .context("HAR has no log.entries array")?;
+        let mut responses: HashMap<_, Vec<_>> = HashMap::new();
+        let mut count = 0;
+        for entry in entries {
+            let (Some(method), Some(url)) = (
+                entry["request"]["method"].as_str(),
+                entry["request"]["url"].as_str(),
+            ) else {
+                continue;
+            };
+            let response = &entry["response"];
+            // Failed requests were recorded with status 0; nothing to serve
+            let status = response["status"].as_u64().unwrap_or(0) as u32;
+            if status == 0 {
+                continue;
+            }
+            let content = &response["content"];
+            let text = content["text"].as_str();
+            let body = match text {
+                Some(text) if content["encoding"] == "base64" => {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(text)
+                        .with_context(|| format!("Invalid base64 body for {}", url))?
+                }
+                Some(text) => text.as_bytes().to_vec(),
+                None => Vec::new(),
+            };
+            // A body that was not captured, or cut off by the capture cap, would be
+            // served as a broken response; leave the request to the fallback instead
+            let size = content["size"].as_u64().unwrap_or(0) as usize;
+            let complete = match text {
+                Some(_) => body.len() >= size,
+                None => !expects_body(status),
+            };
+            if !complete {
+                continue;
+            }
+            // Bodies are stored decoded, so the original framing headers no longer apply
+            let headers = response["headers"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|h| Some((h["name"].as_str()?, h["value"].as_str()?)))
+                .filter(|(name, _)| {
+                    !["content-encoding", "content-length", "transfer-encoding"]
+                        .iter()
+                        .any(|skip| name.eq_ignore_ascii_case(skip))
+                })
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            responses
+                .entry(replay_key(method, url))
+                .or_default()
+                .push(RecordedResponse {
+                    status,
+                    headers,
+                    body,
+                });
+            count += 1;
+        }
+        Ok(Self {
+            responses,
+            cursors: std::sync::Mutex::new(HashMap::new()),
+            entries: count,
+        })
+    }
+
+    /// Number of replayable responses.
+    pub fn len(&self) -> usize {
+        self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries == 0
+    }
+
+    pub fn lookup(&self, method: &str, url: &str) -> Option<RecordedResponse> {
+        let key = replay_key(method, url);
+        let recorded = self.responses.get(&key)?;
+        let mut cursors = self.cursors.lock().unwrap();
+        let cursor = cursors.entry(key).or_insert(0);
+        let response = recorded[(*cursor).min(recorded.len() - 1)].clone();
+        *cursor += 1;
+        Some(response)
+    }
+}
+
+/// Whether a response with this status normally carries a body worth replaying.
+fn expects_body(status: u32) -> bool {
+    !matches!(status, 204 | 205 | 304) && !(300..400).contains(&status)
+}
+
+/// Fragments never reach the server, so they do not take part in matching.
+fn replay_key(method: &str, url: &str) -> (String, String) {
+    let url = url.split('#').next().unwrap_or(url);
+    (method.to_ascii_uppercase(), url.to_string())
+}
+
+/// Format seconds since the Unix epoch as an ISO 8601 UTC timestamp with milliseconds.
+fn iso8601(epoch_secs: f64) -> String {
+    let total_ms = (epoch_secs * 1000.0).round().max(0.0) as i64;
+    let (days, ms_of_day) = (
+        total_ms.div_euclid(86_400_000),
+        total_ms.rem_euclid(86_400_000),
+    );
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iso8601() {
+        assert_eq!(iso8601(0.0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(951_782_400.5), "2000-02-29T00:00:00.500Z");
+        assert_eq!(iso8601(1_700_000_000.123), "2023-11-14T22:13:20.123Z");
+    }
+
+    #[test]
+    fn test_output_path_stays_inside_dir() {
+        let dir = Path::new("/tmp/out");
+        assert_eq!(
+            output_path(dir, Some("run/trace.har")).unwrap(),
+            Path::new("/tmp/out/run/trace.har")
+        );
+        assert!(output_path(dir, Some("../trace.har")).is_err());
+        assert!(output_path(dir, Some("/etc/trace.har")).is_err());
+        assert!(output_path(dir, None).unwrap().starts_with(dir));
+    }
+
+    #[test]
+    fn test_archive_replays_in_order() {
+        let har = json!({ "log": { "entries": [
+            { "request": { "method": "GET", "url": "https://a.test/n" },
+              "response": { "status": 200, "headers": [
+                  { "name": "Content-Encoding", "value": "gzip" },
+                  { "name": "Content-Type", "value": "text/plain" }
+              ], "content": { "text": "one" } } },
+            { "request": { "method": "GET", "url": "https://a.test/n" },
+              "response": { "status": 200, "headers": [], "content": { "text": "dHdv", "encoding": "base64" } } },
+            { "request": { "method": "GET", "url": "https://a.test/down" },
+              "response": { "status": 0, "headers": [], "content": {} } },
+            { "request": { "method": "GET", "url": "https://a.test/moved" },
+              "response": { "status": 302, "headers": [
+                  { "name": "Location", "value": "https://a.test/n" }
+              ], "content": { "size": 0 } } },
+        ] } });
+        let archive = HarArchive::from_json(&har).unwrap();
+        assert_eq!(archive.len(), 3);
+
+        let first = archive.lookup("get", "https://a.test/n#top").unwrap();
+        assert_eq!(first.body, b"one");
+        assert_eq!(first.headers, vec![("Content-Type".into(), "text/plain".into())]);
+        assert_eq!(archive.lookup("GET", "https://a.test/n").unwrap().body, b"two");
+        // Past the end: keep serving the last recording
+        assert_eq!(archive.lookup("GET", "https://a.test/n").unwrap().body, b"two");
+        assert!(archive.lookup("POST", "https://a.test/n").is_none());
+        assert!(archive.lookup("GET", "https://a.test/down").is_none());
+        let moved = archive.lookup("GET", "https://a.test/moved").unwrap();
+        assert_eq!(moved.status, 302);
+    }
+
+    #[test]
+    fn test_archive_skips_missing_and_truncated_bodies() {
+        let har = json!({ "log": { "entries": [
+            { "request": { "method": "GET", "url": "https://a.test/no-body" },
+              "response": { "status": 200, "headers": [
+                  { "name": "Content-Length", "value": "5120" }
+              ], "content": { "size": 5120, "mimeType": "text/html" } } },
+            { "request": { "method": "GET", "url": "https://a.test/cut" },
+              "response": { "status": 200, "headers": [], "content": {
+                  "size": 5120, "text": "<html>", "comment": "Truncated from 5120 bytes" } } },
+            { "request": { "method": "GET", "url": "https://a.test/empty" },
+              "response": { "status": 204, "headers": [], "content": { "size": 0 } } },
+        ] } });
+        let archive = HarArchive::from_json(&har).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert!(archive.lookup("GET", "https://a.test/no-body").is_none());
+        assert!(archive.lookup("GET", "https://a.test/cut").is_none());
+        let empty = archive.lookup("GET", "https://a.test/empty").unwrap();
+        assert!(empty.body.is_empty());
+    }
+
+    #[test]
+    fn test_build_har_pages_and_entries() {
+        let entry = |id: &str, url: &str, navigation| NetworkEntry {
+            request_id: id.into(),
+            tab_id: "tab".into(),
+            url: url.into(),
+            method: "GET".into(),
+            status: 200,
+            navigation,
+            headers: Some(json!({ "Set-Cookie": "a=1\nb=2", "Location": "/next" })),
+            ..Default::default()
+        };
+        let entries = vec![
+            entry("1", "https://a.test/", true),
+            entry("2", "https://a.test/app.js?v=2&debug", false),
+            entry("3", "https://b.test/", true),
+        ];
+        let har = build_har(&entries, &HashMap::new());
+        let log = &har["log"];
+
+        assert_eq!(log["version"], "1.2");
+        assert_eq!(log["pages"].as_array().unwrap().len(), 2);
+        assert_eq!(log["entries"][1]["pageref"], "page_1");
+        assert_eq!(log["entries"][2]["pageref"], "page_2");
+        assert_eq!(
+            log["entries"][1]["request"]["queryString"],
+            json!([{ "name": "v", "value": "2" }, { "name": "debug", "value": "" }])
+        );
+        let response = &log["entries"][0]["response"];
+        assert_eq!(response["headers"].as_array().unwrap().len(), 3);
+        assert_eq!(response["redirectURL"], "/next");
+    }
+
+    #[test]
+    fn test_build_har_entry_per_redirect_hop() {
+        let hop = |url: &str, status, redirect_url: Option<&str>| NetworkEntry {
+            request_id: "7".into(),
+            tab_id: "tab".into(),
+            url: url.into(),
+            method: "GET".into(),
+            status,
+            navigation: true,
+            redirect_url: redirect_url.map(String::from),
+            ..Default::default()
+        };
+        let mut entries = vec![
+            hop("http://a.test/", 301, Some("https://a.test/")),
+            hop("https://a.test/", 302, Some("https://a.test/home")),
+            hop("https://a.test/home", 200, None),
+            hop("https://a.test/next", 200, None),
+        ];
+        entries[3].request_id = "8".into();
+        let body = ResponseBody {
+            request_id: "7".into(),
+            url: "https://a.test/home".into(),
+            mime_type: None,
+            base64_encoded: false,
+            size: 4,
+            truncated: false,
+            body: "home".into(),
+        };
+        let bodies = HashMap::from([("7".to_string(), body)]);
+        let har = build_har(&entries, &bodies);
+        let log = &har["log"];
+
+        let har_entries = log["entries"].as_array().unwrap();
+        assert_eq!(har_entries.len(), 4);
+        let statuses: Vec<_> = har_entries
+            .iter()
+            .map(|e| &e["response"]["status"])
+            .collect();
+        assert_eq!(statuses, [301, 302, 200, 200]);
+        assert_eq!(har_entries[0]["response"]["redirectURL"], "https://a.test/");
+        assert_eq!(
+            har_entries[1]["response"]["redirectURL"],
+            "https://a.test/home"
+        );
+        assert_eq!(har_entries[2]["response"]["redirectURL"], "");
+        // The chain is one page; the next navigation starts another
+        let pagerefs: Vec<_> = har_entries.iter().map(|e| &e["pageref"]).collect();
+        assert_eq!(pagerefs, ["page_1", "page_1", "page_1", "page_2"]);
+        // Only the final hop carries the body
+        assert!(har_entries[0]["response"]["content"]["text"].is_null());
+        assert_eq!(har_entries[2]["response"]["content"]["text"], "home");
+    }
+}
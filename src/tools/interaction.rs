@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use chromiumoxide::page::Page;
 use serde::{Deserialize, Serialize};
 
-use crate::interaction::{click, keyboard, scroll};
+use crate::interaction::{click, keyboard, mouse, scroll};
 use crate::selectors::SelectorType;
 
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
@@ -13,6 +13,10 @@ pub struct ClickParams {
     pub selector_type: Option<SelectorType>,
     #[schemars(description = "Mouse button: left, right, or middle")]
     pub button: Option<String>,
+    #[schemars(description = "Number of clicks (2 for double-click, default: 1)")]
+    pub click_count: Option<u32>,
+    #[schemars(description = "Modifier keys held during the click (ctrl, shift, alt, meta)")]
+    pub modifiers: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -25,8 +29,18 @@ pub async fn do_click(page: &Page, params: &ClickParams) -> Result<ClickResult>
     let selector_type = params.selector_type.clone().unwrap_or_default();
     let (selector, selector_type) = crate::selectors::normalize_selector_type(&params.selector, selector_type);
     let button = params.button.as_deref().unwrap_or("left");
+    let click_count = params.click_count.unwrap_or(1);
+    let modifiers = params.modifiers.as_deref().unwrap_or(&[]);
 
-    let result = click::hybrid_click(page, &selector, &selector_type, button).await?;
+    let result = click::hybrid_click(
+        page,
+        &selector,
+        &selector_type,
+        button,
+        click_count,
+        modifiers,
+    )
+    .await?;
 
     Ok(ClickResult {
         success: result.success,
@@ -44,6 +58,10 @@ pub struct TypeTextParams {
     pub selector_type: Option<SelectorType>,
     #[schemars(description = "Clear the field before typing")]
     pub clear_first: Option<bool>,
+    #[schemars(description = "Send a keydown/keypress/keyup per character, with Shift pressed around shifted characters, instead of inserting the text at once (for autocompletes and key-driven editors)")]
+    pub per_character: Option<bool>,
+    #[schemars(description = "Delay between characters in milliseconds (per_character mode only)")]
+    pub delay_ms: Option<u64>,
 }
 
 pub async fn type_text(page: &Page, params: &TypeTextParams) -> Result<bool> {
@@ -57,6 +75,8 @@ pub async fn type_text(page: &Page, params: &TypeTextParams) -> Result<bool> {
         &selector_type,
         &params.text,
         clear_first,
+        params.per_character.unwrap_or(false),
+        params.delay_ms.unwrap_or(0),
     )
     .await?;
 
@@ -74,26 +94,179 @@ pub struct HoverParams {
 pub async fn hover(page: &Page, params: &HoverParams) -> Result<bool> {
     let selector_type = params.selector_type.clone().unwrap_or_default();
     let (selector, selector_type) = crate::selectors::normalize_selector_type(&params.selector, selector_type);
-    let selector_js = click::selector_to_js(&selector, &selector_type)?;
+    let (x, y) = click::element_center(page, &selector, &selector_type, true).await?;
+    mouse::move_to(page, x, y, 0, 0).await?;
+    Ok(true)
+}
 
-    let js = format!(
-        r#"(() => {{
-            const el = {selector_js};
-            if (!el) throw new Error('Element not found');
-            el.scrollIntoView({{ block: 'center', behavior: 'instant' }});
-            const rect = el.getBoundingClientRect();
-            const opts = {{ bubbles: true, clientX: rect.left + rect.width/2, clientY: rect.top + rect.height/2 }};
-            el.dispatchEvent(new MouseEvent('mouseenter', opts));
-            el.dispatchEvent(new MouseEvent('mouseover', opts));
-            el.dispatchEvent(new MouseEvent('mousemove', opts));
-            return true;
-        }})()"#,
-        selector_js = selector_js
-    );
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DragParams {
+    #[schemars(description = "Selector for the element to drag (omit to use from_x/from_y)")]
+    pub from: Option<String>,
+    #[schemars(description = "Selector for the drop target (omit to use to_x/to_y)")]
+    pub to: Option<String>,
+    #[schemars(description = "Type of selector for from/to: css, text, or xpath")]
+    pub selector_type: Option<SelectorType>,
+    #[schemars(description = "Start X in viewport pixels (when from is omitted)")]
+    pub from_x: Option<f64>,
+    #[schemars(description = "Start Y in viewport pixels (when from is omitted)")]
+    pub from_y: Option<f64>,
+    #[schemars(description = "End X in viewport pixels (when to is omitted)")]
+    pub to_x: Option<f64>,
+    #[schemars(description = "End Y in viewport pixels (when to is omitted)")]
+    pub to_y: Option<f64>,
+    #[schemars(description = "Number of intermediate mouse moves (default: 10)")]
+    pub steps: Option<u32>,
+}
 
-    page.evaluate(js.as_str())
-        .await
-        .context("Failed to hover")?;
+#[derive(Debug, Serialize)]
+pub struct DragResult {
+    pub from: (f64, f64),
+    pub to: (f64, f64),
+    pub method_used: String,
+}
+
+async fn drag_point(
+    page: &Page,
+    selector: Option<&str>,
+    selector_type: &SelectorType,
+    x: Option<f64>,
+    y: Option<f64>,
+    which: &str,
+    scroll: bool,
+) -> Result<(f64, f64)> {
+    match (selector, x, y) {
+        (Some(selector), _, _) => {
+            let (selector, selector_type) =
+                crate::selectors::normalize_selector_type(selector, selector_type.clone());
+            click::element_center(page, &selector, &selector_type, scroll).await
+        }
+        (None, Some(x), Some(y)) => Ok((x, y)),
+        _ => anyhow::bail!(
+            "Drag needs '{which}' selector or both {which}_x and {which}_y coordinates"
+        ),
+    }
+}
+
+pub async fn drag(page: &Page, params: &DragParams) -> Result<DragResult> {
+    let selector_type = params.selector_type.clone().unwrap_or_default();
+    let from = drag_point(
+        page,
+        params.from.as_deref(),
+        &selector_type,
+        params.from_x,
+        params.from_y,
+        "from",
+        true,
+    )
+    .await?;
+    // Viewport coordinates only stay valid if nothing scrolls, so the target is
+    // only scrolled to when the source is an element that can be measured again.
+    let to = drag_point(
+        page,
+        params.to.as_deref(),
+        &selector_type,
+        params.to_x,
+        params.to_y,
+        "to",
+        params.from.is_some(),
+    )
+    .await?;
+    let from = match params.from {
+        Some(_) if params.to.is_some() => {
+            drag_point(
+                page,
+                params.from.as_deref(),
+                &selector_type,
+                None,
+                None,
+                "from",
+                false,
+            )
+            .await?
+        }
+        _ => from,
+    };
+
+    let method = mouse::drag(page, from, to, params.steps.unwrap_or(10), 0).await?;
+
+    Ok(DragResult {
+        from,
+        to,
+        method_used: match method {
+            mouse::DragMethod::Mouse => "cdp_mouse",
+            mouse::DragMethod::Html5 => "html5_drag",
+        }
+        .to_string(),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ClickAtParams {
+    #[schemars(description = "X coordinate in viewport CSS pixels")]
+    pub x: f64,
+    #[schemars(description = "Y coordinate in viewport CSS pixels")]
+    pub y: f64,
+    #[schemars(description = "Mouse button: left, right, or middle")]
+    pub button: Option<String>,
+    #[schemars(description = "Number of clicks (2 for double-click, default: 1)")]
+    pub click_count: Option<u32>,
+    #[schemars(description = "Modifier keys held during the click (ctrl, shift, alt, meta)")]
+    pub modifiers: Option<Vec<String>>,
+}
+
+pub async fn click_at(page: &Page, params: &ClickAtParams) -> Result<bool> {
+    let modifiers = keyboard::modifier_flags(params.modifiers.as_deref().unwrap_or(&[]));
+    mouse::click_at(
+        page,
+        params.x,
+        params.y,
+        mouse::parse_button(params.button.as_deref().unwrap_or("left")),
+        params.click_count.unwrap_or(1) as i64,
+        modifiers,
+    )
+    .await?;
+    Ok(true)
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct MoveMouseParams {
+    #[schemars(description = "X coordinate in viewport CSS pixels")]
+    pub x: f64,
+    #[schemars(description = "Y coordinate in viewport CSS pixels")]
+    pub y: f64,
+}
+
+pub async fn move_mouse(page: &Page, params: &MoveMouseParams) -> Result<bool> {
+    mouse::move_to(page, params.x, params.y, 0, 0).await?;
+    Ok(true)
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct WheelParams {
+    #[schemars(description = "X coordinate in viewport CSS pixels where the wheel event happens")]
+    pub x: f64,
+    #[schemars(description = "Y coordinate in viewport CSS pixels where the wheel event happens")]
+    pub y: f64,
+    #[schemars(description = "Horizontal scroll delta in pixels (positive scrolls right)")]
+    pub delta_x: Option<f64>,
+    #[schemars(description = "Vertical scroll delta in pixels (positive scrolls down)")]
+    pub delta_y: Option<f64>,
+    #[schemars(description = "Modifier keys held during the wheel event (e.g. ctrl for zoom)")]
+    pub modifiers: Option<Vec<String>>,
+}
+
+pub async fn wheel(page: &Page, params: &WheelParams) -> Result<bool> {
+    let modifiers = keyboard::modifier_flags(params.modifiers.as_deref().unwrap_or(&[]));
+    mouse::wheel(
+        page,
+        params.x,
+        params.y,
+        params.delta_x.unwrap_or(0.0),
+        params.delta_y.unwrap_or(0.0),
+        modifiers,
+    )
+    .await?;
     Ok(true)
 }
 
@@ -134,7 +307,7 @@ pub async fn select_option(page: &Page, params: &SelectOptionParams) -> Result<b
 
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct PressKeyParams {
-    #[schemars(description = "Key to press (Enter, Tab, ArrowDown, etc.)")]
+    #[schemars(description = "Key to press (Enter, Tab, ArrowDown, F5, Numpad5, a, etc.) or a combo like Control+a")]
     pub key: String,
     #[schemars(description = "Modifier keys (ctrl, shift, alt, meta)")]
     pub modifiers: Option<Vec<String>>,
@@ -1,6 +1,12 @@
 use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::log::{EventEntryAdded, LogEntryLevel, LogEntrySource};
+use chromiumoxide::cdp::js_protocol::runtime::{
+    EventConsoleApiCalled, EventExceptionThrown, ExceptionDetails, RemoteObject, StackTrace,
+};
 use chromiumoxide::page::Page;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -48,27 +54,71 @@ pub async fn execute_js(page: &Page, params: &ExecuteJsParams) -> Result<serde_j
 }
 
 /// Console log entry.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ConsoleEntry {
     pub level: String,
     pub text: String,
     pub timestamp: f64,
+    /// Source URL of the script that made the call.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// 1-based line number within `url`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<i64>,
+    /// Target id of the tab the entry came from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tab_id: Option<String>,
+    /// Formatted stack trace for exceptions and browser-side errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<String>,
+    /// Log domain source (network, security, ...) for browser-side entries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
 }
 
 /// Shared console log storage.
 #[derive(Debug, Clone, Default)]
 pub struct ConsoleLog {
     pub entries: Arc<Mutex<Vec<ConsoleEntry>>>,
+    pub exception_count: Arc<AtomicU64>,
 }
 
 impl ConsoleLog {
     pub fn new() -> Self {
         Self {
             entries: Arc::new(Mutex::new(Vec::new())),
+            exception_count: Arc::new(AtomicU64::new(0)),
         }
     }
 
+    /// Number of exceptions recorded so far. Pass to `exceptions_since` after an
+    /// action to find out what it threw.
+    pub fn exception_mark(&self) -> u64 {
+        self.exception_count.load(Ordering::Relaxed)
+    }
+
+    /// Exceptions recorded after `mark` was taken (oldest first).
+    pub async fn exceptions_since(&self, mark: u64) -> Vec<ConsoleEntry> {
+        let new = self.exception_mark().saturating_sub(mark) as usize;
+        if new == 0 {
+            return Vec::new();
+        }
+        let entries = self.entries.lock().await;
+        let mut found: Vec<ConsoleEntry> = entries
+            .iter()
+            .rev()
+            .filter(|e| e.level == "exception")
+            .take(new)
+            .cloned()
+            .collect();
+        found.reverse();
+        found
+    }
+
     pub async fn add(&self, entry: ConsoleEntry) {
+        if entry.level == "exception" {
+            self.exception_count.fetch_add(1, Ordering::Relaxed);
+        }
         let mut entries = self.entries.lock().await;
         if entries.len() >= 1000 {
             entries.remove(0);
@@ -81,6 +131,7 @@ impl ConsoleLog {
         level: Option<&str>,
         clear: bool,
         pattern: Option<&str>,
+        tab_id: Option<&str>,
     ) -> Vec<ConsoleEntry> {
         let mut entries = self.entries.lock().await;
         let filtered: Vec<ConsoleEntry> = entries
@@ -91,6 +142,11 @@ impl ConsoleLog {
                         return false;
                     }
                 }
+                if let Some(tab_id) = tab_id {
+                    if e.tab_id.as_deref() != Some(tab_id) {
+                        return false;
+                    }
+                }
                 if let Some(pattern) = pattern {
                     if !e.text.contains(pattern) {
                         return false;
@@ -111,7 +167,7 @@ impl ConsoleLog {
 
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ReadConsoleParams {
-    #[schemars(description = "Filter by log level: log, warn, error")]
+    #[schemars(description = "Filter by log level: log, info, warn, error, debug, or exception (uncaught errors and unhandled rejections)")]
     pub level: Option<String>,
     #[schemars(description = "Clear console entries after reading")]
     pub clear: Option<bool>,
@@ -119,6 +175,8 @@ pub struct ReadConsoleParams {
     pub pattern: Option<String>,
     #[schemars(description = "Maximum number of entries to return (default: 100)")]
     pub limit: Option<u32>,
+    #[schemars(description = "Only return entries from this tab (see list_tabs)")]
+    pub tab_id: Option<String>,
 }
 
 pub async fn read_console(
@@ -130,6 +188,7 @@ pub async fn read_console(
             params.level.as_deref(),
             params.clear.unwrap_or(false),
             params.pattern.as_deref(),
+            params.tab_id.as_deref(),
         )
         .await;
 
@@ -147,3 +206,295 @@ pub async fn read_console(
 
     Ok(serde_json::to_value(entries)?)
 }
+
+/// Normalize a CDP console call type to the levels `read_console` filters on.
+fn console_level(call_type: &str) -> &str {
+    match call_type {
+        "warning" => "warn",
+        "assert" => "error",
+        other => other,
+    }
+}
+
+/// Render a console argument the way DevTools prints it inline.
+fn format_remote_object(obj: &RemoteObject) -> String {
+    if let Some(ref value) = obj.value {
+        return match value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+    }
+    if let Some(ref unserializable) = obj.unserializable_value {
+        return unserializable.inner().clone();
+    }
+    if let Some(ref description) = obj.description {
+        return description.clone();
+    }
+    obj.r#type.as_ref().to_string()
+}
+
+/// Join console arguments, applying printf-style substitutions (`%s`, `%d`, `%o`, ...)
+/// from the first argument when it is a format string.
+fn format_console_args(args: &[RemoteObject]) -> String {
+    let mut rendered: Vec<String> = args.iter().map(format_remote_object).collect();
+    let Some(first) = args.first() else {
+        return String::new();
+    };
+    let is_format = matches!(first.value, Some(serde_json::Value::String(ref s)) if s.contains('%'));
+    if !is_format {
+        return rendered.join(" ");
+    }
+
+    let format = rendered.remove(0);
+    let mut rest = rendered.into_iter();
+    let mut out = String::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek().copied() {
+            Some('%') => {
+                chars.next();
+                out.push('%');
+            }
+            Some(spec @ ('s' | 'd' | 'i' | 'f' | 'o' | 'O' | 'c')) => {
+                chars.next();
+                let arg = rest.next().unwrap_or_default();
+                match spec {
+                    'd' | 'i' => out.push_str(
+                        &arg.parse::<f64>()
+                            .map(|n| (n.trunc() as i64).to_string())
+                            .unwrap_or_else(|_| "NaN".to_string()),
+                    ),
+                    // %c applies CSS, which has no textual representation
+                    'c' => {}
+                    _ => out.push_str(&arg),
+                }
+            }
+            _ => out.push('%'),
+        }
+    }
+    for arg in rest {
+        out.push(' ');
+        out.push_str(&arg);
+    }
+    out
+}
+
+/// Render a stack trace as DevTools-style `at fn (url:line:col)` lines.
+fn format_stack_trace(stack: &StackTrace) -> Option<String> {
+    if stack.call_frames.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = stack
+        .call_frames
+        .iter()
+        .map(|f| {
+            let name = if f.function_name.is_empty() {
+                "<anonymous>"
+            } else {
+                f.function_name.as_str()
+            };
+            format!(
+                "    at {} ({}:{}:{})",
+                name,
+                f.url,
+                f.line_number + 1,
+                f.column_number + 1
+            )
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+/// Build the entry for an uncaught exception or unhandled promise rejection.
+fn exception_entry(details: &ExceptionDetails, timestamp: f64, tab_id: &str) -> ConsoleEntry {
+    // `text` is the prefix ("Uncaught", "Uncaught (in promise)"); the thrown value's
+    // description carries the message, with the stack appended for Error objects.
+    let message = details
+        .exception
+        .as_ref()
+        .map(format_remote_object)
+        .and_then(|m| m.lines().next().map(str::to_string))
+        .unwrap_or_default();
+    let text = if message.is_empty() {
+        details.text.clone()
+    } else {
+        format!("{} {}", details.text, message)
+    };
+    let frame = details
+        .stack_trace
+        .as_ref()
+        .and_then(|st| st.call_frames.first());
+
+    ConsoleEntry {
+        level: "exception".to_string(),
+        text,
+        timestamp,
+        url: frame
+            .map(|f| f.url.clone())
+            .or_else(|| details.url.clone())
+            .filter(|u| !u.is_empty()),
+        line: frame
+            .map(|f| f.line_number + 1)
+            .or(Some(details.line_number + 1)),
+        tab_id: Some(tab_id.to_string()),
+        stack: details.stack_trace.as_ref().and_then(format_stack_trace),
+        source: None,
+    }
+}
+
+/// Subscribe to console calls, uncaught exceptions, and browser-side log entries
+/// (CSP violations, failed resource loads) on a page and feed them into the shared ConsoleLog.
+pub async fn start_listening(page: &Page, console_log: ConsoleLog) -> Result<()> {
+    let mut calls = page.event_listener::<EventConsoleApiCalled>().await?;
+    let mut exceptions = page.event_listener::<EventExceptionThrown>().await?;
+    let mut log_entries = page.event_listener::<EventEntryAdded>().await?;
+    let tab_id = page.target_id().as_ref().to_string();
+
+    tokio::spawn(async move {
+        loop {
+            let entry = tokio::select! {
+                Some(call) = calls.next() => {
+                    let frame = call
+                        .stack_trace
+                        .as_ref()
+                        .and_then(|st| st.call_frames.first());
+                    ConsoleEntry {
+                        level: console_level(call.r#type.as_ref()).to_string(),
+                        text: format_console_args(&call.args),
+                        timestamp: *call.timestamp.inner(),
+                        url: frame.map(|f| f.url.clone()).filter(|u| !u.is_empty()),
+                        line: frame.map(|f| f.line_number + 1),
+                        tab_id: Some(tab_id.clone()),
+                        ..Default::default()
+                    }
+                }
+                Some(ex) = exceptions.next() => {
+                    exception_entry(&ex.exception_details, *ex.timestamp.inner(), &tab_id)
+                }
+                Some(added) = log_entries.next() => {
+                    let e = &added.entry;
+                    // Console API calls already arrive via Runtime; keep only browser-side
+                    // problems worth surfacing.
+                    let level = match e.level {
+                        LogEntryLevel::Error => "error",
+                        LogEntryLevel::Warning => "warn",
+                        _ => continue,
+                    };
+                    if e.source == LogEntrySource::Javascript {
+                        continue;
+                    }
+                    ConsoleEntry {
+                        level: level.to_string(),
+                        text: e.text.clone(),
+                        timestamp: *e.timestamp.inner(),
+                        url: e.url.clone(),
+                        line: e.line_number.map(|l| l + 1),
+                        tab_id: Some(tab_id.clone()),
+                        stack: e.stack_trace.as_ref().and_then(format_stack_trace),
+                        source: Some(e.source.as_ref().to_string()),
+                    }
+                }
+                else => break,
+            };
+            console_log.add(entry).await;
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chromiumoxide::cdp::js_protocol::runtime::RemoteObjectType;
+
+    fn string_arg(s: &str) -> RemoteObject {
+        let mut obj = RemoteObject::new(RemoteObjectType::String);
+        obj.value = Some(serde_json::json!(s));
+        obj
+    }
+
+    fn number_arg(n: impl Into<serde_json::Value>) -> RemoteObject {
+        let mut obj = RemoteObject::new(RemoteObjectType::Number);
+        obj.value = Some(n.into());
+        obj
+    }
+
+    #[test]
+    fn test_format_console_args_joins_plain_values() {
+        let args = vec![string_arg("count:"), number_arg(3)];
+        assert_eq!(format_console_args(&args), "count: 3");
+    }
+
+    #[test]
+    fn test_format_console_args_applies_substitutions() {
+        let args = vec![
+            string_arg("%s has %d items (%c)%%"),
+            string_arg("cart"),
+            number_arg(2.7),
+            string_arg("color: red"),
+        ];
+        assert_eq!(format_console_args(&args), "cart has 2 items ()%");
+    }
+
+    #[test]
+    fn test_format_console_args_appends_extra_args() {
+        let args = vec![string_arg("done %s"), string_arg("a"), string_arg("b")];
+        assert_eq!(format_console_args(&args), "done a b");
+    }
+
+    #[tokio::test]
+    async fn test_console_log_filters_by_tab() {
+        let log = ConsoleLog::new();
+        for tab in ["A", "B", "A"] {
+            log.add(ConsoleEntry {
+                level: "log".to_string(),
+                text: format!("from {}", tab),
+                tab_id: Some(tab.to_string()),
+                ..Default::default()
+            })
+            .await;
+        }
+
+        let entries = log.read(None, false, None, Some("A")).await;
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.tab_id.as_deref() == Some("A")));
+    }
+
+    #[tokio::test]
+    async fn test_exceptions_since_mark() {
+        let log = ConsoleLog::new();
+        let exception = |text: &str| ConsoleEntry {
+            level: "exception".to_string(),
+            text: text.to_string(),
+            ..Default::default()
+        };
+        log.add(exception("Uncaught Error: before")).await;
+        let mark = log.exception_mark();
+        assert!(log.exceptions_since(mark).await.is_empty());
+
+        log.add(ConsoleEntry {
+            level: "log".to_string(),
+            text: "noise".to_string(),
+            ..Default::default()
+        })
+        .await;
+        log.add(exception("Uncaught TypeError: x is undefined")).await;
+        log.add(exception("Uncaught (in promise) boom")).await;
+
+        let texts: Vec<String> = log
+            .exceptions_since(mark)
+            .await
+            .into_iter()
+            .map(|e| e.text)
+            .collect();
+        assert_eq!(
+            texts,
+            vec!["Uncaught TypeError: x is undefined", "Uncaught (in promise) boom"]
+        );
+    }
+}
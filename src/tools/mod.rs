@@ -1,9 +1,14 @@
+pub mod block;
 pub mod dom;
+pub mod emulation;
+pub mod har;
 pub mod interaction;
 pub mod javascript;
 pub mod navigation;
 pub mod network;
 pub mod page;
+pub mod route;
 pub mod screenshot;
 pub mod script;
 pub mod snapshot;
+pub mod websocket;
@@ -1,17 +1,46 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::network::{
+    EventLoadingFailed, EventLoadingFinished, EventRequestWillBeSent, EventResponseReceived,
+    LoaderId, ResourceType,
+};
+use chromiumoxide::cdp::browser_protocol::page::{
+    EventFrameNavigated, EventLifecycleEvent, EventNavigatedWithinDocument, FrameId,
+    GetNavigationHistoryParams, NavigateParams as CdpNavigateParams, NavigateReturns,
+    NavigateToHistoryEntryParams, NavigationType, ReloadParams,
+};
+use chromiumoxide::listeners::EventStream;
 use chromiumoxide::page::Page;
+use futures::future::OptionFuture;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
 
 fn default_include_snapshot() -> bool {
     true
 }
 
+/// Default navigation timeout.
+const DEFAULT_TIMEOUT_MS: u64 = 30_000;
+/// How long the network must stay quiet for `networkidle`.
+const NETWORK_IDLE_MS: u64 = 500;
+
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct NavigateParams {
     #[schemars(description = "URL to navigate to")]
     pub url: String,
-    #[schemars(description = "Wait condition: load, domcontentloaded, or networkidle")]
+    #[schemars(
+        description = "When to consider navigation done: commit (response received), domcontentloaded, load (default), or networkidle (no requests in flight for 500ms after load)"
+    )]
     pub wait_until: Option<String>,
+    #[schemars(description = "Navigation timeout in milliseconds (default: 30000)")]
+    pub timeout_ms: Option<u64>,
+    #[schemars(
+        description = "Treat 4xx/5xx responses and network errors (net::ERR_*) as a tool error (default: false)"
+    )]
+    pub fail_on_http_error: Option<bool>,
     #[serde(default = "default_include_snapshot")]
     #[schemars(description = "Include snapshot in navigation tool response (default: true)")]
     pub include_snapshot: bool,
@@ -19,68 +48,532 @@ pub struct NavigateParams {
 
 #[derive(Debug, Serialize)]
 pub struct NavigateResult {
+    /// Final URL after redirects.
     pub url: String,
     pub title: String,
+    /// HTTP status of the main document response (absent for about:, data: and cached restores).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Redirect hops in order, each with the URL that answered and its 3xx status.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub redirects: Vec<RedirectHop>,
+    /// Network error such as `net::ERR_NAME_NOT_RESOLVED` when the document failed to load.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
-pub async fn navigate(page: &Page, params: &NavigateParams) -> Result<NavigateResult> {
-    tracing::info!("Navigating to: {}", params.url);
-    page.goto(&params.url)
-        .await
-        .with_context(|| format!("Failed to navigate to {}", params.url))?;
+#[derive(Debug, Clone, Serialize)]
+pub struct RedirectHop {
+    pub url: String,
+    pub status: u32,
+}
 
-    // Brief settle time after navigation completes.
-    // chromiumoxide's goto() already waits for the page load event.
-    // These additional waits handle post-load JS rendering.
-    match params.wait_until.as_deref() {
-        Some("networkidle") => {
-            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+impl NavigateResult {
+    /// One-line description for tool output, e.g.
+    /// `Example — https://example.com/ (HTTP 200, text/html, redirected from http://example.com/)`.
+    pub fn summary(&self) -> String {
+        let mut out = format!("{} — {}", self.title, self.url);
+        let mut details = Vec::new();
+        if let Some(ref error) = self.error {
+            details.push(format!("failed: {}", error));
         }
-        Some("domcontentloaded") | None => {
-            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        if let Some(status) = self.status {
+            details.push(format!("HTTP {}", status));
         }
-        _ => {}
+        if let Some(ref mime) = self.mime_type {
+            details.push(mime.clone());
+        }
+        if !self.redirects.is_empty() {
+            let hops: Vec<String> = self
+                .redirects
+                .iter()
+                .map(|hop| format!("{} ({})", hop.url, hop.status))
+                .collect();
+            details.push(format!("redirected from {}", hops.join(" → ")));
+        }
+        if !details.is_empty() {
+            out.push_str(&format!(" ({})", details.join(", ")));
+        }
+        out
     }
 
-    let url = page.url().await?.unwrap_or_default();
-    let title = page.get_title().await?.unwrap_or_default();
+    /// Whether the main document came back with a 4xx/5xx status or a network error.
+    pub fn is_error(&self) -> bool {
+        self.error.is_some() || self.status.is_some_and(|s| s >= 400)
+    }
+}
 
-    Ok(NavigateResult { url, title })
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitUntil {
+    Commit,
+    DomContentLoaded,
+    Load,
+    NetworkIdle,
 }
 
-pub async fn go_back(page: &Page) -> Result<NavigateResult> {
-    page.evaluate("window.history.back()")
+impl WaitUntil {
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value.map(|v| v.to_ascii_lowercase()).as_deref() {
+            Some("commit") => Ok(Self::Commit),
+            Some("domcontentloaded") => Ok(Self::DomContentLoaded),
+            Some("load") | None => Ok(Self::Load),
+            Some("networkidle") => Ok(Self::NetworkIdle),
+            Some(other) => anyhow::bail!(
+                "Unknown wait_until '{}'. Use commit, domcontentloaded, load, or networkidle.",
+                other
+            ),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Commit => "commit",
+            Self::DomContentLoaded => "domcontentloaded",
+            Self::Load => "load",
+            Self::NetworkIdle => "networkidle",
+        }
+    }
+}
+
+/// What the watcher learned about a main-frame document request.
+#[derive(Debug, Default)]
+struct DocumentRequest {
+    redirects: Vec<RedirectHop>,
+    status: Option<u32>,
+    mime_type: Option<String>,
+    url: Option<String>,
+    error: Option<String>,
+}
+
+/// Watches lifecycle and network events for one navigation. Create it *before*
+/// starting the navigation so no event is missed.
+struct NavigationWatcher {
+    frame_id: FrameId,
+    /// Loader of the navigation being waited on. `None` until known: `Page.navigate`
+    /// returns it, while reloads adopt the first new main-frame document load.
+    loader_id: Option<LoaderId>,
+    lifecycle: EventStream<EventLifecycleEvent>,
+    requests: EventStream<EventRequestWillBeSent>,
+    responses: EventStream<EventResponseReceived>,
+    finished: EventStream<EventLoadingFinished>,
+    failed: EventStream<EventLoadingFailed>,
+    within_document: EventStream<EventNavigatedWithinDocument>,
+    frame_navigated: EventStream<EventFrameNavigated>,
+    /// `Page.navigate` running alongside the watcher. chromiumoxide only answers
+    /// it once the page has loaded, so earlier events must not wait on it.
+    navigation: Option<JoinHandle<chromiumoxide::Result<NavigateReturns>>>,
+    /// Network error Chrome reported for `Page.navigate` (no document was committed).
+    navigate_error: Option<String>,
+    /// `Page.navigate` finished without a new loader: a same-document navigation.
+    same_document: bool,
+    /// Set when a history navigation finished without loading a new document
+    /// (same-document entry or back/forward cache restore), with the new URL.
+    settled_url: Option<String>,
+    /// In-flight requests: request id → "METHOD url".
+    in_flight: HashMap<String, String>,
+    /// Main-frame document requests keyed by request id (equal to their loader id).
+    documents: HashMap<String, DocumentRequest>,
+    dom_content_loaded: bool,
+    loaded: bool,
+}
+
+impl NavigationWatcher {
+    async fn start(page: &Page) -> Result<Self> {
+        let frame_id = page
+            .mainframe()
+            .await?
+            .context("Page has no main frame")?;
+        Ok(Self {
+            frame_id,
+            loader_id: None,
+            lifecycle: page.event_listener::<EventLifecycleEvent>().await?,
+            requests: page.event_listener::<EventRequestWillBeSent>().await?,
+            responses: page.event_listener::<EventResponseReceived>().await?,
+            finished: page.event_listener::<EventLoadingFinished>().await?,
+            failed: page.event_listener::<EventLoadingFailed>().await?,
+            within_document: page.event_listener::<EventNavigatedWithinDocument>().await?,
+            frame_navigated: page.event_listener::<EventFrameNavigated>().await?,
+            navigation: None,
+            navigate_error: None,
+            same_document: false,
+            settled_url: None,
+            in_flight: HashMap::new(),
+            documents: HashMap::new(),
+            dom_content_loaded: false,
+            loaded: false,
+        })
+    }
+
+    fn is_main_frame(&self, frame_id: Option<&FrameId>) -> bool {
+        frame_id == Some(&self.frame_id)
+    }
+
+    fn is_current_loader(&self, loader_id: &LoaderId) -> bool {
+        self.loader_id.as_ref() == Some(loader_id)
+    }
+
+    fn adopt_loader(&mut self, loader_id: &LoaderId) {
+        if self.loader_id.is_none() && !loader_id.inner().is_empty() {
+            self.loader_id = Some(loader_id.clone());
+        }
+    }
+
+    fn committed(&self) -> bool {
+        let Some(loader_id) = self.loader_id.as_ref() else {
+            return false;
+        };
+        self.documents
+            .get(loader_id.inner())
+            .is_some_and(|doc| doc.status.is_some() || doc.error.is_some())
+            || self.dom_content_loaded
+            || self.loaded
+    }
+
+    fn satisfied(&self, wait_until: WaitUntil) -> bool {
+        if self.settled_url.is_some() || self.same_document {
+            return true;
+        }
+        match wait_until {
+            WaitUntil::Commit => self.committed(),
+            WaitUntil::DomContentLoaded => self.dom_content_loaded,
+            WaitUntil::Load | WaitUntil::NetworkIdle => self.loaded,
+        }
+    }
+
+    /// The document failed outright, so no lifecycle events will follow.
+    fn document_failed(&self) -> bool {
+        self.navigate_error.is_some()
+            || self
+                .loader_id
+                .as_ref()
+                .and_then(|loader_id| self.documents.get(loader_id.inner()))
+                .is_some_and(|doc| doc.error.is_some())
+    }
+
+    fn handle_lifecycle(&mut self, event: &EventLifecycleEvent) {
+        if event.frame_id != self.frame_id {
+            return;
+        }
+        if event.name == "init" {
+            self.adopt_loader(&event.loader_id);
+        }
+        if !self.is_current_loader(&event.loader_id) {
+            return;
+        }
+        match event.name.as_str() {
+            "DOMContentLoaded" => self.dom_content_loaded = true,
+            "load" => self.loaded = true,
+            _ => {}
+        }
+    }
+
+    /// Same-document and cache-restored navigations only count while no new
+    /// document load has started, so a `pushState` during page load is ignored.
+    fn handle_within_document(&mut self, event: &EventNavigatedWithinDocument) {
+        if event.frame_id == self.frame_id && self.loader_id.is_none() {
+            self.settled_url = Some(event.url.clone());
+        }
+    }
+
+    fn handle_frame_navigated(&mut self, event: &EventFrameNavigated) {
+        if event.frame.id == self.frame_id
+            && event.r#type == NavigationType::BackForwardCacheRestore
+            && self.loader_id.is_none()
+        {
+            self.settled_url = Some(event.frame.url.clone());
+        }
+    }
+
+    fn handle_request(&mut self, req: &EventRequestWillBeSent) {
+        let request_id = req.request_id.inner().to_string();
+        let is_document = req.r#type == Some(ResourceType::Document)
+            && self.is_main_frame(req.frame_id.as_ref())
+            && request_id == *req.loader_id.inner();
+        if is_document {
+            self.adopt_loader(&req.loader_id);
+            let doc = self.documents.entry(request_id.clone()).or_default();
+            if let Some(ref redirect) = req.redirect_response {
+                doc.redirects.push(RedirectHop {
+                    url: redirect.url.clone(),
+                    status: redirect.status as u32,
+                });
+            }
+        }
+        self.in_flight.insert(
+            request_id,
+            format!("{} {}", req.request.method, req.request.url),
+        );
+    }
+
+    fn handle_response(&mut self, resp: &EventResponseReceived) {
+        if let Some(doc) = self.documents.get_mut(resp.request_id.inner()) {
+            // file: and data: documents report status 0
+            doc.status = Some(resp.response.status as u32).filter(|s| *s != 0);
+            doc.mime_type = Some(resp.response.mime_type.clone()).filter(|m| !m.is_empty());
+            doc.url = Some(resp.response.url.clone());
+        }
+    }
+
+    /// Record what `Page.navigate` returned: its loader, a same-document
+    /// navigation, or a network error.
+    fn handle_navigate_response(
+        &mut self,
+        response: std::result::Result<
+            chromiumoxide::Result<NavigateReturns>,
+            tokio::task::JoinError,
+        >,
+    ) -> Result<()> {
+        let nav = match response.context("Navigation task failed")? {
+            Ok(nav) => nav,
+            // chromiumoxide gives up on its own timer; keep following a navigation
+            // that already started until our deadline.
+            Err(e) if self.loader_id.is_some() => {
+                tracing::debug!("Page.navigate did not answer: {}", e);
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(error) = nav.error_text {
+            self.navigate_error = Some(error);
+        } else if let Some(loader_id) = nav.loader_id {
+            if !self.is_current_loader(&loader_id) {
+                // An earlier load was adopted before the response arrived
+                self.dom_content_loaded = false;
+                self.loaded = false;
+                self.loader_id = Some(loader_id);
+            }
+        } else {
+            self.same_document = true;
+        }
+        Ok(())
+    }
+
+    fn handle_failed(&mut self, failed: &EventLoadingFailed) {
+        self.in_flight.remove(failed.request_id.inner());
+        if let Some(doc) = self.documents.get_mut(failed.request_id.inner()) {
+            if !failed.canceled.unwrap_or(false) {
+                doc.error = Some(failed.error_text.clone());
+            }
+        }
+    }
+
+    /// Wait until `wait_until` holds for the watched navigation.
+    async fn wait(&mut self, wait_until: WaitUntil, timeout_ms: u64) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
+        let idle = Duration::from_millis(NETWORK_IDLE_MS);
+        let mut idle_since: Option<tokio::time::Instant> = None;
+
+        loop {
+            if self.document_failed() {
+                return Ok(());
+            }
+            if self.satisfied(wait_until) {
+                if wait_until != WaitUntil::NetworkIdle {
+                    return Ok(());
+                }
+                if self.in_flight.is_empty() {
+                    let since = *idle_since.get_or_insert_with(tokio::time::Instant::now);
+                    if since.elapsed() >= idle {
+                        return Ok(());
+                    }
+                } else {
+                    idle_since = None;
+                }
+            }
+
+            // Wake up for the idle check even when no events arrive
+            let wake = match idle_since {
+                Some(since) => (since + idle).min(deadline),
+                None => deadline,
+            };
+
+            tokio::select! {
+                Some(event) = self.lifecycle.next() => self.handle_lifecycle(&event),
+                Some(req) = self.requests.next() => self.handle_request(&req),
+                Some(resp) = self.responses.next() => self.handle_response(&resp),
+                Some(done) = self.finished.next() => {
+                    self.in_flight.remove(done.request_id.inner());
+                }
+                Some(failed) = self.failed.next() => self.handle_failed(&failed),
+                Some(event) = self.within_document.next() => self.handle_within_document(&event),
+                Some(event) = self.frame_navigated.next() => self.handle_frame_navigated(&event),
+                Some(response) = OptionFuture::from(self.navigation.as_mut()) => {
+                    self.navigation = None;
+                    self.handle_navigate_response(response)?;
+                }
+                _ = tokio::time::sleep_until(wake) => {
+                    if tokio::time::Instant::now() >= deadline {
+                        anyhow::bail!("{}", self.timeout_message(wait_until, timeout_ms));
+                    }
+                }
+            }
+        }
+    }
+
+    fn timeout_message(&self, wait_until: WaitUntil, timeout_ms: u64) -> String {
+        let mut msg = format!(
+            "Navigation timed out after {}ms waiting for {}",
+            timeout_ms,
+            wait_until.as_str()
+        );
+        if self.in_flight.is_empty() {
+            return msg;
+        }
+        let mut pending: Vec<&String> = self.in_flight.values().collect();
+        pending.sort();
+        msg.push_str(&format!("; {} request(s) still pending:", pending.len()));
+        for request in pending.iter().take(20) {
+            msg.push_str(&format!("\n- {}", request));
+        }
+        if pending.len() > 20 {
+            msg.push_str(&format!("\n- ... and {} more", pending.len() - 20));
+        }
+        msg
+    }
+
+    /// Build the result for the watched navigation from what was observed.
+    async fn finish(mut self, page: &Page) -> Result<NavigateResult> {
+        let doc = self
+            .loader_id
+            .as_ref()
+            .and_then(|loader_id| self.documents.remove(loader_id.inner()))
+            .unwrap_or_default();
+
+        let title = page.get_title().await?.unwrap_or_default();
+        let url = match doc.url.or(self.settled_url.take()) {
+            Some(url) => url,
+            None => page.url().await?.unwrap_or_default(),
+        };
+
+        Ok(NavigateResult {
+            url,
+            title,
+            status: doc.status,
+            mime_type: doc.mime_type,
+            redirects: doc.redirects,
+            error: self.navigate_error.take().or(doc.error),
+        })
+    }
+}
+
+impl Drop for NavigationWatcher {
+    fn drop(&mut self) {
+        // Early returns (commit, timeouts) leave chromiumoxide still waiting for load
+        if let Some(navigation) = self.navigation.take() {
+            navigation.abort();
+        }
+    }
+}
+
+pub async fn navigate(page: &Page, params: &NavigateParams) -> Result<NavigateResult> {
+    tracing::info!("Navigating to: {}", params.url);
+    let wait_until = WaitUntil::parse(params.wait_until.as_deref())?;
+    let timeout_ms = params.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS);
+
+    let mut watcher = NavigationWatcher::start(page).await?;
+    let navigation = {
+        let page = page.clone();
+        let url = params.url.clone();
+        tokio::spawn(async move {
+            let response = page.execute(CdpNavigateParams::new(url)).await?;
+            Ok(response.result)
+        })
+    };
+    watcher.navigation = Some(navigation);
+    watcher
+        .wait(wait_until, timeout_ms)
         .await
-        .context("Failed to go back")?;
-    // Settle time for history navigation to update the DOM
-    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        .with_context(|| format!("Failed to navigate to {}", params.url))?;
 
-    let url = page.url().await?.unwrap_or_default();
-    let title = page.get_title().await?.unwrap_or_default();
+    let mut result = watcher.finish(page).await?;
+    if result.error.is_some() && result.status.is_none() {
+        // net::ERR_* leaves no committed document to take the URL from
+        result.url = params.url.clone();
+    }
+    check_status(result, params.fail_on_http_error)
+}
 
-    Ok(NavigateResult { url, title })
+/// With `fail_on_http_error`, turn 4xx/5xx and network errors into a tool error.
+fn check_status(result: NavigateResult, fail_on_http_error: Option<bool>) -> Result<NavigateResult> {
+    if fail_on_http_error.unwrap_or(false) && result.is_error() {
+        anyhow::bail!("Navigation failed: {}", result.summary());
+    }
+    Ok(result)
 }
 
-pub async fn go_forward(page: &Page) -> Result<NavigateResult> {
-    page.evaluate("window.history.forward()")
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub index: usize,
+    pub url: String,
+    pub title: String,
+    pub current: bool,
+}
+
+pub async fn get_history(page: &Page) -> Result<Vec<HistoryEntry>> {
+    let history = page
+        .execute(GetNavigationHistoryParams::default())
         .await
-        .context("Failed to go forward")?;
-    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        .context("Failed to read navigation history")?
+        .result;
+    Ok(history
+        .entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| HistoryEntry {
+            index,
+            url: entry.url,
+            title: entry.title,
+            current: index as i64 == history.current_index,
+        })
+        .collect())
+}
 
-    let url = page.url().await?.unwrap_or_default();
-    let title = page.get_title().await?.unwrap_or_default();
+/// Move `delta` entries through the session history and wait for the result:
+/// a document load, a same-document navigation, or a back/forward cache restore.
+async fn traverse_history(page: &Page, delta: i64) -> Result<NavigateResult> {
+    let direction = if delta < 0 { "back" } else { "forward" };
+    let history = page
+        .execute(GetNavigationHistoryParams::default())
+        .await
+        .context("Failed to read navigation history")?
+        .result;
+    let target = history.current_index + delta;
+    let entry = usize::try_from(target)
+        .ok()
+        .and_then(|i| history.entries.get(i))
+        .with_context(|| format!("Cannot go {}: no history entry", direction))?;
 
-    Ok(NavigateResult { url, title })
+    let mut watcher = NavigationWatcher::start(page).await?;
+    page.execute(NavigateToHistoryEntryParams::new(entry.id))
+        .await
+        .with_context(|| format!("Failed to go {}", direction))?;
+    watcher
+        .wait(WaitUntil::Load, DEFAULT_TIMEOUT_MS)
+        .await
+        .with_context(|| format!("Failed to go {} to {}", direction, entry.url))?;
+    watcher.finish(page).await
 }
 
-pub async fn reload(page: &Page) -> Result<NavigateResult> {
-    page.reload().await.context("Failed to reload")?;
-    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+pub async fn go_back(page: &Page) -> Result<NavigateResult> {
+    traverse_history(page, -1).await
+}
 
-    let url = page.url().await?.unwrap_or_default();
-    let title = page.get_title().await?.unwrap_or_default();
+pub async fn go_forward(page: &Page) -> Result<NavigateResult> {
+    traverse_history(page, 1).await
+}
 
-    Ok(NavigateResult { url, title })
+pub async fn reload(page: &Page) -> Result<NavigateResult> {
+    let mut watcher = NavigationWatcher::start(page).await?;
+    page.execute(ReloadParams::default())
+        .await
+        .context("Failed to reload")?;
+    watcher
+        .wait(WaitUntil::Load, DEFAULT_TIMEOUT_MS)
+        .await
+        .context("Failed to reload")?;
+    watcher.finish(page).await
 }
 
 #[derive(Debug, Serialize)]
@@ -140,4 +633,19 @@ mod tests {
 
         assert!(!params.include_snapshot);
     }
+
+    #[test]
+    fn test_wait_until_parse() {
+        assert_eq!(WaitUntil::parse(None).unwrap(), WaitUntil::Load);
+        assert_eq!(WaitUntil::parse(Some("commit")).unwrap(), WaitUntil::Commit);
+        assert_eq!(
+            WaitUntil::parse(Some("DOMContentLoaded")).unwrap(),
+            WaitUntil::DomContentLoaded
+        );
+        assert_eq!(
+            WaitUntil::parse(Some("networkidle")).unwrap(),
+            WaitUntil::NetworkIdle
+        );
+        assert!(WaitUntil::parse(Some("idle")).is_err());
+    }
 }
@@ -1,25 +1,145 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chromiumoxide::cdp::browser_protocol::network::{
-    EnableParams, EventRequestWillBeSent, EventResponseReceived,
+    EnableParams, EventEventSourceMessageReceived, EventLoadingFailed, EventLoadingFinished,
+    EventRequestServedFromCache, EventRequestWillBeSent, EventResponseReceived,
+    EventWebSocketClosed, EventWebSocketCreated, EventWebSocketFrameError,
+    EventWebSocketFrameReceived, EventWebSocketFrameSent, WebSocketFrame,
+    self as cdp_network, GetRequestPostDataParams, ResourceTiming,
 };
 use chromiumoxide::page::Page;
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 
-/// A captured network request/response.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::browser::Tabs;
+use crate::tools::websocket::{SocketMessage, WebSocketLog};
+
+/// Request bodies larger than this are cut off in the log.
+const MAX_POST_DATA: usize = 64 * 1024;
+/// Default cap for `get_response_body`.
+const DEFAULT_MAX_BODY_BYTES: usize = 100_000;
+
+/// A captured network request/response. Requests that never got a response
+/// have status 0 and an `error`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkEntry {
+    /// CDP request id, the key for `get_response_body`.
+    pub request_id: String,
+    /// Target id of the tab that issued the request.
+    pub tab_id: String,
     pub url: String,
     pub method: String,
     pub status: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_text: Option<String>,
+    /// Protocol the response came over, e.g. `http/1.1` or `h2`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Response headers.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub headers: Option<serde_json::Value>,
-    pub body_preview: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_headers: Option<serde_json::Value>,
+    /// Request body, truncated to 64 KiB.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<String>,
+    /// Wall-clock start of the request, in seconds since the epoch.
+    pub started_at: f64,
+    /// Time from request start until the body finished loading (until the
+    /// headers arrived while the body is still loading).
     pub timing_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<RequestTiming>,
+    /// Bytes received over the wire, headers included. Set once loading finishes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoded_size: Option<u64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub from_cache: bool,
+    /// Why the request failed, e.g. `net::ERR_NAME_NOT_RESOLVED` or a CORS error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The request was aborted, by the page or by navigating away.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub canceled: bool,
+    /// Set when the request was blocked, e.g. `client` (block_resources, routes),
+    /// `inspector`, `mixed-content`, `csp`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocked_reason: Option<String>,
+    /// Top-level document load of its tab; starts a new page in HAR exports.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub navigation: bool,
+    /// Where this 3xx hop sent the browser. The next hop is logged as its own
+    /// entry with the same request id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect_url: Option<String>,
+}
+
+impl NetworkEntry {
+    /// Request-side fields; the response and failure handlers fill in the rest.
+    fn from_request(tab_id: &str, req: &EventRequestWillBeSent) -> Self {
+        Self {
+            request_id: req.request_id.inner().to_string(),
+            tab_id: tab_id.to_string(),
+            url: req.request.url.clone(),
+            method: req.request.method.clone(),
+            resource_type: req.r#type.as_ref().map(|t| t.as_ref().to_string()),
+            request_headers: Some(req.request.headers.inner().clone()),
+            started_at: *req.wall_time.inner(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+/// Per-phase breakdown in milliseconds, shaped like HAR timings: `-1` marks a
+/// phase that did not happen (e.g. no DNS lookup on a reused connection).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestTiming {
+    pub blocked: f64,
+    pub dns: f64,
+    pub connect: f64,
+    pub ssl: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+}
+
+impl RequestTiming {
+    /// Phases up to the response headers from CDP's `Response.timing`, whose
+    /// fields are milliseconds relative to `request_time` (`-1` when absent).
+    fn from_resource_timing(t: &ResourceTiming) -> Self {
+        let span = |start: f64, end: f64| {
+            if start < 0.0 || end < 0.0 {
+                -1.0
+            } else {
+                end - start
+            }
+        };
+        let first_phase = [t.dns_start, t.connect_start, t.send_start]
+            .into_iter()
+            .find(|v| *v >= 0.0)
+            .unwrap_or(0.0);
+        Self {
+            blocked: first_phase,
+            dns: span(t.dns_start, t.dns_end),
+            connect: span(t.connect_start, t.connect_end),
+            ssl: span(t.ssl_start, t.ssl_end),
+            send: span(t.send_start, t.send_end),
+            wait: span(t.send_end, t.receive_headers_end),
+            receive: 0.0,
+        }
+    }
 }
 
 /// Shared network log storage.
@@ -29,6 +149,10 @@ pub struct NetworkLog {
     pub enabled: Arc<Mutex<bool>>,
     pub patterns: Arc<Mutex<Vec<String>>>,
     pub pending_count: Arc<AtomicU32>,
+    /// WebSocket frames and EventSource messages.
+    pub websockets: WebSocketLog,
+    /// Capture task per tab, keyed by target id.
+    listeners: Arc<std::sync::Mutex<HashMap<String, JoinHandle<()>>>>,
 }
 
 impl Default for NetworkLog {
@@ -44,6 +168,8 @@ impl NetworkLog {
             enabled: Arc::new(Mutex::new(false)),
             patterns: Arc::new(Mutex::new(Vec::new())),
             pending_count: Arc::new(AtomicU32::new(0)),
+            websockets: WebSocketLog::new(),
+            listeners: Arc::new(std::sync::Mutex::new(HashMap::new())),
         }
     }
 
@@ -60,6 +186,33 @@ impl NetworkLog {
         }
     }
 
+    pub async fn is_enabled(&self) -> bool {
+        *self.enabled.lock().await
+    }
+
+    /// Whether a capture task is running for the tab.
+    pub fn is_listening(&self, tab_id: &str) -> bool {
+        let listeners = self.listeners.lock().unwrap();
+        listeners.get(tab_id).is_some_and(|task| !task.is_finished())
+    }
+
+    /// Stop capturing: tear down every tab's listener and forget in-flight
+    /// requests. Logged entries are kept. Returns how many tabs were capturing.
+    pub async fn disable(&self) -> usize {
+        *self.enabled.lock().await = false;
+        self.patterns.lock().await.clear();
+        let stopped = {
+            let mut listeners = self.listeners.lock().unwrap();
+            listeners
+                .drain()
+                .filter(|(_, task)| !task.is_finished())
+                .map(|(_, task)| task.abort())
+                .count()
+        };
+        self.pending_count.store(0, Ordering::Relaxed);
+        stopped
+    }
+
     pub async fn add(&self, entry: NetworkEntry) {
         let enabled = self.enabled.lock().await;
         if !*enabled {
@@ -82,6 +235,34 @@ impl NetworkLog {
         entries.push(entry);
     }
 
+    /// Log a WebSocket or EventSource event, subject to the same URL patterns.
+    async fn add_socket_message(&self, message: SocketMessage) {
+        let patterns = self.patterns.lock().await;
+        if !patterns.is_empty() && !patterns.iter().any(|p| message.url.contains(p)) {
+            return;
+        }
+        drop(patterns);
+        self.websockets.add(message).await;
+    }
+
+    /// Look up the most recent entry for a CDP request id.
+    pub async fn find(&self, request_id: &str) -> Option<NetworkEntry> {
+        let entries = self.entries.lock().await;
+        entries
+            .iter()
+            .rev()
+            .find(|e| e.request_id == request_id)
+            .cloned()
+    }
+
+    /// Apply `f` to the most recent entry for `request_id`, if it is still logged.
+    async fn update(&self, request_id: &str, f: impl FnOnce(&mut NetworkEntry)) {
+        let mut entries = self.entries.lock().await;
+        if let Some(entry) = entries.iter_mut().rev().find(|e| e.request_id == request_id) {
+            f(entry);
+        }
+    }
+
     pub async fn get_log(
         &self,
         url_pattern: Option<&str>,
@@ -120,12 +301,18 @@ pub struct NetworkEnableParams {
     pub patterns: Option<Vec<String>>,
 }
 
+/// Enable capture and start listening on `pages`; tabs opened later are picked up
+/// as they join the pool. Returns how many tabs are being captured.
 pub async fn network_enable(
+    pages: &[Page],
     network_log: &NetworkLog,
     params: &NetworkEnableParams,
-) -> Result<bool> {
+) -> Result<usize> {
     network_log.enable(params.patterns.clone()).await;
-    Ok(true)
+    for page in pages {
+        start_listening(page, network_log.clone()).await?;
+    }
+    Ok(pages.len())
 }
 
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
@@ -136,7 +323,15 @@ pub struct GetNetworkLogParams {
     pub method: Option<String>,
     #[schemars(description = "Filter by status code")]
     pub status: Option<u32>,
-    #[schemars(description = "Include response headers in output (default: false)")]
+    #[schemars(description = "Only requests issued by this tab (tab_id from list_tabs)")]
+    pub tab_id: Option<String>,
+    #[schemars(
+        description = "Only requests that failed to load: network errors, CORS failures, canceled or blocked requests"
+    )]
+    pub failed_only: Option<bool>,
+    #[schemars(
+        description = "Include request and response headers and request bodies in output (default: false)"
+    )]
     pub include_headers: Option<bool>,
     #[schemars(description = "Maximum number of entries to return (default: 50)")]
     pub limit: Option<u32>,
@@ -153,11 +348,19 @@ pub async fn get_network_log(
             params.status,
         )
         .await;
+    if let Some(ref tab_id) = params.tab_id {
+        entries.retain(|e| e.tab_id == *tab_id);
+    }
+    if params.failed_only.unwrap_or(false) {
+        entries.retain(NetworkEntry::is_failed);
+    }
 
-    // Strip headers unless explicitly requested
+    // Strip headers and request bodies unless explicitly requested
     if !params.include_headers.unwrap_or(false) {
         for entry in &mut entries {
             entry.headers = None;
+            entry.request_headers = None;
+            entry.post_data = None;
         }
     }
 
@@ -177,50 +380,458 @@ pub async fn get_network_log(
     Ok(serde_json::to_value(entries)?)
 }
 
-/// Subscribe to CDP network events on a page and feed entries into the shared NetworkLog.
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetResponseBodyParams {
+    #[schemars(description = "request_id of an entry from get_network_log")]
+    pub request_id: String,
+    #[schemars(description = "Maximum body size to return in bytes (default: 100000)")]
+    pub max_bytes: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseBody {
+    pub request_id: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Binary bodies are returned base64-encoded.
+    pub base64_encoded: bool,
+    /// Full body size in bytes, before truncation.
+    pub size: usize,
+    pub truncated: bool,
+    pub body: String,
+}
+
+/// Fetch the body of a logged response from the tab that loaded it.
+pub async fn get_response_body(
+    tabs: &Tabs,
+    network_log: &NetworkLog,
+    params: &GetResponseBodyParams,
+) -> Result<ResponseBody> {
+    let entry = find_entry(network_log, &params.request_id).await?;
+    let page = tabs.get_page(&entry.tab_id).await?;
+    fetch_response_body(&page, &entry, params.max_bytes).await
+}
+
+/// Like [`get_response_body`], for callers holding a single page rather than
+/// the tab pool. Fails if the request came from another tab.
+pub async fn get_response_body_from_page(
+    page: &Page,
+    network_log: &NetworkLog,
+    params: &GetResponseBodyParams,
+) -> Result<ResponseBody> {
+    let entry = find_entry(network_log, &params.request_id).await?;
+    if page.target_id().as_ref() != entry.tab_id {
+        anyhow::bail!(
+            "Request '{}' was made by tab '{}', not the current page",
+            entry.request_id,
+            entry.tab_id
+        );
+    }
+    fetch_response_body(page, &entry, params.max_bytes).await
+}
+
+async fn find_entry(network_log: &NetworkLog, request_id: &str) -> Result<NetworkEntry> {
+    network_log.find(request_id).await.with_context(|| {
+        format!(
+            "No captured request with id '{}' — use get_network_log to find request ids",
+            request_id
+        )
+    })
+}
+
+pub(crate) async fn fetch_response_body(
+    page: &Page,
+    entry: &NetworkEntry,
+    max_bytes: Option<u32>,
+) -> Result<ResponseBody> {
+    use base64::Engine;
+
+    let returns = page
+        .execute(cdp_network::GetResponseBodyParams::new(entry.request_id.clone()))
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to get response body for {} (bodies are only kept while the page that loaded them is open)",
+                entry.url
+            )
+        })?
+        .result;
+
+    let max_bytes = max_bytes.map_or(DEFAULT_MAX_BODY_BYTES, |n| n as usize);
+    let (body, size, truncated) = if returns.base64_encoded {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let bytes = engine
+            .decode(&returns.body)
+            .context("Response body is not valid base64")?;
+        let truncated = bytes.len() > max_bytes;
+        let body = if truncated {
+            engine.encode(&bytes[..max_bytes])
+        } else {
+            returns.body
+        };
+        (body, bytes.len(), truncated)
+    } else {
+        let size = returns.body.len();
+        let body = truncate_utf8(&returns.body, max_bytes).to_string();
+        let truncated = body.len() < size;
+        (body, size, truncated)
+    };
+
+    Ok(ResponseBody {
+        request_id: entry.request_id.clone(),
+        url: entry.url.clone(),
+        mime_type: entry.mime_type.clone(),
+        base64_encoded: returns.base64_encoded,
+        size,
+        truncated,
+        body,
+    })
+}
+
+/// Cut `s` to at most `max` bytes without splitting a character.
+pub(crate) fn truncate_utf8(s: &str, max: usize) -> &str {
+    if s.len() <= max {
+        return s;
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Whether `req` loads the tab's top-level document (not an iframe or subresource).
+async fn is_navigation(page: &Page, req: &EventRequestWillBeSent) -> bool {
+    let is_document = req.r#type.as_ref().map(|t| t.as_ref()) == Some("Document");
+    is_document && req.frame_id.is_some() && page.mainframe().await.ok().flatten() == req.frame_id
+}
+
+/// Request body from the `requestWillBeSent` event, or from the browser when
+/// the event omitted it (large bodies are not inlined).
+async fn post_data(page: &Page, req: &EventRequestWillBeSent) -> Option<String> {
+    use base64::Engine;
+
+    if !req.request.has_post_data.unwrap_or(false) {
+        return None;
+    }
+    let data = match req.request.post_data_entries {
+        Some(ref parts) if !parts.is_empty() => {
+            let engine = base64::engine::general_purpose::STANDARD;
+            let mut bytes = Vec::new();
+            for part in parts.iter().filter_map(|p| p.bytes.as_ref()) {
+                bytes.extend(engine.decode(AsRef::<str>::as_ref(part)).ok()?);
+            }
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        _ => {
+            page.execute(GetRequestPostDataParams::new(req.request_id.clone()))
+                .await
+                .ok()?
+                .result
+                .post_data
+        }
+    };
+    Some(truncate_utf8(&data, MAX_POST_DATA).to_string())
+}
+
+/// Subscribe to CDP network events on a page and feed entries into the shared
+/// NetworkLog. Does nothing if the page is already being captured.
 pub async fn start_listening(page: &Page, network_log: NetworkLog) -> Result<()> {
+    let tab_id = page.target_id().as_ref().to_string();
+    if network_log.is_listening(&tab_id) {
+        return Ok(());
+    }
+
     // Enable CDP Network domain on the page
     page.execute(EnableParams::default()).await?;
 
-    // Subscribe to request + response events
+    // Subscribe to request, response, completion and failure events
     let mut requests = page.event_listener::<EventRequestWillBeSent>().await?;
     let mut responses = page.event_listener::<EventResponseReceived>().await?;
+    let mut finished = page.event_listener::<EventLoadingFinished>().await?;
+    let mut failed = page.event_listener::<EventLoadingFailed>().await?;
+    let mut from_cache = page.event_listener::<EventRequestServedFromCache>().await?;
+    // ...and to WebSocket and EventSource traffic
+    let mut ws_created = page.event_listener::<EventWebSocketCreated>().await?;
+    let mut ws_sent = page.event_listener::<EventWebSocketFrameSent>().await?;
+    let mut ws_received = page.event_listener::<EventWebSocketFrameReceived>().await?;
+    let mut ws_error = page.event_listener::<EventWebSocketFrameError>().await?;
+    let mut ws_closed = page.event_listener::<EventWebSocketClosed>().await?;
+    let mut sse_messages = page.event_listener::<EventEventSourceMessageReceived>().await?;
 
     // Spawn background task: collect requests in a HashMap keyed by request_id,
-    // then when a response arrives, merge into a NetworkEntry and add to the log
+    // log an entry when the response headers arrive (or when the request fails
+    // without one), and complete its timing and size once loading ends.
+    // A request counts as pending until its response, failure, or completion.
     let log = network_log.clone();
     let pending_counter = network_log.pending_count.clone();
-    tokio::spawn(async move {
+    let page = page.clone();
+    let task_tab_id = tab_id.clone();
+    let task = async move {
+        let tab_id = task_tab_id;
         let mut pending_map: HashMap<String, Arc<EventRequestWillBeSent>> = HashMap::new();
+        // When the response headers arrived (ms, monotonic), for requests awaiting loadingFinished
+        let mut loading: HashMap<String, Option<f64>> = HashMap::new();
+        // Requests reported as served from cache before their response arrived
+        let mut cached: HashSet<String> = HashSet::new();
+        // Open WebSocket connections: request id → URL
+        let mut sockets: HashMap<String, String> = HashMap::new();
 
         loop {
             tokio::select! {
                 Some(req) = requests.next() => {
-                    pending_counter.fetch_add(1, Ordering::Relaxed);
-                    pending_map.insert(req.request_id.inner().to_string(), req);
+                    let request_id = req.request_id.inner().to_string();
+                    // A redirect reuses the request id; only count it once
+                    let Some(prev) = pending_map.insert(request_id, req.clone()) else {
+                        pending_counter.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    };
+                    // ...but log the hop that answered with the redirect as its own entry
+                    let Some(ref redirect) = req.redirect_response else {
+                        continue;
+                    };
+                    let timing = redirect.timing.as_ref();
+                    let entry = NetworkEntry {
+                        status: redirect.status as u32,
+                        status_text: Some(redirect.status_text.clone()),
+                        http_version: redirect.protocol.clone(),
+                        mime_type: Some(redirect.mime_type.clone()),
+                        headers: Some(redirect.headers.inner().clone()),
+                        post_data: post_data(&page, &prev).await,
+                        timing_ms: (req.timestamp.inner() - prev.timestamp.inner()) * 1000.0,
+                        timing: timing.map(RequestTiming::from_resource_timing),
+                        encoded_size: Some(redirect.encoded_data_length as u64),
+                        navigation: is_navigation(&page, &prev).await,
+                        redirect_url: Some(req.request.url.clone()),
+                        ..NetworkEntry::from_request(&tab_id, &prev)
+                    };
+                    log.add(entry).await;
+                }
+                Some(event) = from_cache.next() => {
+                    let request_id = event.request_id.inner().to_string();
+                    if loading.contains_key(&request_id) {
+                        log.update(&request_id, |entry| entry.from_cache = true).await;
+                    } else if pending_map.contains_key(&request_id) {
+                        cached.insert(request_id);
+                    }
                 }
                 Some(resp) = responses.next() => {
                     let request_id = resp.request_id.inner().to_string();
-                    let method = pending_map.get(&request_id)
-                        .map(|r| r.request.method.clone())
-                        .unwrap_or_default();
-                    if pending_map.remove(&request_id).is_some() {
-                        pending_counter.fetch_sub(1, Ordering::Relaxed);
+                    let Some(req) = pending_map.get(&request_id).cloned() else {
+                        continue;
+                    };
+                    if loading.contains_key(&request_id) {
+                        continue;
                     }
+                    pending_counter.fetch_sub(1, Ordering::Relaxed);
+                    let timing = resp.response.timing.as_ref();
+                    loading.insert(
+                        request_id.clone(),
+                        timing.map(|t| t.request_time * 1000.0 + t.receive_headers_end),
+                    );
                     let entry = NetworkEntry {
                         url: resp.response.url.clone(),
-                        method,
                         status: resp.response.status as u32,
+                        status_text: Some(resp.response.status_text.clone()),
+                        http_version: resp.response.protocol.clone(),
+                        resource_type: Some(resp.r#type.as_ref().to_string()),
+                        mime_type: Some(resp.response.mime_type.clone()),
                         headers: Some(resp.response.headers.inner().clone()),
-                        body_preview: String::new(),
-                        timing_ms: 0.0,
+                        post_data: post_data(&page, &req).await,
+                        timing_ms: (resp.timestamp.inner() - req.timestamp.inner()) * 1000.0,
+                        timing: timing.map(RequestTiming::from_resource_timing),
+                        from_cache: cached.remove(&request_id)
+                            || resp.response.from_disk_cache.unwrap_or(false),
+                        navigation: is_navigation(&page, &req).await,
+                        ..NetworkEntry::from_request(&tab_id, &req)
                     };
                     log.add(entry).await;
                 }
+                Some(done) = finished.next() => {
+                    let request_id = done.request_id.inner().to_string();
+                    let Some(req) = pending_map.remove(&request_id) else {
+                        continue;
+                    };
+                    cached.remove(&request_id);
+                    let Some(headers_at) = loading.remove(&request_id) else {
+                        // Finished without a response event; stop counting it as pending
+                        pending_counter.fetch_sub(1, Ordering::Relaxed);
+                        continue;
+                    };
+                    let finished_at = *done.timestamp.inner();
+                    log.update(&request_id, |entry| {
+                        entry.timing_ms = (finished_at - req.timestamp.inner()) * 1000.0;
+                        entry.encoded_size = Some(done.encoded_data_length as u64);
+                        if let (Some(timing), Some(headers_at)) = (entry.timing.as_mut(), headers_at) {
+                            timing.receive = (finished_at * 1000.0 - headers_at).max(0.0);
+                        }
+                    })
+                    .await;
+                }
+                Some(fail) = failed.next() => {
+                    let request_id = fail.request_id.inner().to_string();
+                    let Some(req) = pending_map.remove(&request_id) else {
+                        continue;
+                    };
+                    cached.remove(&request_id);
+                    let error = match fail.cors_error_status {
+                        Some(ref cors) => format!("{} (CORS: {})", fail.error_text, cors.cors_error.as_ref()),
+                        None => fail.error_text.clone(),
+                    };
+                    let canceled = fail.canceled.unwrap_or(false);
+                    // Requests failed by interception (routes, block_resources, HAR replay)
+                    // carry no blockedReason of their own
+                    let blocked_reason = fail
+                        .blocked_reason
+                        .as_ref()
+                        .map(|r| r.as_ref().to_string())
+                        .or_else(|| {
+                            fail.error_text
+                                .contains("ERR_BLOCKED_BY_CLIENT")
+                                .then(|| "client".to_string())
+                        });
+                    let timing_ms = (fail.timestamp.inner() - req.timestamp.inner()) * 1000.0;
+
+                    if loading.remove(&request_id).is_some() {
+                        // Failed while the body was loading: the entry already exists
+                        log.update(&request_id, |entry| {
+                            entry.error = Some(error);
+                            entry.canceled = canceled;
+                            entry.blocked_reason = blocked_reason;
+                            entry.timing_ms = timing_ms;
+                        })
+                        .await;
+                    } else {
+                        pending_counter.fetch_sub(1, Ordering::Relaxed);
+                        let entry = NetworkEntry {
+                            resource_type: Some(fail.r#type.as_ref().to_string()),
+                            post_data: post_data(&page, &req).await,
+                            timing_ms,
+                            error: Some(error),
+                            canceled,
+                            blocked_reason,
+                            navigation: is_navigation(&page, &req).await,
+                            ..NetworkEntry::from_request(&tab_id, &req)
+                        };
+                        log.add(entry).await;
+                    }
+                }
+                Some(event) = ws_created.next() => {
+                    let connection_id = event.request_id.inner().to_string();
+                    let message =
+                        SocketMessage::new("websocket", &connection_id, &tab_id, &event.url, "open");
+                    sockets.insert(connection_id, event.url.clone());
+                    log.add_socket_message(message).await;
+                }
+                Some(event) = ws_sent.next() => {
+                    let message =
+                        ws_frame_message(&sockets, &tab_id, &event.request_id, "sent", &event.response);
+                    log.add_socket_message(message).await;
+                }
+                Some(event) = ws_received.next() => {
+                    let message =
+                        ws_frame_message(&sockets, &tab_id, &event.request_id, "received", &event.response);
+                    log.add_socket_message(message).await;
+                }
+                Some(event) = ws_error.next() => {
+                    let connection_id = event.request_id.inner();
+                    let url = sockets.get(connection_id).map(String::as_str).unwrap_or_default();
+                    let message = SocketMessage::new("websocket", connection_id, &tab_id, url, "error")
+                        .with_payload(None, &event.error_message);
+                    log.add_socket_message(message).await;
+                }
+                Some(event) = ws_closed.next() => {
+                    let connection_id = event.request_id.inner();
+                    let url = sockets.remove(connection_id).unwrap_or_default();
+                    let message = SocketMessage::new("websocket", connection_id, &tab_id, &url, "close");
+                    log.add_socket_message(message).await;
+                }
+                Some(event) = sse_messages.next() => {
+                    let connection_id = event.request_id.inner();
+                    let url = match pending_map.get(connection_id) {
+                        Some(req) => req.request.url.clone(),
+                        None => log.find(connection_id).await.map(|e| e.url).unwrap_or_default(),
+                    };
+                    let mut message =
+                        SocketMessage::new("eventsource", connection_id, &tab_id, &url, "message")
+                            .with_payload(Some("received"), &event.data);
+                    message.event_name = Some(event.event_name.clone());
+                    message.event_id = (!event.event_id.is_empty()).then(|| event.event_id.clone());
+                    log.add_socket_message(message).await;
+                }
                 else => break,
             }
         }
-    });
+
+        // The tab closed: requests still in flight will never complete
+        let in_flight = pending_map.keys().filter(|id| !loading.contains_key(*id)).count();
+        pending_counter.fetch_sub(in_flight as u32, Ordering::Relaxed);
+    };
+
+    // Another caller may have attached while we subscribed; keep only one task
+    let mut listeners = network_log.listeners.lock().unwrap();
+    if listeners.get(&tab_id).is_some_and(|task| !task.is_finished()) {
+        return Ok(());
+    }
+    listeners.retain(|_, task| !task.is_finished());
+    listeners.insert(tab_id, tokio::spawn(task));
 
     Ok(())
 }
+
+fn ws_frame_message(
+    sockets: &HashMap<String, String>,
+    tab_id: &str,
+    request_id: &cdp_network::RequestId,
+    direction: &str,
+    frame: &WebSocketFrame,
+) -> SocketMessage {
+    let connection_id = request_id.inner();
+    let url = sockets.get(connection_id).map(String::as_str).unwrap_or_default();
+    let mut message = SocketMessage::new("websocket", connection_id, tab_id, url, "message")
+        .with_payload(Some(direction), &frame.payload_data);
+    message.opcode = Some(frame.opcode as u8);
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_timing_phases() {
+        let mut t: ResourceTiming = serde_json::from_value(serde_json::json!({
+            "requestTime": 100.0, "proxyStart": -1, "proxyEnd": -1,
+            "dnsStart": 2, "dnsEnd": 5, "connectStart": 5, "connectEnd": 20,
+            "sslStart": 10, "sslEnd": 20, "workerStart": -1, "workerReady": -1,
+            "workerFetchStart": -1, "workerRespondWithSettled": -1,
+            "sendStart": 21, "sendEnd": 22, "pushStart": 0, "pushEnd": 0,
+            "receiveHeadersStart": 60, "receiveHeadersEnd": 62
+        }))
+        .unwrap();
+        let timing = RequestTiming::from_resource_timing(&t);
+        assert_eq!(
+            timing,
+            RequestTiming { blocked: 2.0, dns: 3.0, connect: 15.0, ssl: 10.0, send: 1.0, wait: 40.0, receive: 0.0 }
+        );
+
+        // Reused connection: no DNS, connect or TLS phases
+        t.dns_start = -1.0;
+        t.dns_end = -1.0;
+        t.connect_start = -1.0;
+        t.connect_end = -1.0;
+        t.ssl_start = -1.0;
+        t.ssl_end = -1.0;
+        let timing = RequestTiming::from_resource_timing(&t);
+        assert_eq!((timing.blocked, timing.dns, timing.connect, timing.ssl), (21.0, -1.0, -1.0, -1.0));
+    }
+
+    #[test]
+    fn test_truncate_utf8() {
+        assert_eq!(truncate_utf8("hello", 10), "hello");
+        assert_eq!(truncate_utf8("hello", 3), "hel");
+        // 'é' is two bytes; never split it
+        assert_eq!(truncate_utf8("café", 4), "caf");
+    }
+}
@@ -1,7 +1,7 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use crate::browser::BrowserSession;
+use crate::browser::Tabs;
 
 #[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct NewTabParams {
@@ -11,14 +11,16 @@ pub struct NewTabParams {
 
 #[derive(Debug, Serialize)]
 pub struct TabInfo {
+    pub index: usize,
     pub tab_id: String,
     pub url: String,
     pub title: String,
+    pub active: bool,
 }
 
-pub async fn new_tab(session: &BrowserSession, params: &NewTabParams) -> Result<String> {
+pub async fn new_tab(tabs: &Tabs, params: &NewTabParams) -> Result<String> {
     let url = params.url.as_deref().unwrap_or("about:blank");
-    let page = session.new_page(url).await?;
+    let page = tabs.new_page(url).await?;
     Ok(page.target_id().as_ref().to_string())
 }
 
@@ -28,30 +30,50 @@ pub struct CloseTabParams {
     pub tab_id: Option<String>,
 }
 
-pub async fn close_tab(session: &BrowserSession, params: &CloseTabParams) -> Result<bool> {
-    let mut pool = session.pool.lock().await;
-    if let Some(ref tab_id) = params.tab_id {
-        pool.remove_page(tab_id);
-    } else {
-        let active = pool.active_page().clone();
-        let target_id = active.target_id().as_ref().to_string();
-        active.close().await.context("Failed to close tab")?;
-        pool.remove_page(&target_id);
-    }
+pub async fn close_tab(tabs: &Tabs, params: &CloseTabParams) -> Result<bool> {
+    let target_id = match params.tab_id {
+        Some(ref tab_id) => tab_id.clone(),
+        None => tabs.active_page().await?.target_id().as_ref().to_string(),
+    };
+    tabs.close_page(&target_id).await?;
     Ok(true)
 }
 
-pub async fn list_tabs(session: &BrowserSession) -> Result<Vec<TabInfo>> {
-    let pool = session.pool.lock().await;
-    let mut tabs = Vec::new();
-    for page in pool.list_pages() {
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct SwitchTabParams {
+    #[schemars(description = "ID of the tab to switch to (from list_tabs)")]
+    pub tab_id: Option<String>,
+    #[schemars(description = "Index of the tab to switch to (from list_tabs), used when tab_id is omitted")]
+    pub index: Option<u32>,
+}
+
+pub async fn switch_tab(tabs: &Tabs, params: &SwitchTabParams) -> Result<TabInfo> {
+    let page = match (&params.tab_id, params.index) {
+        (Some(tab_id), _) => tabs.select_by_target_id(tab_id).await?,
+        (None, Some(index)) => tabs.select_index(index as usize).await?,
+        (None, None) => anyhow::bail!("Provide tab_id or index"),
+    };
+    let tab_id = page.target_id().as_ref().to_string();
+    list_tabs(tabs)
+        .await?
+        .into_iter()
+        .find(|t| t.tab_id == tab_id)
+        .ok_or_else(|| anyhow::anyhow!("Tab '{}' closed while switching", tab_id))
+}
+
+pub async fn list_tabs(tabs: &Tabs) -> Result<Vec<TabInfo>> {
+    let (pages, active) = tabs.pages().await;
+    let mut result = Vec::new();
+    for (index, page) in pages.iter().enumerate() {
         let url = page.url().await.unwrap_or(None).unwrap_or_default();
         let title = page.get_title().await.unwrap_or(None).unwrap_or_default();
-        tabs.push(TabInfo {
+        result.push(TabInfo {
+            index,
             tab_id: page.target_id().as_ref().to_string(),
             url,
             title,
+            active: index == active,
         });
     }
-    Ok(tabs)
+    Ok(result)
 }
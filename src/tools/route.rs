@@ -0,0 +1,695 @@
+use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::fetch::{
+    self, ContinueRequestParams, EventRequestPaused, FailRequestParams, FulfillRequestParams,
+    HeaderEntry, RequestPattern, RequestStage,
+};
+use chromiumoxide::cdp::browser_protocol::network::ErrorReason;
+use chromiumoxide::page::Page;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::tools::block::BlockRules;
+use crate::tools::har::HarArchive;
+
+/// An interception rule applied to every tab.
+#[derive(Debug, Clone, Serialize)]
+pub struct Route {
+    pub id: u32,
+    pub url_pattern: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_types: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub action: RouteAction,
+    /// Requests handled by this route so far.
+    pub hits: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum RouteAction {
+    Fulfill {
+        status: u32,
+        headers: BTreeMap<String, String>,
+        #[serde(skip)]
+        body: Arc<Vec<u8>>,
+        body_bytes: usize,
+    },
+    Abort {
+        error_reason: String,
+    },
+    Continue {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        method: Option<String>,
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        post_data: Option<String>,
+    },
+}
+
+impl Route {
+    fn matches(&self, url: &str, resource_type: &str) -> bool {
+        let type_matches = self
+            .resource_types
+            .as_ref()
+            .is_none_or(|types| types.iter().any(|t| t.eq_ignore_ascii_case(resource_type)));
+        type_matches && url_matches(&self.url_pattern, url)
+    }
+}
+
+/// `*` matches any run of characters and `?` a single one; a pattern without
+/// wildcards matches as a substring, like `NetworkLog` capture patterns.
+pub(crate) fn url_matches(pattern: &str, url: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return url.contains(pattern);
+    }
+    let mut regex = String::from("^");
+    for c in pattern.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex::Regex::new(&regex).is_ok_and(|re| re.is_match(url))
+}
+
+/// Serves requests from a recorded HAR file. Explicit routes still take precedence.
+#[derive(Debug)]
+pub struct HarReplay {
+    pub path: String,
+    archive: HarArchive,
+    /// Let requests missing from the archive reach the network instead of failing them.
+    pub fallback: bool,
+    served: AtomicU32,
+    missed: AtomicU32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayStatus {
+    pub path: String,
+    pub entries: usize,
+    pub fallback: bool,
+    pub served: u32,
+    pub missed: u32,
+}
+
+impl HarReplay {
+    pub fn load(path: &str, fallback: bool) -> Result<Self> {
+        let archive = HarArchive::load(std::path::Path::new(path))?;
+        if archive.is_empty() {
+            anyhow::bail!("{} has no replayable responses", path);
+        }
+        Ok(Self {
+            path: path.to_string(),
+            archive,
+            fallback,
+            served: AtomicU32::new(0),
+            missed: AtomicU32::new(0),
+        })
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        ReplayStatus {
+            path: self.path.clone(),
+            entries: self.archive.len(),
+            fallback: self.fallback,
+            served: self.served.load(Ordering::Relaxed),
+            missed: self.missed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Shared route table. Tabs pause requests through the Fetch domain only while
+/// at least one route, blocking rule set, or HAR replay exists. Precedence:
+/// explicit routes, then blocking, then replay.
+#[derive(Debug, Clone)]
+pub struct Routes {
+    rules: Arc<Mutex<Vec<Route>>>,
+    next_id: Arc<AtomicU32>,
+    replay: Arc<Mutex<Option<Arc<HarReplay>>>>,
+    blocking: Arc<Mutex<Option<Arc<BlockRules>>>>,
+}
+
+impl Default for Routes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Routes {
+    pub fn new() -> Self {
+        Self {
+            rules: Arc::new(Mutex::new(Vec::new())),
+            next_id: Arc::new(AtomicU32::new(1)),
+            replay: Arc::new(Mutex::new(None)),
+            blocking: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub async fn list(&self) -> Vec<Route> {
+        self.rules.lock().await.clone()
+    }
+
+    async fn is_active(&self) -> bool {
+        !self.rules.lock().await.is_empty()
+            || self.replay.lock().await.is_some()
+            || self.blocking.lock().await.is_some()
+    }
+
+    pub async fn replay(&self) -> Option<Arc<HarReplay>> {
+        self.replay.lock().await.clone()
+    }
+
+    /// Start (or with `None`, stop) serving requests from a HAR file. Tabs opened
+    /// afterwards pick it up; call [`sync_pages`] for the ones already open.
+    pub async fn set_replay(&self, replay: Option<HarReplay>) {
+        *self.replay.lock().await = replay.map(Arc::new);
+    }
+
+    pub async fn blocking(&self) -> Option<Arc<BlockRules>> {
+        self.blocking.lock().await.clone()
+    }
+
+    /// Replace (or with `None`, clear) the resource blocking rules.
+    pub async fn set_blocking(&self, rules: Option<BlockRules>) {
+        *self.blocking.lock().await = rules.map(Arc::new);
+    }
+
+    /// The route handling a request, counting the hit. Later routes take precedence.
+    async fn take_match(&self, url: &str, resource_type: &str) -> Option<Route> {
+        let mut rules = self.rules.lock().await;
+        let route = rules
+            .iter_mut()
+            .rev()
+            .find(|r| r.matches(url, resource_type))?;
+        route.hits += 1;
+        Some(route.clone())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct RouteParams {
+    #[schemars(
+        description = "URL to match: a glob where * matches anything (e.g. '*/api/users*'), or a plain substring"
+    )]
+    pub url_pattern: String,
+    #[schemars(
+        description = "fulfill (mock a response), abort (fail the request), or continue (send it on, optionally modified). Default: abort when error_reason is set, continue when url/method/post_data is set, otherwise fulfill."
+    )]
+    pub action: Option<String>,
+    #[schemars(description = "Only match these resource types, e.g. ['xhr', 'fetch', 'image']")]
+    pub resource_types: Option<Vec<String>>,
+    #[schemars(description = "HTTP status to fulfill with (default: 200)")]
+    pub status: Option<u32>,
+    #[schemars(
+        description = "Response headers to fulfill with, or request headers to add/override when continuing"
+    )]
+    pub headers: Option<BTreeMap<String, String>>,
+    #[schemars(description = "Response body to fulfill with")]
+    pub body: Option<String>,
+    #[schemars(description = "Path of a file to fulfill with instead of body")]
+    pub body_file: Option<String>,
+    #[schemars(
+        description = "Content-Type to fulfill with (default: guessed from the body or file extension)"
+    )]
+    pub content_type: Option<String>,
+    #[schemars(
+        description = "Network error to abort with: Failed, Aborted, TimedOut, AccessDenied, ConnectionRefused, ConnectionReset, NameNotResolved, InternetDisconnected, BlockedByClient (default: Failed)"
+    )]
+    pub error_reason: Option<String>,
+    #[schemars(description = "Replacement URL when continuing (must keep the same scheme)")]
+    pub url: Option<String>,
+    #[schemars(description = "Replacement HTTP method when continuing")]
+    pub method: Option<String>,
+    #[schemars(description = "Replacement request body when continuing")]
+    pub post_data: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ReplayHarParams {
+    #[schemars(
+        description = "Path of the HAR file to serve responses from (record one with export_har and include_bodies)"
+    )]
+    pub path: Option<String>,
+    #[schemars(
+        description = "Let requests that are not in the HAR go to the network (default: false — they fail as blocked)"
+    )]
+    pub fallback: Option<bool>,
+    #[schemars(description = "Stop replaying and go back to the live network")]
+    pub stop: Option<bool>,
+}
+
+/// Start or stop HAR replay across `pages` and every tab opened later.
+pub async fn replay_har(
+    pages: &[Page],
+    routes: &Routes,
+    params: &ReplayHarParams,
+) -> Result<Option<ReplayStatus>> {
+    let replay = if params.stop.unwrap_or(false) {
+        None
+    } else {
+        let path = params
+            .path
+            .as_deref()
+            .context("Provide path to a HAR file, or stop: true")?;
+        Some(HarReplay::load(path, params.fallback.unwrap_or(false))?)
+    };
+    let status = replay.as_ref().map(HarReplay::status);
+    routes.set_replay(replay).await;
+    sync_pages(pages, routes).await?;
+    Ok(status)
+}
+
+#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct UnrouteParams {
+    #[schemars(description = "ID of the route to remove (from route or list_routes)")]
+    pub id: Option<u32>,
+    #[schemars(description = "Remove every route with exactly this url_pattern")]
+    pub url_pattern: Option<String>,
+    #[schemars(description = "Remove all routes")]
+    pub all: Option<bool>,
+}
+
+fn build_action(params: &RouteParams) -> Result<RouteAction> {
+    let action = match params.action.as_deref() {
+        Some(action) => action.to_ascii_lowercase(),
+        None if params.error_reason.is_some() => "abort".into(),
+        None if params.url.is_some() || params.method.is_some() || params.post_data.is_some() => {
+            "continue".into()
+        }
+        None => "fulfill".into(),
+    };
+    match action.as_str() {
+        "fulfill" => {
+            let body = match (&params.body, &params.body_file) {
+                (Some(_), Some(_)) => anyhow::bail!("Provide body or body_file, not both"),
+                (_, Some(path)) => std::fs::read(path)
+                    .with_context(|| format!("Failed to read body_file '{}'", path))?,
+                (Some(body), None) => body.clone().into_bytes(),
+                (None, None) => Vec::new(),
+            };
+            let mut headers = params.headers.clone().unwrap_or_default();
+            let has_content_type = headers
+                .keys()
+                .any(|k| k.eq_ignore_ascii_case("content-type"));
+            if let Some(ref content_type) = params.content_type {
+                headers.retain(|k, _| !k.eq_ignore_ascii_case("content-type"));
+                headers.insert("Content-Type".into(), content_type.clone());
+            } else if !has_content_type {
+                let guessed = guess_content_type(params.body_file.as_deref(), &body);
+                headers.insert("Content-Type".into(), guessed.into());
+            }
+            Ok(RouteAction::Fulfill {
+                status: params.status.unwrap_or(200),
+                headers,
+                body_bytes: body.len(),
+                body: Arc::new(body),
+            })
+        }
+        "abort" => {
+            let reason = params.error_reason.as_deref().unwrap_or("Failed");
+            let reason: ErrorReason = reason
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Unknown error_reason '{}'", reason))?;
+            Ok(RouteAction::Abort {
+                error_reason: reason.as_ref().to_string(),
+            })
+        }
+        "continue" => Ok(RouteAction::Continue {
+            url: params.url.clone(),
+            method: params.method.clone(),
+            headers: params.headers.clone().unwrap_or_default(),
+            post_data: params.post_data.clone(),
+        }),
+        other => anyhow::bail!(
+            "Unknown route action '{}'. Use fulfill, abort, or continue.",
+            other
+        ),
+    }
+}
+
+fn guess_content_type(body_file: Option<&str>, body: &[u8]) -> &'static str {
+    let extension = body_file
+        .and_then(|f| std::path::Path::new(f).extension())
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("json") => "application/json",
+        Some("html" | "htm") => "text/html",
+        Some("js" | "mjs") => "text/javascript",
+        Some("css") => "text/css",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("txt") => "text/plain",
+        Some(_) => "application/octet-stream",
+        None if serde_json::from_slice::<serde_json::Value>(body).is_ok() => "application/json",
+        None => "text/plain",
+    }
+}
+
+/// Add a route and start pausing requests on `pages` if this is the first one.
+pub async fn add_route(pages: &[Page], routes: &Routes, params: &RouteParams) -> Result<Route> {
+    let route = Route {
+        id: routes.next_id.fetch_add(1, Ordering::Relaxed),
+        url_pattern: params.url_pattern.clone(),
+        resource_types: params.resource_types.clone(),
+        action: build_action(params)?,
+        hits: 0,
+    };
+    routes.rules.lock().await.push(route.clone());
+    sync_pages(pages, routes).await?;
+    Ok(route)
+}
+
+/// Remove routes by id, pattern, or all at once. Returns how many were removed.
+pub async fn remove_routes(
+    pages: &[Page],
+    routes: &Routes,
+    params: &UnrouteParams,
+) -> Result<usize> {
+    let removed = {
+        let mut rules = routes.rules.lock().await;
+        let before = rules.len();
+        match (params.id, &params.url_pattern, params.all.unwrap_or(false)) {
+            (_, _, true) => rules.clear(),
+            (Some(id), _, _) => rules.retain(|r| r.id != id),
+            (None, Some(pattern), _) => rules.retain(|r| &r.url_pattern != pattern),
+            (None, None, false) => anyhow::bail!("Provide id, url_pattern, or all"),
+        }
+        before - rules.len()
+    };
+    sync_pages(pages, routes).await?;
+    Ok(removed)
+}
+
+/// Apply the current route table and replay state to already open tabs.
+pub async fn sync_pages(pages: &[Page], routes: &Routes) -> Result<()> {
+    for page in pages {
+        sync_page(page, routes).await?;
+    }
+    Ok(())
+}
+
+/// Turn request pausing on or off for a page to match the route table.
+async fn sync_page(page: &Page, routes: &Routes) -> Result<()> {
+    if routes.is_active().await {
+        let pattern = RequestPattern {
+            url_pattern: Some("*".into()),
+            resource_type: None,
+            request_stage: Some(RequestStage::Request),
+        };
+        page.execute(fetch::EnableParams {
+            patterns: Some(vec![pattern]),
+            handle_auth_requests: None,
+        })
+        .await
+        .context("Failed to enable request interception")?;
+    } else {
+        page.execute(fetch::DisableParams::default())
+            .await
+            .context("Failed to disable request interception")?;
+    }
+    Ok(())
+}
+
+/// Handle paused requests for a page entering the pool. Pausing itself is only
+/// enabled while routes or a HAR replay exist.
+pub async fn start_intercepting(page: &Page, routes: Routes) -> Result<()> {
+    let mut paused = page.event_listener::<EventRequestPaused>().await?;
+    let task_page = page.clone();
+    let task_routes = routes.clone();
+    tokio::spawn(async move {
+        while let Some(event) = paused.next().await {
+            let route = task_routes
+                .take_match(&event.request.url, event.resource_type.as_ref())
+                .await;
+            let result = if route.is_some() {
+                resolve_paused(&task_page, &event, route.as_ref()).await
+            } else if is_blocked(&task_routes, &event).await {
+                task_page
+                    .execute(FailRequestParams::new(
+                        event.request_id.clone(),
+                        ErrorReason::BlockedByClient,
+                    ))
+                    .await
+                    .map(|_| ())
+                    .map_err(Into::into)
+            } else if let Some(replay) = task_routes.replay().await {
+                replay_paused(&task_page, &event, &replay).await
+            } else {
+                resolve_paused(&task_page, &event, None).await
+            };
+            if let Err(e) = result {
+                tracing::warn!("Route for {} failed: {:#}", event.request.url, e);
+                // Never leave the request hanging
+                let _ = task_page
+                    .execute(ContinueRequestParams::new(event.request_id.clone()))
+                    .await;
+            }
+        }
+    });
+    sync_page(page, &routes).await
+}
+
+async fn resolve_paused(
+    page: &Page,
+    event: &EventRequestPaused,
+    route: Option<&Route>,
+) -> Result<()> {
+    use base64::Engine;
+    let engine = base64::engine::general_purpose::STANDARD;
+
+    let Some(route) = route else {
+        page.execute(ContinueRequestParams::new(event.request_id.clone()))
+            .await?;
+        return Ok(());
+    };
+    match route.action {
+        RouteAction::Fulfill {
+            status,
+            ref headers,
+            ref body,
+            ..
+        } => {
+            page.execute(FulfillRequestParams {
+                request_id: event.request_id.clone(),
+                response_code: status as i64,
+                response_headers: Some(header_entries(headers)),
+                binary_response_headers: None,
+                body: Some(engine.encode(body.as_slice()).into()),
+                response_phrase: None,
+            })
+            .await?;
+        }
+        RouteAction::Abort { ref error_reason } => {
+            let reason: ErrorReason = error_reason
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Unknown error_reason '{}'", error_reason))?;
+            page.execute(FailRequestParams::new(event.request_id.clone(), reason))
+                .await?;
+        }
+        RouteAction::Continue {
+            ref url,
+            ref method,
+            ref headers,
+            ref post_data,
+        } => {
+            let headers = if headers.is_empty() {
+                None
+            } else {
+                let mut merged: BTreeMap<String, String> = match event.request.headers.inner() {
+                    serde_json::Value::Object(map) => map
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.as_str().unwrap_or_default().to_string()))
+                        .collect(),
+                    _ => BTreeMap::new(),
+                };
+                for (name, value) in headers {
+                    merged.retain(|k, _| !k.eq_ignore_ascii_case(name));
+                    merged.insert(name.clone(), value.clone());
+                }
+                Some(header_entries(&merged))
+            };
+            page.execute(ContinueRequestParams {
+                request_id: event.request_id.clone(),
+                url: url.clone(),
+                method: method.clone(),
+                post_data: post_data.as_ref().map(|d| engine.encode(d).into()),
+                headers,
+                intercept_response: None,
+            })
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn is_blocked(routes: &Routes, event: &EventRequestPaused) -> bool {
+    match routes.blocking().await {
+        Some(rules) => rules.check(&event.request.url, event.resource_type.as_ref()),
+        None => false,
+    }
+}
+
+async fn replay_paused(page: &Page, event: &EventRequestPaused, replay: &HarReplay) -> Result<()> {
+    use base64::Engine;
+
+    let Some(recorded) = replay
+        .archive
+        .lookup(&event.request.method, &event.request.url)
+    else {
+        replay.missed.fetch_add(1, Ordering::Relaxed);
+        if replay.fallback {
+            page.execute(ContinueRequestParams::new(event.request_id.clone()))
+                .await?;
+        } else {
+            tracing::debug!("Not in HAR, blocking: {}", event.request.url);
+            page.execute(FailRequestParams::new(
+                event.request_id.clone(),
+                ErrorReason::BlockedByClient,
+            ))
+            .await?;
+        }
+        return Ok(());
+    };
+
+    replay.served.fetch_add(1, Ordering::Relaxed);
+    let headers = recorded
+        .headers
+        .iter()
+        .map(|(name, value)| HeaderEntry::new(name.clone(), value.clone()))
+        .collect();
+    page.execute(FulfillRequestParams {
+        request_id: event.request_id.clone(),
+        response_code: recorded.status as i64,
+        response_headers: Some(headers),
+        binary_response_headers: None,
+        body: Some(
+            base64::engine::general_purpose::STANDARD
+                .encode(&recorded.body)
+                .into(),
+        ),
+        response_phrase: None,
+    })
+    .await?;
+    Ok(())
+}
+
+fn header_entries(headers: &BTreeMap<String, String>) -> Vec<HeaderEntry> {
+    headers
+        .iter()
+        .map(|(name, value)| HeaderEntry::new(name.clone(), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(url_pattern: &str) -> RouteParams {
+        RouteParams {
+            url_pattern: url_pattern.into(),
+            action: None,
+            resource_types: None,
+            status: None,
+            headers: None,
+            body: None,
+            body_file: None,
+            content_type: None,
+            error_reason: None,
+            url: None,
+            method: None,
+            post_data: None,
+        }
+    }
+
+    #[test]
+    fn test_url_matches() {
+        assert!(url_matches(
+            "*/api/users*",
+            "https://x.test/api/users?page=2"
+        ));
+        assert!(!url_matches("*/api/users", "https://x.test/api/users/1"));
+        assert!(url_matches(
+            "https://x.test/v?/*",
+            "https://x.test/v2/items"
+        ));
+        // No wildcard: substring, like NetworkLog patterns
+        assert!(url_matches("analytics", "https://cdn.test/analytics.js"));
+        assert!(url_matches("a+b(c)", "https://x.test/a+b(c)"));
+    }
+
+    #[test]
+    fn test_build_action_infers_kind() {
+        let mut p = params("*");
+        p.body = Some("{\"ok\":true}".into());
+        match build_action(&p).unwrap() {
+            RouteAction::Fulfill {
+                status, headers, ..
+            } => {
+                assert_eq!(status, 200);
+                assert_eq!(headers["Content-Type"], "application/json");
+            }
+            other => panic!("expected fulfill, got {:?}", other),
+        }
+
+        let mut p = params("*");
+        p.error_reason = Some("connectionrefused".into());
+        assert!(matches!(
+            build_action(&p).unwrap(),
+            RouteAction::Abort { error_reason } if error_reason == "ConnectionRefused"
+        ));
+
+        let mut p = params("*");
+        p.method = Some("POST".into());
+        assert!(matches!(
+            build_action(&p).unwrap(),
+            RouteAction::Continue { .. }
+        ));
+
+        let mut p = params("*");
+        p.action = Some("abort".into());
+        p.error_reason = Some("Nope".into());
+        assert!(build_action(&p).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_later_routes_take_precedence() {
+        let routes = Routes::new();
+        let mut generic = params("*/api/*");
+        generic.status = Some(500);
+        add_route(&[], &routes, &generic).await.unwrap();
+        let mut specific = params("*/api/users");
+        specific.resource_types = Some(vec!["fetch".into()]);
+        add_route(&[], &routes, &specific).await.unwrap();
+
+        let hit = routes.take_match("https://x.test/api/users", "Fetch").await;
+        assert_eq!(hit.unwrap().id, 2);
+        // Resource type filter falls through to the older route
+        let hit = routes.take_match("https://x.test/api/users", "Image").await;
+        assert_eq!(hit.unwrap().id, 1);
+        assert_eq!(routes.list().await[0].hits, 1);
+
+        let unroute = UnrouteParams {
+            id: Some(1),
+            url_pattern: None,
+            all: None,
+        };
+        assert_eq!(remove_routes(&[], &routes, &unroute).await.unwrap(), 1);
+        assert!(routes
+            .take_match("https://x.test/api/orders", "XHR")
+            .await
+            .is_none());
+    }
+}
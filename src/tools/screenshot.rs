@@ -1,62 +1,317 @@
+use std::collections::HashMap;
+
 use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::dom::Rgba;
+use chromiumoxide::cdp::browser_protocol::emulation::SetDefaultBackgroundColorOverrideParams;
+use chromiumoxide::cdp::browser_protocol::page::{
+    CaptureScreenshotFormat, CaptureScreenshotParams, Viewport,
+};
 use chromiumoxide::page::Page;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+use crate::selectors::SelectorType;
+use crate::tools::snapshot::{self, RefCounter, SnapshotOutput, SnapshotParams};
+
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
 pub struct ScreenshotParams {
-    #[schemars(description = "CSS selector of element to screenshot (omit for viewport)")]
+    #[schemars(description = "Selector of element to screenshot (omit for viewport)")]
     pub selector: Option<String>,
+    #[schemars(description = "Type of selector: css, text, or xpath")]
+    pub selector_type: Option<SelectorType>,
     #[schemars(description = "Capture the full scrollable page")]
     pub full_page: Option<bool>,
-    #[schemars(description = "Image format: png or jpeg")]
+    #[schemars(description = "Image format: png, jpeg, or webp (default: png)")]
     pub format: Option<String>,
-    #[schemars(description = "JPEG quality (1-100, only for jpeg format)")]
+    #[schemars(description = "Compression quality 0-100 for jpeg and webp (default: 80)")]
     pub quality: Option<u32>,
+    #[schemars(
+        description = "Region to capture in CSS pixels — viewport coordinates, or document coordinates with full_page"
+    )]
+    pub clip: Option<ClipRect>,
+    #[schemars(description = "Scale factor for the output image, e.g. 0.5 for half size (default: 1)")]
+    pub scale: Option<f64>,
+    #[schemars(
+        description = "Downscale so the image is at most this many pixels wide. Recommended for full_page captures."
+    )]
+    pub max_width: Option<u32>,
+    #[schemars(description = "Transparent instead of white page background (png and webp only)")]
+    pub omit_background: Option<bool>,
+    #[schemars(
+        description = "Draw numbered boxes over interactive elements labelled with their snapshot refs (e0, e1, ...) so you can pick a ref from the image"
+    )]
+    pub annotate: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ClipRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A captured image, base64-encoded, with the MIME type matching its format.
+/// `annotations` holds the snapshot whose refs were drawn when `annotate` was set.
+#[derive(Debug, Clone)]
+pub struct Screenshot {
+    pub data: String,
+    pub mime_type: &'static str,
+    pub annotations: Option<SnapshotOutput>,
+}
+
+const OVERLAY_ID: &str = "__remix_ref_overlay";
+
+fn parse_format(format: Option<&str>) -> Result<(CaptureScreenshotFormat, &'static str)> {
+    match format.unwrap_or("png").to_ascii_lowercase().as_str() {
+        "png" => Ok((CaptureScreenshotFormat::Png, "image/png")),
+        "jpeg" | "jpg" => Ok((CaptureScreenshotFormat::Jpeg, "image/jpeg")),
+        "webp" => Ok((CaptureScreenshotFormat::Webp, "image/webp")),
+        other => anyhow::bail!("Unsupported screenshot format '{}'. Use png, jpeg, or webp.", other),
+    }
+}
+
+/// Combine the requested `scale` with the `max_width` cap. `css_width` is the clip
+/// width in CSS pixels; the captured image is `css_width * scale * dpr` pixels wide.
+fn effective_scale(css_width: f64, dpr: f64, scale: Option<f64>, max_width: Option<u32>) -> f64 {
+    let mut scale = scale.filter(|s| *s > 0.0).unwrap_or(1.0);
+    if let Some(max_width) = max_width.filter(|w| *w > 0) {
+        let output_width = css_width * scale * dpr;
+        if output_width > max_width as f64 {
+            scale = max_width as f64 / (css_width * dpr);
+        }
+    }
+    scale
+}
+
+/// Draw a box and `eN` label over every element in `refs`, in document coordinates
+/// so the overlay lines up for viewport, element, and full-page captures alike.
+async fn draw_ref_overlay(page: &Page, refs: &HashMap<String, String>) -> Result<()> {
+    let mut entries: Vec<(&String, &String)> = refs.iter().collect();
+    entries.sort_by_key(|(id, _)| id[1..].parse::<u64>().unwrap_or(u64::MAX));
+    let js = format!(
+        r#"(() => {{
+            const entries = {entries};
+            const colors = ['#e6194b', '#3cb44b', '#4363d8', '#f58231', '#911eb4', '#008080', '#9a6324', '#800000'];
+            document.getElementById({id})?.remove();
+            const overlay = document.createElement('div');
+            overlay.id = {id};
+            overlay.style.cssText = 'position:absolute;left:0;top:0;width:0;height:0;pointer-events:none;z-index:2147483647;';
+            entries.forEach(([ref, selector], i) => {{
+                let el;
+                try {{ el = document.querySelector(selector); }} catch (e) {{ return; }}
+                if (!el) return;
+                const r = el.getBoundingClientRect();
+                if (r.width === 0 && r.height === 0) return;
+                const color = colors[i % colors.length];
+                const box = document.createElement('div');
+                box.style.cssText = `position:absolute;box-sizing:border-box;left:${{r.left + scrollX}}px;top:${{r.top + scrollY}}px;width:${{r.width}}px;height:${{r.height}}px;border:2px solid ${{color}};`;
+                const label = document.createElement('div');
+                label.textContent = ref;
+                label.style.cssText = `position:absolute;left:-2px;top:-2px;transform:translateY(-100%);padding:0 3px;background:${{color}};color:#fff;font:bold 11px/14px monospace;white-space:nowrap;`;
+                if (r.top + scrollY < 16) label.style.transform = 'none';
+                box.appendChild(label);
+                overlay.appendChild(box);
+            }});
+            document.documentElement.appendChild(overlay);
+        }})()"#,
+        entries = serde_json::to_string(&entries)?,
+        id = serde_json::to_string(OVERLAY_ID)?
+    );
+    page.evaluate(js)
+        .await
+        .context("Failed to draw ref annotations")?;
+    Ok(())
+}
+
+async fn remove_ref_overlay(page: &Page) -> Result<()> {
+    let js = format!(
+        "document.getElementById({})?.remove()",
+        serde_json::to_string(OVERLAY_ID)?
+    );
+    page.evaluate(js)
+        .await
+        .context("Failed to remove ref annotations")?;
+    Ok(())
 }
 
-pub async fn screenshot(page: &Page, params: &ScreenshotParams) -> Result<String> {
+/// Capture a screenshot. `counter` numbers the refs of an annotated one.
+pub async fn screenshot(
+    page: &Page,
+    params: &ScreenshotParams,
+    counter: &RefCounter,
+) -> Result<Screenshot> {
     use base64::Engine;
 
-    let _format = params.format.as_deref().unwrap_or("png");
+    let (format, mime_type) = parse_format(params.format.as_deref())?;
     let full_page = params.full_page.unwrap_or(false);
 
-    let bytes = if let Some(ref selector) = params.selector {
-        // Screenshot a specific element
-        let element = page
-            .find_element(selector)
-            .await
-            .context("Element not found for screenshot")?;
-        element
-            .screenshot(chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat::Png)
+    // Scroll offsets, viewport and document sizes, all in CSS pixels
+    let metrics: serde_json::Value = page
+        .evaluate(
+            "({ scrollX, scrollY, innerWidth, innerHeight, dpr: devicePixelRatio || 1, \
+               docWidth: document.documentElement.scrollWidth, \
+               docHeight: document.documentElement.scrollHeight })",
+        )
+        .await
+        .context("Failed to read page metrics")?
+        .into_value()?;
+    let metric = |key: &str| metrics[key].as_f64().unwrap_or(0.0);
+    let (scroll_x, scroll_y) = (metric("scrollX"), metric("scrollY"));
+
+    // Every capture goes through a document-relative clip so scaling applies uniformly.
+    let (region, beyond_viewport) = if let Some(ref selector) = params.selector {
+        let selector_type = params.selector_type.clone().unwrap_or_default();
+        let (selector, selector_type) =
+            crate::selectors::normalize_selector_type(selector, selector_type);
+        let selector_js = crate::interaction::click::selector_to_js(&selector, &selector_type)?;
+        let js = format!(
+            r#"(() => {{
+                const el = {selector_js};
+                if (!el) return {{ error: 'Element not found for screenshot: ' + {sel_str} }};
+                el.scrollIntoView({{ block: 'nearest', inline: 'nearest', behavior: 'instant' }});
+                const r = el.getBoundingClientRect();
+                if (r.width === 0 || r.height === 0) return {{ error: 'Element has zero size' }};
+                return {{ x: r.left + scrollX, y: r.top + scrollY, width: r.width, height: r.height }};
+            }})()"#,
+            selector_js = selector_js,
+            sel_str = serde_json::to_string(&selector)?
+        );
+        let rect: serde_json::Value = page
+            .evaluate(js)
             .await
-            .context("Failed to take element screenshot")?
-    } else if full_page {
-        // Full page screenshot
-        // First get the full scroll dimensions
-        let dims: serde_json::Value = page
-            .evaluate("({ width: document.documentElement.scrollWidth, height: document.documentElement.scrollHeight })")
-            .await?
+            .context("Failed to locate element for screenshot")?
             .into_value()?;
+        if let Some(error) = rect.get("error").and_then(|e| e.as_str()) {
+            anyhow::bail!("{}", error);
+        }
+        let rect: ClipRect = serde_json::from_value(rect)?;
+        (rect, true)
+    } else if let Some(ref clip) = params.clip {
+        if clip.width <= 0.0 || clip.height <= 0.0 {
+            anyhow::bail!("clip width and height must be positive");
+        }
+        let (offset_x, offset_y) = if full_page { (0.0, 0.0) } else { (scroll_x, scroll_y) };
+        let rect = ClipRect {
+            x: clip.x + offset_x,
+            y: clip.y + offset_y,
+            ..clip.clone()
+        };
+        (rect, full_page)
+    } else if full_page {
+        let rect = ClipRect {
+            x: 0.0,
+            y: 0.0,
+            width: metric("docWidth").max(metric("innerWidth")),
+            height: metric("docHeight").max(metric("innerHeight")),
+        };
+        (rect, true)
+    } else {
+        let rect = ClipRect {
+            x: scroll_x,
+            y: scroll_y,
+            width: metric("innerWidth"),
+            height: metric("innerHeight"),
+        };
+        (rect, false)
+    };
 
-        let _width = dims["width"].as_u64().unwrap_or(1280);
-        let _height = dims["height"].as_u64().unwrap_or(720);
+    let scale = effective_scale(region.width, metric("dpr").max(1.0), params.scale, params.max_width);
 
-        // Use the page screenshot with full page option
-        page.screenshot(
-            chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotParams::builder()
-                .capture_beyond_viewport(true)
-                .build(),
-        )
+    let mut builder = CaptureScreenshotParams::builder()
+        .format(format.clone())
+        .clip(Viewport {
+            x: region.x,
+            y: region.y,
+            width: region.width,
+            height: region.height,
+            scale,
+        })
+        .capture_beyond_viewport(beyond_viewport);
+    if format != CaptureScreenshotFormat::Png {
+        builder = builder.quality(params.quality.unwrap_or(80).min(100) as i64);
+    }
+
+    let omit_background =
+        params.omit_background.unwrap_or(false) && format != CaptureScreenshotFormat::Jpeg;
+    if omit_background {
+        page.execute(SetDefaultBackgroundColorOverrideParams {
+            color: Some(Rgba {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: Some(0.),
+            }),
+        })
         .await
-        .context("Failed to take full page screenshot")?
+        .context("Failed to make background transparent")?;
+    }
+
+    let annotate = params.annotate.unwrap_or(false);
+    let captured = async {
+        let annotations = if annotate {
+            let params = SnapshotParams {
+                selector: None,
+                ..Default::default()
+            };
+            let snap = snapshot::snapshot_with_refs(page, &params, counter).await?;
+            draw_ref_overlay(page, &snap.refs).await?;
+            Some(snap)
+        } else {
+            None
+        };
+        let bytes = page
+            .screenshot(builder.build())
+            .await
+            .context("Failed to take screenshot")?;
+        anyhow::Ok((bytes, annotations))
+    }
+    .await;
+
+    // Put the page back even when the capture failed part-way
+    let restored = if omit_background {
+        page.execute(SetDefaultBackgroundColorOverrideParams { color: None })
+            .await
+            .map(|_| ())
+            .context("Failed to restore page background")
     } else {
-        // Viewport screenshot
-        page.screenshot(
-            chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotParams::builder().build(),
-        )
-        .await
-        .context("Failed to take screenshot")?
+        Ok(())
+    };
+    let removed = if annotate {
+        remove_ref_overlay(page).await
+    } else {
+        Ok(())
     };
 
-    Ok(base64::engine::general_purpose::STANDARD.encode(&bytes))
+    let (bytes, annotations) = captured?;
+    restored?;
+    removed?;
+
+    Ok(Screenshot {
+        data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+        mime_type,
+        annotations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(parse_format(None).unwrap().1, "image/png");
+        assert_eq!(parse_format(Some("JPG")).unwrap().1, "image/jpeg");
+        assert_eq!(parse_format(Some("webp")).unwrap().1, "image/webp");
+        assert!(parse_format(Some("gif")).is_err());
+    }
+
+    #[test]
+    fn test_effective_scale_caps_width() {
+        assert_eq!(effective_scale(1280.0, 1.0, None, None), 1.0);
+        assert_eq!(effective_scale(1280.0, 1.0, Some(0.5), None), 0.5);
+        // 1280 css px at dpr 2 → 2560 px; capped to 640 px
+        assert_eq!(effective_scale(1280.0, 2.0, None, Some(640)), 0.25);
+        // Already narrower than the cap: leave the requested scale alone
+        assert_eq!(effective_scale(400.0, 1.0, Some(0.5), Some(640)), 0.5);
+    }
 }
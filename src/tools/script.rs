@@ -4,13 +4,19 @@ use boa_engine::property::Attribute;
 use boa_engine::{Context, JsArgs, JsError, JsValue, NativeFunction, Source};
 use chromiumoxide::page::Page;
 use serde::Deserialize;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
+use crate::browser::Tabs;
+use crate::interaction::{keyboard, mouse};
 use crate::selectors::SelectorType;
 use std::collections::HashMap;
 
-use crate::tools::{dom, interaction, javascript, navigation, network, screenshot, snapshot};
+use crate::tools::{
+    dom, emulation, har, interaction, javascript, navigation, network, page as tab, route,
+    screenshot, snapshot, websocket,
+};
 
 use rmcp::model::Content;
 
@@ -57,16 +63,73 @@ impl ScriptResult {
 
 struct ScriptContext {
     handle: tokio::runtime::Handle,
-    page: Page,
+    /// The page the `page` object acts on; changes when the script switches tabs.
+    page: Mutex<Page>,
+    tabs: Option<Tabs>,
     console_log: javascript::ConsoleLog,
     network_log: network::NetworkLog,
+    /// Where `page.exportHar()` writes files.
+    output_dir: PathBuf,
     output_lines: Mutex<Vec<String>>,
-    screenshots: Mutex<Vec<String>>,
+    screenshots: Mutex<Vec<screenshot::Screenshot>>,
     snapshot_refs: Mutex<Option<HashMap<String, String>>>,
+    /// Mouse buttons held by `page.mouse.down()`, reported on later moves.
+    mouse_buttons: Mutex<i64>,
 }
 
 impl ScriptContext {
+    fn page(&self) -> Page {
+        self.page.lock().unwrap().clone()
+    }
+
+    /// The session's ref numbering; a script without tabs numbers refs per document.
+    fn ref_counter(&self) -> snapshot::RefCounter {
+        self.tabs
+            .as_ref()
+            .map(|tabs| tabs.ref_counter().clone())
+            .unwrap_or_default()
+    }
+
+    fn tabs(&self) -> Result<&Tabs, String> {
+        self.tabs
+            .as_ref()
+            .ok_or_else(|| "Tab APIs are not available in this script context".to_string())
+    }
+
+    /// Point the `page` object at another tab. Refs belong to the old page, so drop them.
+    fn set_page(&self, page: Page) {
+        *self.page.lock().unwrap() = page;
+        *self.snapshot_refs.lock().unwrap() = None;
+    }
+
+    /// Record refs from a snapshot, keeping earlier ones: refs are pinned to their
+    /// elements, so an old ref either still works or is reported stale.
+    fn add_refs(&self, refs: HashMap<String, String>) {
+        self.snapshot_refs
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .extend(refs);
+    }
+
+    /// Resolve a ref to its selector, failing if its element has left the page.
     fn resolve_ref(&self, selector: &str) -> Result<String, String> {
+        let resolved = self.lookup_ref(selector)?;
+        if let Some(ref_id) = crate::selectors::r#ref::parse_ref(selector) {
+            let page = self.page();
+            let attached = self
+                .handle
+                .block_on(snapshot::ref_attached(&page, &ref_id))
+                .map_err(|e| format!("{:#}", e))?;
+            if !attached {
+                return Err(crate::selectors::r#ref::ResolveRefError::Stale(ref_id).to_string());
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Resolve a ref to its selector without checking that its element still exists.
+    fn lookup_ref(&self, selector: &str) -> Result<String, String> {
         let refs_guard = self.snapshot_refs.lock().unwrap();
         if let Some(ref refs) = *refs_guard {
             match crate::selectors::r#ref::resolve_selector(selector, refs) {
@@ -102,15 +165,62 @@ pub async fn run_script(
     console_log: &javascript::ConsoleLog,
     network_log: &network::NetworkLog,
     initial_refs: Option<HashMap<String, String>>,
+) -> Result<(ScriptResult, Vec<Content>, Option<HashMap<String, String>>)> {
+    execute_script(
+        page,
+        None,
+        params,
+        console_log,
+        network_log,
+        &har::default_output_dir(),
+        initial_refs,
+    )
+    .await
+}
+
+/// Like [`run_script`], starting on the active tab and exposing `page.tabs()`,
+/// `page.newTab()`, `page.switchTab()` and `page.closeTab()`.
+pub async fn run_script_with_tabs(
+    tabs: &Tabs,
+    params: &RunScriptParams,
+    console_log: &javascript::ConsoleLog,
+    network_log: &network::NetworkLog,
+    output_dir: &Path,
+    initial_refs: Option<HashMap<String, String>>,
+) -> Result<(ScriptResult, Vec<Content>, Option<HashMap<String, String>>)> {
+    let page = tabs.active_page().await?;
+    execute_script(
+        &page,
+        Some(tabs.clone()),
+        params,
+        console_log,
+        network_log,
+        output_dir,
+        initial_refs,
+    )
+    .await
+}
+
+async fn execute_script(
+    page: &Page,
+    tabs: Option<Tabs>,
+    params: &RunScriptParams,
+    console_log: &javascript::ConsoleLog,
+    network_log: &network::NetworkLog,
+    output_dir: &Path,
+    initial_refs: Option<HashMap<String, String>>,
 ) -> Result<(ScriptResult, Vec<Content>, Option<HashMap<String, String>>)> {
     let ctx = Arc::new(ScriptContext {
         handle: tokio::runtime::Handle::current(),
-        page: page.clone(),
+        page: Mutex::new(page.clone()),
+        tabs,
         console_log: console_log.clone(),
         network_log: network_log.clone(),
+        output_dir: output_dir.to_path_buf(),
         output_lines: Mutex::new(Vec::new()),
         screenshots: Mutex::new(Vec::new()),
         snapshot_refs: Mutex::new(initial_refs),
+        mouse_buttons: Mutex::new(0),
     });
 
     let script = params.script.clone();
@@ -123,6 +233,7 @@ pub async fn run_script(
     let elapsed_ms = start.elapsed().as_millis();
 
     // Get final page state
+    let page = ctx.page();
     let url = page.url().await?.unwrap_or_default();
     let title = page.get_title().await?.unwrap_or_default();
 
@@ -132,7 +243,7 @@ pub async fn run_script(
     let screenshots = ctx.screenshots.lock().unwrap();
     let contents: Vec<Content> = screenshots
         .iter()
-        .map(|b64| Content::image(b64.clone(), "image/png"))
+        .map(|shot| Content::image(shot.data.clone(), shot.mime_type))
         .collect();
 
     // Extract snapshot refs if page.snapshot() was called during the script
@@ -197,13 +308,14 @@ fn execute_in_boa(ctx: &Arc<ScriptContext>, script: &str) -> Result<(), String>
 // ── Page Object Builder ────────────────────────────────────────────────
 
 fn build_page_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue {
+    let mouse_obj = build_mouse_object(ctx, js_ctx);
     let mut builder = ObjectInitializer::new(js_ctx);
 
     // Navigation
     builder.function(
         make_navigate(ctx.clone()),
         boa_engine::js_string!("navigate"),
-        1,
+        2,
     );
     builder.function(make_back(ctx.clone()), boa_engine::js_string!("back"), 0);
     builder.function(
@@ -216,6 +328,11 @@ fn build_page_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue
         boa_engine::js_string!("reload"),
         0,
     );
+    builder.function(
+        make_history(ctx.clone()),
+        boa_engine::js_string!("history"),
+        0,
+    );
     builder.function(make_url(ctx.clone()), boa_engine::js_string!("url"), 0);
     builder.function(
         make_title(ctx.clone()),
@@ -223,10 +340,35 @@ fn build_page_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue
         0,
     );
 
+    // Tabs
+    builder.function(make_tabs(ctx.clone()), boa_engine::js_string!("tabs"), 0);
+    builder.function(
+        make_new_tab(ctx.clone()),
+        boa_engine::js_string!("newTab"),
+        1,
+    );
+    builder.function(
+        make_switch_tab(ctx.clone()),
+        boa_engine::js_string!("switchTab"),
+        1,
+    );
+    builder.function(
+        make_close_tab(ctx.clone()),
+        boa_engine::js_string!("closeTab"),
+        1,
+    );
+
     // Interaction
     builder.function(make_click(ctx.clone()), boa_engine::js_string!("click"), 2);
     builder.function(make_type(ctx.clone()), boa_engine::js_string!("type"), 3);
     builder.function(make_hover(ctx.clone()), boa_engine::js_string!("hover"), 2);
+    builder.function(make_drag(ctx.clone()), boa_engine::js_string!("drag"), 3);
+    builder.function(
+        make_mouse_click(ctx.clone()),
+        boa_engine::js_string!("clickAt"),
+        3,
+    );
+    builder.property(boa_engine::js_string!("mouse"), mouse_obj, Attribute::all());
     builder.function(
         make_select(ctx.clone()),
         boa_engine::js_string!("select"),
@@ -252,7 +394,7 @@ fn build_page_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue
     builder.function(
         make_snapshot(ctx.clone()),
         boa_engine::js_string!("snapshot"),
-        1,
+        2,
     );
     builder.function(
         make_screenshot(ctx.clone()),
@@ -274,6 +416,11 @@ fn build_page_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue
         boa_engine::js_string!("findElements"),
         2,
     );
+    builder.function(
+        make_bounding_box(ctx.clone()),
+        boa_engine::js_string!("boundingBox"),
+        2,
+    );
 
     // JavaScript
     builder.function(make_js(ctx.clone()), boa_engine::js_string!("js"), 1);
@@ -289,11 +436,39 @@ fn build_page_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue
         boa_engine::js_string!("enableNetwork"),
         1,
     );
+    builder.function(
+        make_disable_network(ctx.clone()),
+        boa_engine::js_string!("disableNetwork"),
+        0,
+    );
     builder.function(
         make_get_network_log(ctx.clone()),
         boa_engine::js_string!("getNetworkLog"),
         1,
     );
+    builder.function(
+        make_get_websocket_log(ctx.clone()),
+        boa_engine::js_string!("getWebSocketLog"),
+        1,
+    );
+    builder.function(
+        make_get_response_body(ctx.clone()),
+        boa_engine::js_string!("getResponseBody"),
+        2,
+    );
+    builder.function(
+        make_export_har(ctx.clone()),
+        boa_engine::js_string!("exportHar"),
+        2,
+    );
+    builder.function(make_route(ctx.clone()), boa_engine::js_string!("route"), 2);
+    builder.function(make_unroute(ctx.clone()), boa_engine::js_string!("unroute"), 1);
+    builder.function(make_routes(ctx.clone()), boa_engine::js_string!("routes"), 0);
+    builder.function(
+        make_set_network(ctx.clone()),
+        boa_engine::js_string!("setNetwork"),
+        1,
+    );
     builder.function(
         make_wait_for_network_idle(ctx.clone()),
         boa_engine::js_string!("waitForNetworkIdle"),
@@ -303,6 +478,38 @@ fn build_page_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue
     builder.build().into()
 }
 
+// ── Mouse Object Builder ───────────────────────────────────────────────
+
+fn build_mouse_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue {
+    let mut builder = ObjectInitializer::new(js_ctx);
+    builder.function(
+        make_mouse_move(ctx.clone()),
+        boa_engine::js_string!("move"),
+        2,
+    );
+    builder.function(
+        make_mouse_button(ctx.clone(), true),
+        boa_engine::js_string!("down"),
+        3,
+    );
+    builder.function(
+        make_mouse_button(ctx.clone(), false),
+        boa_engine::js_string!("up"),
+        3,
+    );
+    builder.function(
+        make_mouse_click(ctx.clone()),
+        boa_engine::js_string!("click"),
+        3,
+    );
+    builder.function(
+        make_mouse_wheel(ctx.clone()),
+        boa_engine::js_string!("wheel"),
+        4,
+    );
+    builder.build().into()
+}
+
 // ── Console Object Builder ─────────────────────────────────────────────
 
 fn build_console_object(ctx: &Arc<ScriptContext>, js_ctx: &mut Context) -> JsValue {
@@ -441,23 +648,23 @@ fn make_navigate(ctx: Arc<ScriptContext>) -> NativeFunction {
         NativeFunction::from_closure(move |_this, args, js_ctx| {
             let url = args.get_or_undefined(0).to_string(js_ctx)?;
             let url_str = url.to_std_string_escaped();
+            let options = args.get_or_undefined(1).clone();
 
             let params = navigation::NavigateParams {
                 url: url_str,
-                wait_until: None,
+                wait_until: get_string_prop(&options, "wait_until", js_ctx),
+                timeout_ms: get_number_prop(&options, "timeout_ms", js_ctx).map(|n| n as u64),
+                fail_on_http_error: get_bool_prop(&options, "fail_on_http_error", js_ctx),
                 include_snapshot: false,
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { navigation::navigate(&page, &params).await })
                 .map_err(js_err)?;
 
-            Ok(JsValue::from(boa_engine::js_string!(format!(
-                "{} — {}",
-                result.title, result.url
-            ))))
+            Ok(JsValue::from(boa_engine::js_string!(result.summary())))
         })
     }
 }
@@ -465,16 +672,13 @@ fn make_navigate(ctx: Arc<ScriptContext>) -> NativeFunction {
 fn make_back(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, _args, _js_ctx| {
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { navigation::go_back(&page).await })
                 .map_err(js_err)?;
 
-            Ok(JsValue::from(boa_engine::js_string!(format!(
-                "{} — {}",
-                result.title, result.url
-            ))))
+            Ok(JsValue::from(boa_engine::js_string!(result.summary())))
         })
     }
 }
@@ -482,16 +686,13 @@ fn make_back(ctx: Arc<ScriptContext>) -> NativeFunction {
 fn make_forward(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, _args, _js_ctx| {
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { navigation::go_forward(&page).await })
                 .map_err(js_err)?;
 
-            Ok(JsValue::from(boa_engine::js_string!(format!(
-                "{} — {}",
-                result.title, result.url
-            ))))
+            Ok(JsValue::from(boa_engine::js_string!(result.summary())))
         })
     }
 }
@@ -499,16 +700,134 @@ fn make_forward(ctx: Arc<ScriptContext>) -> NativeFunction {
 fn make_reload(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, _args, _js_ctx| {
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { navigation::reload(&page).await })
                 .map_err(js_err)?;
 
-            Ok(JsValue::from(boa_engine::js_string!(format!(
-                "{} — {}",
-                result.title, result.url
-            ))))
+            Ok(JsValue::from(boa_engine::js_string!(result.summary())))
+        })
+    }
+}
+
+fn make_history(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, _args, js_ctx| {
+            let page = ctx.page();
+            let result = ctx
+                .handle
+                .block_on(async { navigation::get_history(&page).await })
+                .map_err(js_err)?;
+
+            let json = serde_json::to_value(&result).map_err(js_err)?;
+            Ok(json_to_js(&json, js_ctx))
+        })
+    }
+}
+
+fn make_tabs(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, _args, js_ctx| {
+            let tabs = ctx.tabs().map_err(js_err)?;
+            let result = ctx
+                .handle
+                .block_on(async { tab::list_tabs(tabs).await })
+                .map_err(js_err)?;
+
+            let json = serde_json::to_value(&result).map_err(js_err)?;
+            Ok(json_to_js(&json, js_ctx))
+        })
+    }
+}
+
+fn make_new_tab(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let url = args.get_or_undefined(0);
+            let params = tab::NewTabParams {
+                url: if url.is_undefined() || url.is_null() {
+                    None
+                } else {
+                    Some(url.to_string(js_ctx)?.to_std_string_escaped())
+                },
+            };
+
+            let tabs = ctx.tabs().map_err(js_err)?;
+            let page = ctx
+                .handle
+                .block_on(async {
+                    tab::new_tab(tabs, &params).await?;
+                    tabs.active_page().await
+                })
+                .map_err(js_err)?;
+            let tab_id = page.target_id().as_ref().to_string();
+            ctx.set_page(page);
+
+            Ok(JsValue::from(boa_engine::js_string!(tab_id)))
+        })
+    }
+}
+
+fn make_switch_tab(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let target = args.get_or_undefined(0);
+            let params = if target.is_number() {
+                tab::SwitchTabParams {
+                    tab_id: None,
+                    index: Some(target.to_number(js_ctx)? as u32),
+                }
+            } else {
+                tab::SwitchTabParams {
+                    tab_id: Some(target.to_string(js_ctx)?.to_std_string_escaped()),
+                    index: None,
+                }
+            };
+
+            let tabs = ctx.tabs().map_err(js_err)?;
+            let (info, page) = ctx
+                .handle
+                .block_on(async {
+                    let info = tab::switch_tab(tabs, &params).await?;
+                    let page = tabs.active_page().await?;
+                    anyhow::Ok((info, page))
+                })
+                .map_err(js_err)?;
+            ctx.set_page(page);
+
+            let json = serde_json::to_value(&info).map_err(js_err)?;
+            Ok(json_to_js(&json, js_ctx))
+        })
+    }
+}
+
+fn make_close_tab(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let target = args.get_or_undefined(0);
+            let params = tab::CloseTabParams {
+                tab_id: if target.is_undefined() || target.is_null() {
+                    None
+                } else {
+                    Some(target.to_string(js_ctx)?.to_std_string_escaped())
+                },
+            };
+
+            let tabs = ctx.tabs().map_err(js_err)?;
+            let page = ctx
+                .handle
+                .block_on(async {
+                    tab::close_tab(tabs, &params).await?;
+                    tabs.active_page().await
+                })
+                .map_err(js_err)?;
+            // Keep acting on whichever tab is now active
+            if page.target_id() != ctx.page().target_id() {
+                ctx.set_page(page);
+            }
+
+            Ok(JsValue::undefined())
         })
     }
 }
@@ -516,7 +835,7 @@ fn make_reload(ctx: Arc<ScriptContext>) -> NativeFunction {
 fn make_url(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, _args, _js_ctx| {
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let url = ctx
                 .handle
                 .block_on(async { page.url().await })
@@ -530,7 +849,7 @@ fn make_url(ctx: Arc<ScriptContext>) -> NativeFunction {
 fn make_title(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, _args, _js_ctx| {
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let title = ctx
                 .handle
                 .block_on(async { page.get_title().await })
@@ -556,9 +875,11 @@ fn make_click(ctx: Arc<ScriptContext>) -> NativeFunction {
                 selector: selector_str,
                 selector_type: Some(selector_type),
                 button: get_string_prop(&options, "button", js_ctx),
+                click_count: get_number_prop(&options, "click_count", js_ctx).map(|n| n as u32),
+                modifiers: get_string_array_prop(&options, "modifiers", js_ctx),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { interaction::do_click(&page, &params).await })
@@ -589,9 +910,11 @@ fn make_type(ctx: Arc<ScriptContext>) -> NativeFunction {
                 text: text.to_std_string_escaped(),
                 selector_type: Some(selector_type),
                 clear_first: get_bool_prop(&options, "clear", js_ctx),
+                per_character: get_bool_prop(&options, "per_character", js_ctx),
+                delay_ms: get_number_prop(&options, "delay_ms", js_ctx).map(|d| d as u64),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             ctx.handle
                 .block_on(async { interaction::type_text(&page, &params).await })
                 .map_err(js_err)?;
@@ -617,7 +940,7 @@ fn make_hover(ctx: Arc<ScriptContext>) -> NativeFunction {
                 selector_type: Some(selector_type),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             ctx.handle
                 .block_on(async { interaction::hover(&page, &params).await })
                 .map_err(js_err)?;
@@ -627,6 +950,158 @@ fn make_hover(ctx: Arc<ScriptContext>) -> NativeFunction {
     }
 }
 
+/// A drag endpoint: either a selector (refs allowed) or x/y viewport coordinates.
+type DragPoint = (Option<String>, Option<f64>, Option<f64>);
+
+/// Parse a drag endpoint given as a selector string or an `{x, y}` object.
+fn parse_drag_point(
+    ctx: &ScriptContext,
+    value: &JsValue,
+    js_ctx: &mut Context,
+) -> Result<DragPoint, JsError> {
+    if value.is_object() {
+        return Ok((
+            None,
+            get_number_prop(value, "x", js_ctx),
+            get_number_prop(value, "y", js_ctx),
+        ));
+    }
+    let selector = value.to_string(js_ctx)?.to_std_string_escaped();
+    let selector = ctx.resolve_ref(&selector).map_err(js_err)?;
+    Ok((Some(selector), None, None))
+}
+
+fn make_drag(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let (from, from_x, from_y) = parse_drag_point(&ctx, args.get_or_undefined(0), js_ctx)?;
+            let (to, to_x, to_y) = parse_drag_point(&ctx, args.get_or_undefined(1), js_ctx)?;
+            let options = args.get_or_undefined(2).clone();
+
+            let params = interaction::DragParams {
+                from,
+                to,
+                selector_type: parse_selector_type(&options, js_ctx),
+                from_x,
+                from_y,
+                to_x,
+                to_y,
+                steps: get_number_prop(&options, "steps", js_ctx).map(|s| s as u32),
+            };
+
+            let page = ctx.page();
+            let result = ctx.handle
+                .block_on(async { interaction::drag(&page, &params).await })
+                .map_err(js_err)?;
+
+            Ok(JsValue::from(boa_engine::js_string!(format!(
+                "Dragged ({})",
+                result.method_used
+            ))))
+        })
+    }
+}
+
+fn make_mouse_click(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let x = args.get_or_undefined(0).to_number(js_ctx)?;
+            let y = args.get_or_undefined(1).to_number(js_ctx)?;
+            let options = args.get_or_undefined(2).clone();
+
+            let params = interaction::ClickAtParams {
+                x,
+                y,
+                button: get_string_prop(&options, "button", js_ctx),
+                click_count: get_number_prop(&options, "click_count", js_ctx).map(|n| n as u32),
+                modifiers: get_string_array_prop(&options, "modifiers", js_ctx),
+            };
+
+            let page = ctx.page();
+            ctx.handle
+                .block_on(async { interaction::click_at(&page, &params).await })
+                .map_err(js_err)?;
+
+            Ok(JsValue::from(boa_engine::js_string!(format!(
+                "Clicked at ({}, {})",
+                x, y
+            ))))
+        })
+    }
+}
+
+fn make_mouse_move(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let x = args.get_or_undefined(0).to_number(js_ctx)?;
+            let y = args.get_or_undefined(1).to_number(js_ctx)?;
+            let buttons = *ctx.mouse_buttons.lock().unwrap();
+
+            let page = ctx.page();
+            ctx.handle
+                .block_on(async { mouse::move_to(&page, x, y, buttons, 0).await })
+                .map_err(js_err)?;
+
+            Ok(JsValue::undefined())
+        })
+    }
+}
+
+fn make_mouse_button(ctx: Arc<ScriptContext>, down: bool) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let x = args.get_or_undefined(0).to_number(js_ctx)?;
+            let y = args.get_or_undefined(1).to_number(js_ctx)?;
+            let options = args.get_or_undefined(2).clone();
+
+            let button = get_string_prop(&options, "button", js_ctx).unwrap_or_default();
+            let modifiers = get_string_array_prop(&options, "modifiers", js_ctx).unwrap_or_default();
+
+            let held = *ctx.mouse_buttons.lock().unwrap();
+            let page = ctx.page();
+            let buttons = ctx
+                .handle
+                .block_on(async {
+                    mouse::button_at(
+                        &page,
+                        x,
+                        y,
+                        mouse::parse_button(&button),
+                        down,
+                        held,
+                        keyboard::modifier_flags(&modifiers),
+                    )
+                    .await
+                })
+                .map_err(js_err)?;
+            *ctx.mouse_buttons.lock().unwrap() = buttons;
+
+            Ok(JsValue::undefined())
+        })
+    }
+}
+
+fn make_mouse_wheel(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let params = interaction::WheelParams {
+                x: args.get_or_undefined(0).to_number(js_ctx)?,
+                y: args.get_or_undefined(1).to_number(js_ctx)?,
+                delta_x: Some(args.get_or_undefined(2).to_number(js_ctx)?).filter(|d| !d.is_nan()),
+                delta_y: Some(args.get_or_undefined(3).to_number(js_ctx)?).filter(|d| !d.is_nan()),
+                modifiers: None,
+            };
+
+            let page = ctx.page();
+            ctx.handle
+                .block_on(async { interaction::wheel(&page, &params).await })
+                .map_err(js_err)?;
+
+            Ok(JsValue::undefined())
+        })
+    }
+}
+
 fn make_select(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, args, js_ctx| {
@@ -645,7 +1120,7 @@ fn make_select(ctx: Arc<ScriptContext>) -> NativeFunction {
                 selector_type: Some(selector_type),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             ctx.handle
                 .block_on(async { interaction::select_option(&page, &params).await })
                 .map_err(js_err)?;
@@ -675,7 +1150,7 @@ fn make_fill(ctx: Arc<ScriptContext>) -> NativeFunction {
                 selector_type: Some(selector_type),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx.handle
                 .block_on(async { interaction::fill(&page, &params).await })
                 .map_err(js_err)?;
@@ -698,7 +1173,7 @@ fn make_press(ctx: Arc<ScriptContext>) -> NativeFunction {
                 modifiers,
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             ctx.handle
                 .block_on(async { interaction::press_key(&page, &params).await })
                 .map_err(js_err)?;
@@ -724,7 +1199,7 @@ fn make_scroll(ctx: Arc<ScriptContext>) -> NativeFunction {
                 selector_type: parse_selector_type(&options, js_ctx),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             ctx.handle
                 .block_on(async { interaction::do_scroll(&page, &params).await })
                 .map_err(js_err)?;
@@ -753,7 +1228,8 @@ fn make_wait_for(ctx: Arc<ScriptContext>) -> NativeFunction {
         NativeFunction::from_closure(move |_this, args, js_ctx| {
             let selector = args.get_or_undefined(0).to_string(js_ctx)?;
             let selector_str = selector.to_std_string_escaped();
-            let selector_str = ctx.resolve_ref(&selector_str).map_err(js_err)?;
+            // Waiting for a ref's element to disappear is fine, so no stale check
+            let selector_str = ctx.lookup_ref(&selector_str).map_err(js_err)?;
             let options = args.get_or_undefined(1).clone();
 
             let params = dom::WaitForParams {
@@ -763,7 +1239,7 @@ fn make_wait_for(ctx: Arc<ScriptContext>) -> NativeFunction {
                 state: get_string_prop(&options, "state", js_ctx),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let found = ctx
                 .handle
                 .block_on(async { dom::wait_for(&page, &params).await })
@@ -777,23 +1253,36 @@ fn make_wait_for(ctx: Arc<ScriptContext>) -> NativeFunction {
 fn make_snapshot(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, args, js_ctx| {
+            // page.snapshot(selector?, {mode}) or page.snapshot({selector, mode})
             let selector_arg = args.get_or_undefined(0);
-            let selector = if selector_arg.is_undefined() || selector_arg.is_null() {
-                None
+            let (selector, options) = if selector_arg.is_undefined() || selector_arg.is_null() {
+                (None, args.get_or_undefined(1).clone())
+            } else if selector_arg.is_object() {
+                (
+                    get_string_prop(selector_arg, "selector", js_ctx),
+                    selector_arg.clone(),
+                )
             } else {
-                Some(selector_arg.to_string(js_ctx)?.to_std_string_escaped())
+                (
+                    Some(selector_arg.to_string(js_ctx)?.to_std_string_escaped()),
+                    args.get_or_undefined(1).clone(),
+                )
             };
 
-            let params = snapshot::SnapshotParams { selector };
+            let params = snapshot::SnapshotParams {
+                selector,
+                mode: get_string_prop(&options, "mode", js_ctx),
+            };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
+            let counter = ctx.ref_counter();
             let result = ctx
                 .handle
-                .block_on(async { snapshot::snapshot_with_refs(&page, &params).await })
+                .block_on(async { snapshot::snapshot_with_refs(&page, &params, &counter).await })
                 .map_err(js_err)?;
 
             // Persist refs so they can be returned to the server for subsequent tool calls
-            *ctx.snapshot_refs.lock().unwrap() = Some(result.refs);
+            ctx.add_refs(result.refs);
 
             Ok(JsValue::from(boa_engine::js_string!(result.text)))
         })
@@ -805,23 +1294,60 @@ fn make_screenshot(ctx: Arc<ScriptContext>) -> NativeFunction {
         NativeFunction::from_closure(move |_this, args, js_ctx| {
             let options = args.get_or_undefined(0).clone();
 
+            let selector = get_string_prop(&options, "selector", js_ctx)
+                .map(|s| ctx.resolve_ref(&s))
+                .transpose()
+                .map_err(js_err)?;
+            let clip = options
+                .as_object()
+                .and_then(|obj| obj.get(boa_engine::js_string!("clip"), js_ctx).ok())
+                .filter(|v| v.is_object())
+                .map(|clip| {
+                    Ok::<_, String>(screenshot::ClipRect {
+                        x: get_number_prop(&clip, "x", js_ctx).unwrap_or(0.0),
+                        y: get_number_prop(&clip, "y", js_ctx).unwrap_or(0.0),
+                        width: get_number_prop(&clip, "width", js_ctx)
+                            .ok_or("clip.width is required")?,
+                        height: get_number_prop(&clip, "height", js_ctx)
+                            .ok_or("clip.height is required")?,
+                    })
+                })
+                .transpose()
+                .map_err(js_err)?;
+
             let params = screenshot::ScreenshotParams {
-                selector: get_string_prop(&options, "selector", js_ctx),
+                selector,
+                selector_type: parse_selector_type(&options, js_ctx),
                 full_page: get_bool_prop(&options, "full_page", js_ctx),
                 format: get_string_prop(&options, "format", js_ctx),
                 quality: get_number_prop(&options, "quality", js_ctx).map(|n| n as u32),
+                clip,
+                scale: get_number_prop(&options, "scale", js_ctx),
+                max_width: get_number_prop(&options, "max_width", js_ctx).map(|n| n as u32),
+                omit_background: get_bool_prop(&options, "omit_background", js_ctx),
+                annotate: get_bool_prop(&options, "annotate", js_ctx),
             };
 
-            let page = ctx.page.clone();
-            let base64 = ctx
+            let page = ctx.page();
+            let counter = ctx.ref_counter();
+            let mut shot = ctx
                 .handle
-                .block_on(async { screenshot::screenshot(&page, &params).await })
+                .block_on(async { screenshot::screenshot(&page, &params, &counter).await })
                 .map_err(js_err)?;
 
+            // Annotated refs become the script's refs, like page.snapshot()
+            let message = match shot.annotations.take() {
+                Some(snap) => {
+                    ctx.add_refs(snap.refs);
+                    snap.text
+                }
+                None => "Screenshot captured".to_string(),
+            };
+
             // Collect screenshot for return as Content::image
-            ctx.screenshots.lock().unwrap().push(base64);
+            ctx.screenshots.lock().unwrap().push(shot);
 
-            Ok(JsValue::from(boa_engine::js_string!("Screenshot captured")))
+            Ok(JsValue::from(boa_engine::js_string!(message)))
         })
     }
 }
@@ -839,7 +1365,7 @@ fn make_get_text(ctx: Arc<ScriptContext>) -> NativeFunction {
                 selector_type: parse_selector_type(&options, js_ctx),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { dom::get_text(&page, &params).await })
@@ -871,7 +1397,7 @@ fn make_get_html(ctx: Arc<ScriptContext>) -> NativeFunction {
                 max_length: get_number_prop(&options, "max_length", js_ctx).map(|n| n as u32),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { dom::get_html(&page, &params).await })
@@ -896,7 +1422,7 @@ fn make_find_elements(ctx: Arc<ScriptContext>) -> NativeFunction {
                 max_results: get_number_prop(&options, "max_results", js_ctx).map(|n| n as u32),
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { dom::find_elements(&page, &params).await })
@@ -908,6 +1434,34 @@ fn make_find_elements(ctx: Arc<ScriptContext>) -> NativeFunction {
     }
 }
 
+fn make_bounding_box(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let selector = args.get_or_undefined(0).to_string(js_ctx)?;
+            let selector_str = selector.to_std_string_escaped();
+            let selector_str = ctx.resolve_ref(&selector_str).map_err(js_err)?;
+            let options = args.get_or_undefined(1).clone();
+
+            let selector_type = parse_selector_type(&options, js_ctx);
+            let (selector_str, selector_type) = crate::selectors::normalize_selector_type(&selector_str, selector_type.unwrap_or_default());
+
+            let params = dom::GetBoundingBoxParams {
+                selector: selector_str,
+                selector_type: Some(selector_type),
+                scroll_into_view: get_bool_prop(&options, "scroll_into_view", js_ctx),
+            };
+
+            let page = ctx.page();
+            let result = ctx
+                .handle
+                .block_on(async { dom::get_bounding_box(&page, &params).await })
+                .map_err(js_err)?;
+
+            Ok(json_to_js(&result, js_ctx))
+        })
+    }
+}
+
 fn make_js(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, args, js_ctx| {
@@ -935,7 +1489,7 @@ fn make_js(ctx: Arc<ScriptContext>) -> NativeFunction {
                 expression: expr_str,
             };
 
-            let page = ctx.page.clone();
+            let page = ctx.page();
             let result = ctx
                 .handle
                 .block_on(async { javascript::execute_js(&page, &params).await })
@@ -956,6 +1510,7 @@ fn make_read_console(ctx: Arc<ScriptContext>) -> NativeFunction {
                 clear: get_bool_prop(&options, "clear", js_ctx),
                 pattern: get_string_prop(&options, "pattern", js_ctx),
                 limit: get_number_prop(&options, "limit", js_ctx).map(|n| n as u32),
+                tab_id: get_string_prop(&options, "tab_id", js_ctx),
             };
 
             let console_log = ctx.console_log.clone();
@@ -1007,11 +1562,16 @@ fn make_enable_network(ctx: Arc<ScriptContext>) -> NativeFunction {
             };
 
             let network_log = ctx.network_log.clone();
-            let page = ctx.page.clone();
+            let tabs = ctx.tabs().ok();
+            let page = ctx.page();
             ctx.handle
                 .block_on(async {
-                    network::network_enable(&network_log, &enable_params).await?;
-                    network::start_listening(&page, network_log).await
+                    // Capture every tab when running against the pool
+                    let pages = match tabs {
+                        Some(tabs) => tabs.pages().await.0,
+                        None => vec![page],
+                    };
+                    network::network_enable(&pages, &network_log, &enable_params).await
                 })
                 .map_err(js_err)?;
 
@@ -1022,6 +1582,15 @@ fn make_enable_network(ctx: Arc<ScriptContext>) -> NativeFunction {
     }
 }
 
+fn make_disable_network(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, _args, _js_ctx| {
+            let stopped = ctx.handle.block_on(ctx.network_log.disable());
+            Ok(JsValue::from(stopped as u32))
+        })
+    }
+}
+
 fn make_get_network_log(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, args, js_ctx| {
@@ -1031,6 +1600,8 @@ fn make_get_network_log(ctx: Arc<ScriptContext>) -> NativeFunction {
                 url_pattern: get_string_prop(&options, "url_pattern", js_ctx),
                 method: get_string_prop(&options, "method", js_ctx),
                 status: get_number_prop(&options, "status", js_ctx).map(|n| n as u32),
+                tab_id: get_string_prop(&options, "tab_id", js_ctx),
+                failed_only: get_bool_prop(&options, "failed_only", js_ctx),
                 include_headers: get_bool_prop(&options, "include_headers", js_ctx),
                 limit: get_number_prop(&options, "limit", js_ctx).map(|n| n as u32),
             };
@@ -1046,6 +1617,219 @@ fn make_get_network_log(ctx: Arc<ScriptContext>) -> NativeFunction {
     }
 }
 
+fn make_get_websocket_log(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let options = args.get_or_undefined(0).clone();
+
+            let params = websocket::GetWebSocketLogParams {
+                url_pattern: get_string_prop(&options, "url_pattern", js_ctx),
+                connection_id: get_string_prop(&options, "connection_id", js_ctx),
+                direction: get_string_prop(&options, "direction", js_ctx),
+                payload_regex: get_string_prop(&options, "payload_regex", js_ctx),
+                limit: get_number_prop(&options, "limit", js_ctx).map(|n| n as u32),
+            };
+
+            let messages = ctx
+                .handle
+                .block_on(websocket::get_websocket_log(
+                    &ctx.network_log.websockets,
+                    &params,
+                ))
+                .map_err(js_err)?;
+
+            Ok(json_to_js(&serde_json::to_value(messages).map_err(js_err)?, js_ctx))
+        })
+    }
+}
+
+fn make_get_response_body(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let request_id = args
+                .get_or_undefined(0)
+                .to_string(js_ctx)?
+                .to_std_string_escaped();
+            let options = args.get_or_undefined(1).clone();
+            let params = network::GetResponseBodyParams {
+                request_id,
+                max_bytes: get_number_prop(&options, "max_bytes", js_ctx).map(|n| n as u32),
+            };
+
+            let network_log = ctx.network_log.clone();
+            let tabs = ctx.tabs().ok();
+            let page = ctx.page();
+            let result = ctx
+                .handle
+                .block_on(async {
+                    match tabs {
+                        Some(tabs) => network::get_response_body(tabs, &network_log, &params).await,
+                        None => {
+                            network::get_response_body_from_page(&page, &network_log, &params)
+                                .await
+                        }
+                    }
+                })
+                .map_err(js_err)?;
+
+            Ok(json_to_js(&serde_json::to_value(result).map_err(js_err)?, js_ctx))
+        })
+    }
+}
+
+fn make_export_har(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let filename = args.get_or_undefined(0);
+            let filename = if filename.is_undefined() || filename.is_null() {
+                None
+            } else {
+                Some(filename.to_string(js_ctx)?.to_std_string_escaped())
+            };
+            let options = args.get_or_undefined(1).clone();
+            let params = har::ExportHarParams {
+                filename,
+                include_bodies: get_bool_prop(&options, "include_bodies", js_ctx),
+                max_body_bytes: get_number_prop(&options, "max_body_bytes", js_ctx)
+                    .map(|n| n as u32),
+            };
+
+            let network_log = ctx.network_log.clone();
+            let tabs = ctx.tabs().ok().cloned();
+            let page = ctx.page();
+            let result = ctx
+                .handle
+                .block_on(async {
+                    let pages = match tabs {
+                        Some(tabs) => tabs.pages().await.0,
+                        None => vec![page],
+                    };
+                    har::export_har(&pages, &network_log, &ctx.output_dir, &params).await
+                })
+                .map_err(js_err)?;
+
+            Ok(json_to_js(&serde_json::to_value(result).map_err(js_err)?, js_ctx))
+        })
+    }
+}
+
+fn make_route(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let pattern = args
+                .get_or_undefined(0)
+                .to_string(js_ctx)?
+                .to_std_string_escaped();
+            // The handler is declarative: the same options as the route tool
+            let handler = args.get_or_undefined(1);
+            if handler.is_callable() {
+                return Err(js_err(
+                    "page.route(pattern, handler) takes an options object ({status, body} | {action:'abort'} | {action:'continue'}), not a callback",
+                ));
+            }
+            let mut handler = match handler.to_json(js_ctx)? {
+                Some(serde_json::Value::Object(map)) => map,
+                _ => return Err(js_err("page.route(pattern, handler) needs a handler object")),
+            };
+            handler.insert("url_pattern".into(), serde_json::Value::String(pattern));
+            let params: route::RouteParams =
+                serde_json::from_value(serde_json::Value::Object(handler)).map_err(js_err)?;
+
+            let tabs = ctx.tabs().map_err(js_err)?;
+            let route = ctx
+                .handle
+                .block_on(async {
+                    let (pages, _) = tabs.pages().await;
+                    route::add_route(&pages, tabs.routes(), &params).await
+                })
+                .map_err(js_err)?;
+
+            Ok(json_to_js(&serde_json::to_value(route).map_err(js_err)?, js_ctx))
+        })
+    }
+}
+
+fn make_unroute(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let target = args.get_or_undefined(0);
+            let params = if target.is_undefined() || target.is_null() {
+                route::UnrouteParams {
+                    id: None,
+                    url_pattern: None,
+                    all: Some(true),
+                }
+            } else if target.is_number() {
+                route::UnrouteParams {
+                    id: Some(target.to_number(js_ctx)? as u32),
+                    url_pattern: None,
+                    all: None,
+                }
+            } else {
+                route::UnrouteParams {
+                    id: None,
+                    url_pattern: Some(target.to_string(js_ctx)?.to_std_string_escaped()),
+                    all: None,
+                }
+            };
+
+            let tabs = ctx.tabs().map_err(js_err)?;
+            let removed = ctx
+                .handle
+                .block_on(async {
+                    let (pages, _) = tabs.pages().await;
+                    route::remove_routes(&pages, tabs.routes(), &params).await
+                })
+                .map_err(js_err)?;
+
+            Ok(JsValue::from(removed as u32))
+        })
+    }
+}
+
+fn make_routes(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, _args, js_ctx| {
+            let tabs = ctx.tabs().map_err(js_err)?;
+            let routes = ctx.handle.block_on(tabs.routes().list());
+            Ok(json_to_js(&serde_json::to_value(routes).map_err(js_err)?, js_ctx))
+        })
+    }
+}
+
+fn make_set_network(ctx: Arc<ScriptContext>) -> NativeFunction {
+    unsafe {
+        NativeFunction::from_closure(move |_this, args, js_ctx| {
+            let options = args.get_or_undefined(0);
+            // page.setNetwork('slow-3g') is shorthand for {preset: 'slow-3g'}
+            let params: emulation::EmulateNetworkParams = if options.is_string() {
+                emulation::EmulateNetworkParams {
+                    preset: Some(options.to_string(js_ctx)?.to_std_string_escaped()),
+                    ..Default::default()
+                }
+            } else {
+                match options.to_json(js_ctx)? {
+                    Some(value @ serde_json::Value::Object(_)) => {
+                        serde_json::from_value(value).map_err(js_err)?
+                    }
+                    _ => return Err(js_err("page.setNetwork needs an options object or preset name")),
+                }
+            };
+
+            let tabs = ctx.tabs().map_err(js_err)?;
+            let state = ctx
+                .handle
+                .block_on(async {
+                    let (pages, _) = tabs.pages().await;
+                    emulation::emulate_network(&pages, tabs.emulation(), &params).await
+                })
+                .map_err(js_err)?;
+
+            Ok(json_to_js(&serde_json::to_value(state).map_err(js_err)?, js_ctx))
+        })
+    }
+}
+
 fn make_wait_for_network_idle(ctx: Arc<ScriptContext>) -> NativeFunction {
     unsafe {
         NativeFunction::from_closure(move |_this, args, js_ctx| {
@@ -1,13 +1,116 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use anyhow::{Context, Result};
+use chromiumoxide::cdp::browser_protocol::accessibility::{
+    AxNode, AxPropertyName, AxValue, GetFullAxTreeParams,
+};
+use chromiumoxide::cdp::browser_protocol::dom::{BackendNodeId, ResolveNodeParams};
+use chromiumoxide::cdp::js_protocol::runtime::{
+    CallArgument, CallFunctionOnParams, ReleaseObjectGroupParams,
+};
 use chromiumoxide::page::Page;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize, schemars::JsonSchema)]
+/// Attribute that pins a snapshot ref to its element.
+pub const REF_ATTRIBUTE: &str = "data-remix-ref";
+
+/// Next unused ref number per tab, shared by one browser session's tabs. The
+/// in-page counter dies with its document, so a tab that moves on without the
+/// navigate tool (link click, form submit) would otherwise hand out `e0` again;
+/// stale refs must not find new elements.
+#[derive(Debug, Clone, Default)]
+pub struct RefCounter {
+    next: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl RefCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn next(&self, target_id: &str) -> u64 {
+        let counters = self.next.lock().unwrap();
+        counters.get(target_id).copied().unwrap_or(0)
+    }
+
+    fn record(&self, target_id: &str, next: u64) {
+        let mut counters = self.next.lock().unwrap();
+        let entry = counters.entry(target_id.to_string()).or_default();
+        *entry = (*entry).max(next);
+    }
+
+    /// Drop the counter of a tab that has closed.
+    pub fn forget(&self, target_id: &str) {
+        self.next.lock().unwrap().remove(target_id);
+    }
+}
+
+/// Returns the ref pinned to `node`, assigning the next free one if it has none.
+/// Expects `REF_ATTR` and `REF_FLOOR` (the tab's next unused ref number) in scope;
+/// `seen` holds the refs handed out in this pass.
+const PIN_REF_JS: &str = r#"function pinRef(node, seen) {
+                let state = window.__remixRefs;
+                if (!state) {
+                    let next = REF_FLOOR;
+                    document.querySelectorAll('[' + REF_ATTR + ']').forEach(function(el) {
+                        const n = parseInt((el.getAttribute(REF_ATTR) || '').slice(1), 10);
+                        if (n >= next) next = n + 1;
+                    });
+                    state = window.__remixRefs = { next: next };
+                }
+                // Another document in this tab may have issued refs since
+                state.next = Math.max(state.next, REF_FLOOR);
+                let refId = node.getAttribute(REF_ATTR);
+                // A cloned element carries its original's ref; give it its own
+                if (!/^e\d+$/.test(refId || '') || seen.has(refId)) {
+                    refId = 'e' + state.next++;
+                    node.setAttribute(REF_ATTR, refId);
+                }
+                seen.add(refId);
+                return refId;
+            }"#;
+
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(default)]
 pub struct SnapshotParams {
     #[schemars(description = "CSS selector to scope snapshot to a subtree (default: entire page)")]
     pub selector: Option<String>,
+    #[schemars(
+        description = "Where roles and names come from: 'dom' (default, fast) or 'accessibility' (Chrome's computed accessibility tree: exact roles and names, descriptions, tables, lists, dialogs and alerts, aria-hidden respected)"
+    )]
+    pub mode: Option<String>,
+}
+
+impl SnapshotParams {
+    /// Whether this is the whole-page DOM snapshot that tool results carry, and so
+    /// can serve as their diff baseline.
+    pub fn is_page_snapshot(&self) -> bool {
+        self.selector.is_none()
+            && matches!(
+                SnapshotMode::parse(self.mode.as_deref()),
+                Ok(SnapshotMode::Dom)
+            )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SnapshotMode {
+    Dom,
+    Accessibility,
+}
+
+impl SnapshotMode {
+    fn parse(mode: Option<&str>) -> Result<Self> {
+        match mode.map(str::to_ascii_lowercase).as_deref() {
+            None | Some("dom") => Ok(Self::Dom),
+            Some("accessibility") | Some("ax") | Some("a11y") => Ok(Self::Accessibility),
+            Some(other) => anyhow::bail!(
+                "Unknown snapshot mode '{}'. Use 'dom' or 'accessibility'.",
+                other
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,9 +124,20 @@ struct SnapshotPayload {
     lines: Vec<String>,
     refs: HashMap<String, String>,
     message: Option<String>,
+    /// The page's ref counter after this pass.
+    #[serde(default)]
+    next: u64,
 }
 
-pub async fn snapshot_with_refs(page: &Page, params: &SnapshotParams) -> Result<SnapshotOutput> {
+pub async fn snapshot_with_refs(
+    page: &Page,
+    params: &SnapshotParams,
+    counter: &RefCounter,
+) -> Result<SnapshotOutput> {
+    if SnapshotMode::parse(params.mode.as_deref())? == SnapshotMode::Accessibility {
+        return ax_snapshot(page, params, counter).await;
+    }
+
     let root_selector = params.selector.as_deref().unwrap_or("body");
     let sel_str = serde_json::to_string(root_selector)?;
 
@@ -52,9 +166,16 @@ pub async fn snapshot_with_refs(page: &Page, params: &SnapshotParams) -> Result<
 
             const lines = [];
             const refs = {{}};
-            let idx = 0;
             let totalElements = 0;
 
+            // Refs live on the elements themselves, so an element keeps its ref
+            // across snapshots for as long as it stays in the document.
+            const REF_ATTR = {attr};
+            const REF_FLOOR = {floor};
+            {pin_ref}
+            const seenRefs = new Set();
+            const nextRef = () => window.__remixRefs ? window.__remixRefs.next : REF_FLOOR;
+
             function cssEscape(value) {{
                 if (window.CSS && typeof window.CSS.escape === 'function') {{
                     return window.CSS.escape(value);
@@ -62,47 +183,6 @@ pub async fn snapshot_with_refs(page: &Page, params: &SnapshotParams) -> Result<
                 return value.replace(/[^a-zA-Z0-9_-]/g, '\\\\$&');
             }}
 
-            function buildSelector(node) {{
-                if (!node || node.nodeType !== Node.ELEMENT_NODE) return '';
-                if (node.id) return '#' + cssEscape(node.id);
-
-                const parts = [];
-                let current = node;
-                while (current && current.nodeType === Node.ELEMENT_NODE) {{
-                    let part = current.tagName.toLowerCase();
-                    if (current.id) {{
-                        part += '#' + cssEscape(current.id);
-                        parts.unshift(part);
-                        break;
-                    }}
-
-                    const classNames = (current.getAttribute('class') || '')
-                        .trim()
-                        .split(/\s+/)
-                        .filter(Boolean)
-                        .slice(0, 2);
-                    if (classNames.length > 0) {{
-                        part += '.' + classNames.map(cssEscape).join('.');
-                    }}
-
-                    let sibling = current;
-                    let nth = 1;
-                    while ((sibling = sibling.previousElementSibling)) {{
-                        if (sibling.tagName === current.tagName) nth++;
-                    }}
-                    part += `:nth-of-type(${{nth}})`;
-                    parts.unshift(part);
-
-                    current = current.parentElement;
-                    if (current === document.body) {{
-                        parts.unshift('body');
-                        break;
-                    }}
-                }}
-
-                return parts.join(' > ');
-            }}
-
             function isVisible(node) {{
                 const style = getComputedStyle(node);
                 if (style.display === 'none' || style.visibility === 'hidden') return false;
@@ -312,11 +392,10 @@ pub async fn snapshot_with_refs(page: &Page, params: &SnapshotParams) -> Result<
                 if (tag === 'details' && node.open) parts.push('[expanded]');
 
                 if (interactive) {{
-                    const refId = `e${{idx}}`;
-                    const selector = buildSelector(node);
-                    refs[refId] = selector;
+                    const refId = pinRef(node, seenRefs);
+                    // Unquoted so the selector can be spliced into page.js() strings
+                    refs[refId] = `[${{REF_ATTR}}=${{refId}}]`;
                     parts.push(`[ref=${{refId}}]`);
-                    idx++;
                 }}
 
                 lines.push(parts.join(' '));
@@ -351,13 +430,17 @@ pub async fn snapshot_with_refs(page: &Page, params: &SnapshotParams) -> Result<
                 return {{
                     lines: [],
                     refs: {{}},
-                    message: 'No interactive elements found'
+                    message: 'No interactive elements found',
+                    next: nextRef()
                 }};
             }}
 
-            return {{ lines, refs, message: null }};
+            return {{ lines, refs, message: null, next: nextRef() }};
         }})()"#,
-        sel = sel_str
+        sel = sel_str,
+        attr = serde_json::to_string(REF_ATTRIBUTE)?,
+        floor = counter.next(page.target_id().as_ref()),
+        pin_ref = PIN_REF_JS
     );
 
     let payload: SnapshotPayload = page
@@ -366,6 +449,7 @@ pub async fn snapshot_with_refs(page: &Page, params: &SnapshotParams) -> Result<
         .context("Failed to get page snapshot")?
         .into_value()
         .context("Failed to parse snapshot result")?;
+    counter.record(page.target_id().as_ref(), payload.next);
 
     let text = if let Some(message) = payload.message {
         message
@@ -379,6 +463,458 @@ pub async fn snapshot_with_refs(page: &Page, params: &SnapshotParams) -> Result<
     })
 }
 
-pub async fn snapshot(page: &Page, params: &SnapshotParams) -> Result<String> {
-    Ok(snapshot_with_refs(page, params).await?.text)
+pub async fn snapshot(
+    page: &Page,
+    params: &SnapshotParams,
+    counter: &RefCounter,
+) -> Result<String> {
+    Ok(snapshot_with_refs(page, params, counter).await?.text)
+}
+
+/// Whether the element `ref_id` was issued for is still in the page. Refs are
+/// pinned to their elements, so a missing element means the ref is stale.
+pub async fn ref_attached(page: &Page, ref_id: &str) -> Result<bool> {
+    let selector = serde_json::to_string(&ref_selector(ref_id))?;
+    page.evaluate(format!("document.querySelector({}) !== null", selector))
+        .await
+        .context("Failed to check ref")?
+        .into_value()
+        .context("Failed to parse ref check")
+}
+
+/// What the server appends to tool results after each action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutoSnapshotMode {
+    /// The whole snapshot every time.
+    #[default]
+    Full,
+    /// Only lines that changed since the previous snapshot of the same page.
+    Diff,
+    /// Nothing; call snapshot explicitly.
+    Off,
+}
+
+impl AutoSnapshotMode {
+    pub fn parse(mode: &str) -> Result<Self> {
+        match mode.to_ascii_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "diff" => Ok(Self::Diff),
+            "off" | "none" => Ok(Self::Off),
+            other => anyhow::bail!(
+                "Unknown auto-snapshot mode '{}'. Use full, diff, or off.",
+                other
+            ),
+        }
+    }
+}
+
+fn line_ref(line: &str) -> Option<&str> {
+    let start = line.rfind("[ref=")? + "[ref=".len();
+    let len = line[start..].find(']')?;
+    Some(&line[start..start + len])
+}
+
+/// Compare two snapshots of the same page. Lines with a ref are matched by ref,
+/// so an element whose state changed shows up as `~`; other lines are matched
+/// by text. Removed lines (`-`) come first, then added (`+`) and changed (`~`)
+/// lines in page order. Empty when nothing changed.
+pub fn diff_snapshots(previous: &str, current: &str) -> Vec<String> {
+    let mut previous_refs: HashMap<&str, &str> = HashMap::new();
+    let mut previous_plain: Vec<&str> = Vec::new();
+    for line in previous.lines() {
+        match line_ref(line) {
+            Some(ref_id) => {
+                previous_refs.insert(ref_id, line);
+            }
+            None => previous_plain.push(line),
+        }
+    }
+
+    let mut current_refs = std::collections::HashSet::new();
+    let mut changes = Vec::new();
+    for line in current.lines() {
+        match line_ref(line) {
+            Some(ref_id) => {
+                current_refs.insert(ref_id);
+                match previous_refs.get(ref_id) {
+                    Some(old) if *old == line => {}
+                    Some(_) => changes.push(format!("~ {}", line)),
+                    None => changes.push(format!("+ {}", line)),
+                }
+            }
+            None => match previous_plain.iter().position(|old| *old == line) {
+                Some(i) => {
+                    previous_plain.remove(i);
+                }
+                None => changes.push(format!("+ {}", line)),
+            },
+        }
+    }
+
+    let mut removed: Vec<String> = previous
+        .lines()
+        .filter(|line| line_ref(line).is_some_and(|ref_id| !current_refs.contains(ref_id)))
+        .chain(previous_plain)
+        .map(|line| format!("- {}", line))
+        .collect();
+    removed.extend(changes);
+    removed
+}
+
+/// Roles that get a ref, as Chrome's accessibility tree names them.
+const AX_INTERACTIVE_ROLES: &[&str] = &[
+    "button",
+    "link",
+    "textbox",
+    "searchbox",
+    "checkbox",
+    "radio",
+    "combobox",
+    "tab",
+    "menuitem",
+    "menuitemcheckbox",
+    "menuitemradio",
+    "switch",
+    "listbox",
+    "option",
+    "slider",
+    "spinbutton",
+    "treeitem",
+];
+
+/// Non-interactive roles worth a line for context.
+const AX_CONTEXT_ROLES: &[&str] = &[
+    "heading",
+    "navigation",
+    "main",
+    "banner",
+    "contentinfo",
+    "complementary",
+    "search",
+    "form",
+    "region",
+    "img",
+    "image",
+    "figure",
+    "article",
+    "table",
+    "grid",
+    "treegrid",
+    "list",
+    "tree",
+    "menu",
+    "menubar",
+    "toolbar",
+    "tablist",
+    "tabpanel",
+    "dialog",
+    "alertdialog",
+    "alert",
+    "status",
+    "progressbar",
+    "meter",
+];
+
+/// Line limit, matching the DOM snapshot.
+const MAX_LINES: usize = 200;
+
+fn ax_text(value: Option<&AxValue>) -> Option<String> {
+    let text = match value?.value.as_ref()? {
+        serde_json::Value::String(s) => s.trim().to_string(),
+        serde_json::Value::Null => return None,
+        other => other.to_string(),
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+fn ax_property<'a>(node: &'a AxNode, name: &AxPropertyName) -> Option<&'a AxValue> {
+    node.properties
+        .as_ref()?
+        .iter()
+        .find(|p| p.name == *name)
+        .map(|p| &p.value)
+}
+
+fn ax_flag(node: &AxNode, name: &AxPropertyName) -> bool {
+    ax_text(ax_property(node, name)).is_some_and(|v| v == "true")
+}
+
+fn clip(text: &str) -> String {
+    match text.char_indices().nth(60) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Format a node in the DOM snapshot's line format, minus the ref.
+fn ax_line(node: &AxNode, role: &str) -> String {
+    let mut parts = vec![role.to_string()];
+    if let Some(name) = ax_text(node.name.as_ref()) {
+        parts.push(format!("\"{}\"", clip(&name)));
+    }
+
+    let value = ax_text(node.value.as_ref());
+    let min = ax_text(ax_property(node, &AxPropertyName::Valuemin));
+    let max = ax_text(ax_property(node, &AxPropertyName::Valuemax));
+    match (value, min, max) {
+        (Some(value), Some(min), Some(max)) => {
+            parts.push(format!("value={} [{}-{}]", value, min, max))
+        }
+        (Some(value), _, _) if ["slider", "spinbutton", "progressbar", "meter"].contains(&role) => {
+            parts.push(format!("value={}", value))
+        }
+        (Some(value), _, _) => parts.push(format!("value=\"{}\"", clip(&value))),
+        _ => {}
+    }
+
+    match ax_text(ax_property(node, &AxPropertyName::Checked)).as_deref() {
+        Some("true") => parts.push("[checked]".into()),
+        Some("mixed") => parts.push("[mixed]".into()),
+        _ => {}
+    }
+    for (name, label) in [
+        (AxPropertyName::Disabled, "[disabled]"),
+        (AxPropertyName::Required, "[required]"),
+        (AxPropertyName::Expanded, "[expanded]"),
+        (AxPropertyName::Selected, "[selected]"),
+        (AxPropertyName::Pressed, "[pressed]"),
+    ] {
+        if ax_flag(node, &name) {
+            parts.push(label.into());
+        }
+    }
+    if let Some(description) = ax_text(node.description.as_ref()) {
+        parts.push(format!("description=\"{}\"", clip(&description)));
+    }
+    parts.join(" ")
+}
+
+/// Snapshot from Chrome's computed accessibility tree. Refs are pinned to the
+/// backing DOM elements exactly as in the DOM snapshot, so they resolve the same way.
+async fn ax_snapshot(
+    page: &Page,
+    params: &SnapshotParams,
+    counter: &RefCounter,
+) -> Result<SnapshotOutput> {
+    let empty = |message: &str| SnapshotOutput {
+        text: message.to_string(),
+        refs: HashMap::new(),
+    };
+    let nodes = page
+        .execute(GetFullAxTreeParams::default())
+        .await
+        .context("Failed to get accessibility tree")?
+        .result
+        .nodes;
+    let by_id: HashMap<&str, &AxNode> = nodes
+        .iter()
+        .map(|n| (n.node_id.inner().as_str(), n))
+        .collect();
+
+    let root = match params.selector {
+        Some(ref selector) => {
+            let Ok(element) = page.find_element(selector.as_str()).await else {
+                return Ok(empty("No elements found (selector not matched)"));
+            };
+            nodes
+                .iter()
+                .find(|n| n.backend_dom_node_id == Some(element.backend_node_id))
+        }
+        None => nodes.iter().find(|n| n.parent_id.is_none()),
+    };
+    let Some(root) = root else {
+        return Ok(empty("No elements found (selector not matched)"));
+    };
+
+    // Depth-first, in document order; ignored nodes (aria-hidden, display: none,
+    // presentational wrappers) are skipped but their children still get visited.
+    let mut lines = Vec::new();
+    let mut interactive: Vec<(usize, BackendNodeId)> = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if let Some(ref children) = node.child_ids {
+            stack.extend(
+                children
+                    .iter()
+                    .rev()
+                    .filter_map(|id| by_id.get(id.inner().as_str())),
+            );
+        }
+        if node.ignored {
+            continue;
+        }
+        let Some(role) = ax_text(node.role.as_ref()) else {
+            continue;
+        };
+        let is_interactive = AX_INTERACTIVE_ROLES.contains(&role.as_str());
+        if !is_interactive && !AX_CONTEXT_ROLES.contains(&role.as_str()) {
+            continue;
+        }
+        if lines.len() >= MAX_LINES {
+            lines.push("... and more elements (showing first 200)".to_string());
+            break;
+        }
+        if let (true, Some(backend_id)) = (is_interactive, node.backend_dom_node_id) {
+            interactive.push((lines.len(), backend_id));
+        }
+        lines.push(ax_line(node, &role));
+    }
+    if lines.is_empty() {
+        return Ok(empty("No interactive elements found"));
+    }
+
+    let backend_ids: Vec<BackendNodeId> = interactive.iter().map(|(_, id)| *id).collect();
+    let ref_ids = pin_refs(page, &backend_ids, counter).await?;
+    let mut refs = HashMap::new();
+    for ((line, _), ref_id) in interactive.iter().zip(ref_ids) {
+        if let Some(ref_id) = ref_id {
+            lines[*line].push_str(&format!(" [ref={}]", ref_id));
+            refs.insert(ref_id.clone(), ref_selector(&ref_id));
+        }
+    }
+
+    Ok(SnapshotOutput {
+        text: lines.join("\n"),
+        refs,
+    })
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PinnedRefs {
+    refs: Vec<Option<String>>,
+    #[serde(default)]
+    next: u64,
+}
+
+/// Pin refs to DOM nodes by backend id, returning each node's ref (`None` for
+/// nodes that could not be resolved, e.g. text or nodes in other frames).
+async fn pin_refs(
+    page: &Page,
+    backend_ids: &[BackendNodeId],
+    counter: &RefCounter,
+) -> Result<Vec<Option<String>>> {
+    const GROUP: &str = "remix-snapshot";
+    let resolved = futures::future::join_all(backend_ids.iter().map(|id| {
+        page.execute(ResolveNodeParams {
+            backend_node_id: Some(*id),
+            object_group: Some(GROUP.to_string()),
+            ..Default::default()
+        })
+    }))
+    .await;
+    let objects: Vec<Option<_>> = resolved
+        .into_iter()
+        .map(|r| r.ok().and_then(|r| r.result.object.object_id.clone()))
+        .collect();
+    let Some(target) = objects.iter().flatten().next().cloned() else {
+        return Ok(vec![None; backend_ids.len()]);
+    };
+
+    let arguments: Vec<CallArgument> = objects
+        .iter()
+        .map(|object_id| match object_id {
+            Some(object_id) => CallArgument {
+                object_id: Some(object_id.clone()),
+                ..Default::default()
+            },
+            None => CallArgument {
+                value: Some(serde_json::Value::Null),
+                ..Default::default()
+            },
+        })
+        .collect();
+    let function = format!(
+        "function(...nodes) {{
+            const REF_ATTR = {attr};
+            const REF_FLOOR = {floor};
+            {pin_ref}
+            const seen = new Set();
+            const refs = nodes.map(n => n && n.nodeType === Node.ELEMENT_NODE ? pinRef(n, seen) : null);
+            return {{ refs, next: window.__remixRefs ? window.__remixRefs.next : REF_FLOOR }};
+        }}",
+        attr = serde_json::to_string(REF_ATTRIBUTE)?,
+        floor = counter.next(page.target_id().as_ref()),
+        pin_ref = PIN_REF_JS
+    );
+    let call = CallFunctionOnParams::builder()
+        .function_declaration(function)
+        .object_id(target)
+        .arguments(arguments)
+        .return_by_value(true)
+        .build()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let result = page.execute(call).await;
+    let _ = page.execute(ReleaseObjectGroupParams::new(GROUP)).await;
+    let value = result
+        .context("Failed to pin snapshot refs")?
+        .result
+        .result
+        .value
+        .clone()
+        .unwrap_or_default();
+    let pinned: PinnedRefs = serde_json::from_value(value).unwrap_or_default();
+    counter.record(page.target_id().as_ref(), pinned.next);
+    Ok((0..backend_ids.len())
+        .map(|i| pinned.refs.get(i).cloned().flatten())
+        .collect())
+}
+
+fn ref_selector(ref_id: &str) -> String {
+    format!("[{}={}]", REF_ATTRIBUTE, ref_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let previous = "heading \"Settings\"\n\
+            checkbox \"Agree\" [ref=e0]\n\
+            button \"Cancel\" [ref=e1]\n\
+            button \"Next\" [ref=e2]";
+        let current = "heading \"Settings\"\n\
+            checkbox \"Agree\" [checked] [ref=e0]\n\
+            button \"Next\" [ref=e2]\n\
+            button \"Save\" [ref=e3]\n\
+            heading \"Done\"";
+        assert_eq!(
+            diff_snapshots(previous, current),
+            [
+                "- button \"Cancel\" [ref=e1]",
+                "~ checkbox \"Agree\" [checked] [ref=e0]",
+                "+ button \"Save\" [ref=e3]",
+                "+ heading \"Done\"",
+            ]
+        );
+        assert!(diff_snapshots(current, current).is_empty());
+    }
+
+    #[test]
+    fn test_auto_snapshot_mode_parse() {
+        assert_eq!(
+            AutoSnapshotMode::parse("Diff").unwrap(),
+            AutoSnapshotMode::Diff
+        );
+        assert_eq!(
+            AutoSnapshotMode::parse("off").unwrap(),
+            AutoSnapshotMode::Off
+        );
+        assert!(AutoSnapshotMode::parse("partial").is_err());
+    }
+
+    #[test]
+    fn test_ref_counter_is_per_tab_and_forgotten_on_close() {
+        let counter = RefCounter::new();
+        counter.record("tab-a", 5);
+        // Never moves backwards, e.g. when an older document reports in
+        counter.record("tab-a", 2);
+        assert_eq!(counter.next("tab-a"), 5);
+        assert_eq!(counter.next("tab-b"), 0);
+
+        // Clones share the numbering, as a session's tabs do
+        counter.clone().forget("tab-a");
+        assert_eq!(counter.next("tab-a"), 0);
+        assert!(counter.next.lock().unwrap().is_empty());
+    }
 }
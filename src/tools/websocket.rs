@@ -0,0 +1,253 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+/// Messages kept before the oldest are dropped.
+const MAX_MESSAGES: usize = 1000;
+/// Payloads larger than this are cut off in the log.
+const MAX_PAYLOAD: usize = 16 * 1024;
+
+/// One WebSocket or EventSource event. Frames and SSE messages have a
+/// `direction`; connection lifecycle events (open, close, error) do not.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocketMessage {
+    /// Position in the log; increases across connections.
+    pub seq: u64,
+    /// CDP request id shared by every message on the connection.
+    pub connection_id: String,
+    /// Target id of the tab that owns the connection.
+    pub tab_id: String,
+    /// `websocket` or `eventsource`.
+    pub protocol: String,
+    pub url: String,
+    /// `open`, `message`, `close`, or `error`.
+    pub event: String,
+    /// `sent` or `received`, for messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<String>,
+    /// WebSocket frame opcode: 1 text, 2 binary, 8 close, 9 ping, 10 pong.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opcode: Option<u8>,
+    /// SSE `event:` field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_name: Option<String>,
+    /// SSE `id:` field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+    /// Frame payload, SSE data, or error text. Binary frames are base64-encoded.
+    pub payload: String,
+    /// Payload size in bytes before truncation.
+    pub size: usize,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Wall-clock time the event was captured, in seconds since the epoch.
+    pub timestamp: f64,
+}
+
+impl SocketMessage {
+    pub(crate) fn new(
+        protocol: &str,
+        connection_id: &str,
+        tab_id: &str,
+        url: &str,
+        event: &str,
+    ) -> Self {
+        Self {
+            seq: 0,
+            connection_id: connection_id.to_string(),
+            tab_id: tab_id.to_string(),
+            protocol: protocol.to_string(),
+            url: url.to_string(),
+            event: event.to_string(),
+            direction: None,
+            opcode: None,
+            event_name: None,
+            event_id: None,
+            payload: String::new(),
+            size: 0,
+            truncated: false,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub(crate) fn with_payload(mut self, direction: Option<&str>, payload: &str) -> Self {
+        let kept = super::network::truncate_utf8(payload, MAX_PAYLOAD);
+        self.direction = direction.map(str::to_string);
+        self.size = payload.len();
+        self.truncated = kept.len() < payload.len();
+        self.payload = kept.to_string();
+        self
+    }
+}
+
+/// Bounded log of WebSocket frames and EventSource messages, filled by the
+/// network capture listeners.
+#[derive(Debug, Clone, Default)]
+pub struct WebSocketLog {
+    messages: Arc<Mutex<VecDeque<SocketMessage>>>,
+    next_seq: Arc<AtomicU64>,
+}
+
+impl WebSocketLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn add(&self, mut message: SocketMessage) {
+        message.seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        let mut messages = self.messages.lock().await;
+        if messages.len() >= MAX_MESSAGES {
+            messages.pop_front();
+        }
+        messages.push_back(message);
+    }
+
+    pub async fn messages(&self) -> Vec<SocketMessage> {
+        self.messages.lock().await.iter().cloned().collect()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct GetWebSocketLogParams {
+    #[schemars(description = "Filter by connection URL pattern")]
+    pub url_pattern: Option<String>,
+    #[schemars(
+        description = "Only messages on this connection (connection_id from an earlier result)"
+    )]
+    pub connection_id: Option<String>,
+    #[schemars(
+        description = "Only messages going this way: 'sent' or 'received'. Leaves out open/close/error events."
+    )]
+    pub direction: Option<String>,
+    #[schemars(description = "Regular expression the payload must match")]
+    pub payload_regex: Option<String>,
+    #[schemars(
+        description = "Maximum number of messages to return, most recent last (default: 100)"
+    )]
+    pub limit: Option<u32>,
+}
+
+/// Filter the socket log, keeping the most recent `limit` messages.
+pub async fn get_websocket_log(
+    log: &WebSocketLog,
+    params: &GetWebSocketLogParams,
+) -> Result<Vec<SocketMessage>> {
+    let direction = match params.direction.as_deref().map(str::to_ascii_lowercase) {
+        None => None,
+        Some(d) if d == "sent" || d == "received" => Some(d),
+        Some(d) => anyhow::bail!("Unknown direction '{}'. Use 'sent' or 'received'.", d),
+    };
+    let payload_regex = params
+        .payload_regex
+        .as_deref()
+        .map(regex::Regex::new)
+        .transpose()
+        .context("Invalid payload_regex")?;
+
+    let mut messages: Vec<SocketMessage> = log
+        .messages()
+        .await
+        .into_iter()
+        .filter(|m| {
+            params
+                .url_pattern
+                .as_deref()
+                .is_none_or(|p| m.url.contains(p))
+                && params
+                    .connection_id
+                    .as_deref()
+                    .is_none_or(|id| m.connection_id == id)
+                && direction
+                    .as_deref()
+                    .is_none_or(|d| m.direction.as_deref() == Some(d))
+                && payload_regex
+                    .as_ref()
+                    .is_none_or(|re| re.is_match(&m.payload))
+        })
+        .collect();
+
+    let limit = params.limit.unwrap_or(100) as usize;
+    if messages.len() > limit {
+        messages.drain(..messages.len() - limit);
+    }
+    Ok(messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(connection_id: &str, direction: &str, payload: &str) -> SocketMessage {
+        SocketMessage::new(
+            "websocket",
+            connection_id,
+            "tab",
+            "wss://x.test/live",
+            "message",
+        )
+        .with_payload(Some(direction), payload)
+    }
+
+    #[tokio::test]
+    async fn test_filters_and_limit() {
+        let log = WebSocketLog::new();
+        log.add(SocketMessage::new(
+            "websocket",
+            "1",
+            "tab",
+            "wss://x.test/live",
+            "open",
+        ))
+        .await;
+        log.add(frame("1", "sent", r#"{"type":"subscribe"}"#)).await;
+        log.add(frame("1", "received", r#"{"type":"price","value":3}"#))
+            .await;
+        log.add(frame("2", "received", "pong")).await;
+
+        let params = GetWebSocketLogParams {
+            direction: Some("received".into()),
+            payload_regex: Some(r#""type":"price""#.into()),
+            ..Default::default()
+        };
+        let found = get_websocket_log(&log, &params).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].seq, 2);
+
+        let params = GetWebSocketLogParams {
+            connection_id: Some("1".into()),
+            limit: Some(2),
+            ..Default::default()
+        };
+        let found = get_websocket_log(&log, &params).await.unwrap();
+        assert_eq!(found.iter().map(|m| m.seq).collect::<Vec<_>>(), [1, 2]);
+
+        let bad = GetWebSocketLogParams {
+            payload_regex: Some("(".into()),
+            ..Default::default()
+        };
+        assert!(get_websocket_log(&log, &bad).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_log_is_bounded_and_payloads_truncated() {
+        let log = WebSocketLog::new();
+        for i in 0..MAX_MESSAGES + 5 {
+            log.add(frame("1", "received", &i.to_string())).await;
+        }
+        let messages = log.messages().await;
+        assert_eq!(messages.len(), MAX_MESSAGES);
+        assert_eq!(messages[0].payload, "5");
+
+        let big = frame("1", "sent", &"x".repeat(MAX_PAYLOAD + 10));
+        assert!(big.truncated);
+        assert_eq!(big.size, MAX_PAYLOAD + 10);
+        assert_eq!(big.payload.len(), MAX_PAYLOAD);
+    }
+}
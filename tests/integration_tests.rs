@@ -27,7 +27,7 @@ async fn launch_test_browser() -> (Browser, tokio::task::JoinHandle<()>, tempfil
         .await
         .expect("Failed to launch browser");
 
-    let handle = tokio::spawn(async move { while let Some(_) = handler.next().await {} });
+    let handle = tokio::spawn(async move { while handler.next().await.is_some() {} });
 
     // Keep tmp_dir alive — it gets cleaned up on drop
     (browser, handle, tmp_dir)
@@ -39,6 +39,18 @@ fn fixture_url(name: &str) -> String {
     format!("file://{}", path.display())
 }
 
+/// The snapshot ref pinned to the element matching `css`.
+async fn ref_for(page: &chromiumoxide::Page, css: &str) -> String {
+    page.evaluate(format!(
+        "document.querySelector({:?}).getAttribute('data-remix-ref')",
+        css
+    ))
+    .await
+    .unwrap()
+    .into_value()
+    .unwrap()
+}
+
 // ── Navigation Tests ────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -129,6 +141,101 @@ async fn test_click_element() {
     assert_eq!(result, "Link was clicked!");
 }
 
+/// Mouse events `click.html` recorded since the last call.
+async fn take_clicks(page: &chromiumoxide::Page) -> Vec<serde_json::Value> {
+    page.evaluate("window.__clicks.splice(0)")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_click_is_trusted_with_button_count_and_modifiers() {
+    use remix_browser::tools::interaction::{self, ClickParams};
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("click.html").as_str())
+        .await
+        .unwrap();
+    let click = |selector: &str| ClickParams {
+        selector: selector.to_string(),
+        selector_type: None,
+        button: None,
+        click_count: None,
+        modifiers: None,
+    };
+    let summary = |events: &[serde_json::Value]| -> Vec<(String, bool, i64)> {
+        events
+            .iter()
+            .map(|e| {
+                (
+                    e["type"].as_str().unwrap().to_string(),
+                    e["isTrusted"].as_bool().unwrap(),
+                    e["detail"].as_i64().unwrap(),
+                )
+            })
+            .collect()
+    };
+
+    // A visible element gets real CDP mouse input
+    let result = interaction::do_click(&page, &click("#target"))
+        .await
+        .unwrap();
+    assert_eq!(result.method_used, "cdp_mouse");
+    let events = take_clicks(&page).await;
+    assert_eq!(summary(&events), [("click".to_string(), true, 1)]);
+    assert_eq!(events[0]["button"], 0);
+
+    let double = ClickParams {
+        click_count: Some(2),
+        ..click("#target")
+    };
+    interaction::do_click(&page, &double).await.unwrap();
+    assert_eq!(
+        summary(&take_clicks(&page).await),
+        [
+            ("click".to_string(), true, 1),
+            ("click".to_string(), true, 2),
+            ("dblclick".to_string(), true, 2),
+        ]
+    );
+
+    let chord = ClickParams {
+        modifiers: Some(vec!["Shift".into(), "ctrl".into()]),
+        ..click("#target")
+    };
+    interaction::do_click(&page, &chord).await.unwrap();
+    let events = take_clicks(&page).await;
+    assert_eq!(summary(&events), [("click".to_string(), true, 1)]);
+    assert_eq!(events[0]["shiftKey"], true);
+    assert_eq!(events[0]["ctrlKey"], true);
+
+    let right = ClickParams {
+        button: Some("right".into()),
+        ..click("#target")
+    };
+    interaction::do_click(&page, &right).await.unwrap();
+    let events = take_clicks(&page).await;
+    let types: Vec<&str> = events.iter().map(|e| e["type"].as_str().unwrap()).collect();
+    assert!(types.contains(&"contextmenu"), "{:?}", events);
+    assert!(!types.contains(&"click"), "{:?}", events);
+    assert!(events
+        .iter()
+        .all(|e| e["isTrusted"] == true && e["button"] == 2));
+
+    // An element under an overlay falls back to a synthetic click
+    let result = interaction::do_click(&page, &click("#covered"))
+        .await
+        .unwrap();
+    assert_eq!(result.method_used, "js_click");
+    let events = take_clicks(&page).await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["target"], "covered");
+    assert_eq!(events[0]["isTrusted"], false);
+}
+
 #[tokio::test]
 async fn test_type_into_input() {
     let (browser, _handle, _tmp) = launch_test_browser().await;
@@ -162,6 +269,132 @@ async fn test_type_into_input() {
     assert_eq!(value, "Test User");
 }
 
+#[tokio::test]
+async fn test_press_key_enter_tab_and_backspace() {
+    use remix_browser::tools::interaction::{self, PressKeyParams, TypeTextParams};
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("form.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let press = |key: &str| PressKeyParams {
+        key: key.to_string(),
+        modifiers: None,
+    };
+    interaction::type_text(
+        &page,
+        &TypeTextParams {
+            selector: "#name".to_string(),
+            text: "Adaa".to_string(),
+            selector_type: None,
+            clear_first: None,
+            per_character: None,
+            delay_ms: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    // Backspace deletes the character before the caret
+    interaction::press_key(&page, &press("Backspace")).await.unwrap();
+    let value: String = page
+        .evaluate("document.getElementById('name').value")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(value, "Ada");
+
+    // Tab moves focus to the next field
+    interaction::press_key(&page, &press("Tab")).await.unwrap();
+    let focused: String = page
+        .evaluate("document.activeElement.id")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(focused, "email");
+
+    // Enter in a text field submits the form
+    interaction::press_key(&page, &press("Enter")).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let result: String = page
+        .evaluate("document.getElementById('result').textContent")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert!(result.contains(r#""name":"Ada""#), "got: {}", result);
+}
+
+#[tokio::test]
+async fn test_type_text_per_character_holds_shift() {
+    use remix_browser::tools::interaction::{self, TypeTextParams};
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("form.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    page.evaluate(
+        "(() => {
+            window.__keys = [];
+            const name = document.getElementById('name');
+            name.addEventListener('keydown', e => window.__keys.push(e.key + ':' + e.shiftKey));
+            name.addEventListener('keyup', e => {
+                if (e.key === 'Shift') window.__keys.push('Shift up:' + e.shiftKey);
+            });
+        })()",
+    )
+    .await
+    .unwrap();
+
+    interaction::type_text(
+        &page,
+        &TypeTextParams {
+            selector: "#name".to_string(),
+            text: "aB?".to_string(),
+            selector_type: None,
+            clear_first: None,
+            per_character: Some(true),
+            delay_ms: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let keys: Vec<String> = page
+        .evaluate("window.__keys")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    // Shift is pressed and released around each shifted character
+    assert_eq!(
+        keys,
+        [
+            "a:false",
+            "Shift:true",
+            "B:true",
+            "Shift up:false",
+            "Shift:true",
+            "?:true",
+            "Shift up:false",
+        ]
+    );
+    let value: String = page
+        .evaluate("document.getElementById('name').value")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(value, "aB?");
+}
+
 #[tokio::test]
 async fn test_select_option() {
     let (browser, _handle, _tmp) = launch_test_browser().await;
@@ -237,6 +470,118 @@ async fn test_element_screenshot() {
 
 // ── JavaScript Tests ────────────────────────────────────────────────────
 
+async fn capture(
+    page: &chromiumoxide::Page,
+    params: &remix_browser::tools::screenshot::ScreenshotParams,
+) -> (Vec<u8>, &'static str) {
+    use base64::Engine;
+
+    let shot = remix_browser::tools::screenshot::screenshot(page, params, &Default::default())
+        .await
+        .unwrap();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(&shot.data)
+        .unwrap();
+    (bytes, shot.mime_type)
+}
+
+#[tokio::test]
+async fn test_screenshot_formats_and_quality() {
+    use remix_browser::tools::screenshot::ScreenshotParams;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("form.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let jpeg = |quality| ScreenshotParams {
+        format: Some("jpeg".into()),
+        quality: Some(quality),
+        ..Default::default()
+    };
+    let (low, mime) = capture(&page, &jpeg(10)).await;
+    assert_eq!(mime, "image/jpeg");
+    assert_eq!(&low[0..3], &[0xFF, 0xD8, 0xFF]);
+    let (high, _) = capture(&page, &jpeg(95)).await;
+    assert!(
+        high.len() > low.len(),
+        "quality 95 ({} bytes) should be larger than quality 10 ({} bytes)",
+        high.len(),
+        low.len()
+    );
+
+    let webp = ScreenshotParams {
+        format: Some("webp".into()),
+        ..Default::default()
+    };
+    let (bytes, mime) = capture(&page, &webp).await;
+    assert_eq!(mime, "image/webp");
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(&bytes[8..12], b"WEBP");
+}
+
+#[tokio::test]
+async fn test_screenshot_max_width_caps_image_width() {
+    use remix_browser::tools::screenshot::ScreenshotParams;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("form.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    // PNG width is the big-endian u32 at bytes 16..20 of the IHDR chunk
+    let png_width = |bytes: &[u8]| u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+
+    let (full, mime) = capture(&page, &ScreenshotParams::default()).await;
+    assert_eq!(mime, "image/png");
+    assert!(png_width(&full) > 400);
+
+    let params = ScreenshotParams {
+        full_page: Some(true),
+        max_width: Some(400),
+        ..Default::default()
+    };
+    let (capped, _) = capture(&page, &params).await;
+    let width = png_width(&capped);
+    assert!((399..=400).contains(&width), "width {} should be capped at 400", width);
+}
+
+#[tokio::test]
+async fn test_annotated_screenshot_returns_refs_and_cleans_up() {
+    use remix_browser::tools::screenshot::{self, ScreenshotParams};
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("form.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let params = ScreenshotParams {
+        annotate: Some(true),
+        ..Default::default()
+    };
+    let shot = screenshot::screenshot(&page, &params, &Default::default())
+        .await
+        .unwrap();
+    let annotations = shot.annotations.expect("annotate should return the snapshot");
+    let submit_ref = ref_for(&page, "#submit-btn").await;
+    assert!(annotations.refs.contains_key(&submit_ref));
+    assert!(annotations.text.contains(&format!("[ref={}]", submit_ref)));
+
+    let overlay_left: bool = page
+        .evaluate("document.getElementById('__remix_ref_overlay') !== null")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert!(!overlay_left, "the ref overlay should be removed after capture");
+}
+
 #[tokio::test]
 async fn test_evaluate_expression() {
     let (browser, _handle, _tmp) = launch_test_browser().await;
@@ -566,8 +911,11 @@ async fn test_snapshot_form_page() {
         .unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    let params = remix_browser::tools::snapshot::SnapshotParams { selector: None };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let params = remix_browser::tools::snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -611,8 +959,11 @@ async fn test_snapshot_basic_page() {
         .unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    let params = remix_browser::tools::snapshot::SnapshotParams { selector: None };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let params = remix_browser::tools::snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -650,8 +1001,9 @@ async fn test_snapshot_scoped_selector() {
     // Scope to just the form
     let params = remix_browser::tools::snapshot::SnapshotParams {
         selector: Some("#test-form".to_string()),
+        ..Default::default()
     };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -677,22 +1029,22 @@ async fn test_ref_selector_resolution_for_get_text_and_wait_for() {
 
     let snap = remix_browser::tools::snapshot::snapshot_with_refs(
         &page,
-        &remix_browser::tools::snapshot::SnapshotParams { selector: None },
+        &remix_browser::tools::snapshot::SnapshotParams {
+            selector: None,
+            ..Default::default()
+        },
+        &Default::default(),
     )
     .await
     .unwrap();
 
     // With ARIA format, only interactive elements get refs. Use #test-link (a link).
-    let link_ref = snap
-        .refs
-        .iter()
-        .find(|(_, selector)| selector.as_str() == "#test-link")
-        .map(|(ref_id, _)| ref_id.clone())
-        .expect("expected #test-link ref in snapshot");
+    let link_ref = ref_for(&page, "#test-link").await;
+    assert!(snap.refs.contains_key(&link_ref), "expected #test-link ref in snapshot");
 
     let resolved = resolve_ref_selector(&format!("ref={}", link_ref), &snap.refs)
         .expect("ref selector should resolve");
-    assert_eq!(resolved, "#test-link");
+    assert_eq!(resolved, format!("[data-remix-ref={}]", link_ref));
 
     let css_text = remix_browser::tools::dom::get_text(
         &page,
@@ -754,24 +1106,20 @@ async fn test_ref_selector_resolution_for_click_and_type_text() {
 
     let snap = remix_browser::tools::snapshot::snapshot_with_refs(
         &page,
-        &remix_browser::tools::snapshot::SnapshotParams { selector: None },
+        &remix_browser::tools::snapshot::SnapshotParams {
+            selector: None,
+            ..Default::default()
+        },
+        &Default::default(),
     )
     .await
     .unwrap();
 
-    let name_ref = snap
-        .refs
-        .iter()
-        .find(|(_, selector)| selector.as_str() == "#name")
-        .map(|(ref_id, _)| ref_id.clone())
-        .expect("expected #name ref in snapshot");
+    let name_ref = ref_for(&page, "#name").await;
+    assert!(snap.refs.contains_key(&name_ref), "expected #name ref in snapshot");
 
-    let submit_ref = snap
-        .refs
-        .iter()
-        .find(|(_, selector)| selector.as_str() == "#submit-btn")
-        .map(|(ref_id, _)| ref_id.clone())
-        .expect("expected #submit-btn ref in snapshot");
+    let submit_ref = ref_for(&page, "#submit-btn").await;
+    assert!(snap.refs.contains_key(&submit_ref), "expected #submit-btn ref in snapshot");
 
     let resolved_name =
         resolve_ref_selector(&name_ref, &snap.refs).expect("name ref should resolve");
@@ -785,6 +1133,8 @@ async fn test_ref_selector_resolution_for_click_and_type_text() {
             text: "Ref User".to_string(),
             selector_type: Some(remix_browser::selectors::SelectorType::Css),
             clear_first: Some(true),
+            per_character: None,
+            delay_ms: None,
         },
     )
     .await
@@ -804,6 +1154,8 @@ async fn test_ref_selector_resolution_for_click_and_type_text() {
             selector: resolved_submit,
             selector_type: Some(remix_browser::selectors::SelectorType::Css),
             button: Some("left".to_string()),
+            click_count: None,
+            modifiers: None,
         },
     )
     .await
@@ -829,12 +1181,12 @@ async fn test_network_log_circular_buffer() {
     // Add 600 entries (cap is 500)
     for i in 0..600 {
         log.add(remix_browser::tools::network::NetworkEntry {
+            request_id: i.to_string(),
+            tab_id: "tab".to_string(),
             url: format!("https://example.com/{}", i),
             method: "GET".to_string(),
             status: 200,
-            headers: None,
-            body_preview: String::new(),
-            timing_ms: 0.0,
+            ..Default::default()
         })
         .await;
     }
@@ -864,11 +1216,12 @@ async fn test_console_log_circular_buffer() {
             level: "log".to_string(),
             text: format!("entry {}", i),
             timestamp: i as f64,
+            ..Default::default()
         })
         .await;
     }
 
-    let entries = log.read(None, false, None).await;
+    let entries = log.read(None, false, None, None).await;
     assert_eq!(
         entries.len(),
         1000,
@@ -1190,8 +1543,11 @@ async fn test_snapshot_label_for_resolution() {
         .unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    let params = remix_browser::tools::snapshot::SnapshotParams { selector: None };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let params = remix_browser::tools::snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -1234,8 +1590,11 @@ async fn test_snapshot_native_range_input() {
         .unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    let params = remix_browser::tools::snapshot::SnapshotParams { selector: None };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let params = remix_browser::tools::snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -1273,8 +1632,11 @@ async fn test_snapshot_custom_aria_slider() {
         .unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    let params = remix_browser::tools::snapshot::SnapshotParams { selector: None };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let params = remix_browser::tools::snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -1295,15 +1657,22 @@ async fn test_snapshot_custom_aria_slider() {
     // Both sliders should have refs (interactive)
     let snap = remix_browser::tools::snapshot::snapshot_with_refs(
         &page,
-        &remix_browser::tools::snapshot::SnapshotParams { selector: None },
+        &remix_browser::tools::snapshot::SnapshotParams {
+            selector: None,
+            ..Default::default()
+        },
+        &Default::default(),
     )
     .await
     .unwrap();
 
-    let has_rating_ref = snap
-        .refs
-        .iter()
-        .any(|(_, selector)| selector.as_str() == "#rating-slider");
+    let rating_ref: Option<String> = page
+        .evaluate("document.getElementById('rating-slider').getAttribute('data-remix-ref')")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    let has_rating_ref = rating_ref.is_some_and(|r| snap.refs.contains_key(&r));
     assert!(
         has_rating_ref,
         "Custom ARIA slider should have a ref, refs: {:?}",
@@ -1320,8 +1689,11 @@ async fn test_snapshot_wrapping_label_and_aria_labelledby() {
         .unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    let params = remix_browser::tools::snapshot::SnapshotParams { selector: None };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let params = remix_browser::tools::snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -1349,8 +1721,11 @@ async fn test_snapshot_input_type_annotations() {
         .unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    let params = remix_browser::tools::snapshot::SnapshotParams { selector: None };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let params = remix_browser::tools::snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -1395,8 +1770,11 @@ async fn test_snapshot_name_attribute_fallback() {
         .unwrap();
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
 
-    let params = remix_browser::tools::snapshot::SnapshotParams { selector: None };
-    let result = remix_browser::tools::snapshot::snapshot(&page, &params)
+    let params = remix_browser::tools::snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let result = remix_browser::tools::snapshot::snapshot(&page, &params, &Default::default())
         .await
         .unwrap();
 
@@ -1475,6 +1853,8 @@ async fn test_case_insensitive_text_click() {
             selector: "CLICK ME".to_string(),
             selector_type: Some(remix_browser::selectors::SelectorType::Text),
             button: None,
+            click_count: None,
+            modifiers: None,
         },
     )
     .await;
@@ -1517,7 +1897,11 @@ async fn test_run_script_preloaded_refs() {
     // Take a snapshot to get refs
     let snap = remix_browser::tools::snapshot::snapshot_with_refs(
         &page,
-        &remix_browser::tools::snapshot::SnapshotParams { selector: None },
+        &remix_browser::tools::snapshot::SnapshotParams {
+            selector: None,
+            ..Default::default()
+        },
+        &Default::default(),
     )
     .await
     .unwrap();
@@ -1525,12 +1909,7 @@ async fn test_run_script_preloaded_refs() {
     assert!(!snap.refs.is_empty(), "Snapshot should have refs");
 
     // Find the ref for #test-link
-    let link_ref = snap
-        .refs
-        .iter()
-        .find(|(_, selector)| selector.as_str() == "#test-link")
-        .map(|(ref_id, _)| ref_id.clone())
-        .expect("expected #test-link ref");
+    let link_ref = ref_for(&page, "#test-link").await;
 
     // Run script using pre-loaded refs WITHOUT calling page.snapshot() in the script
     let script = format!(
@@ -1575,6 +1954,8 @@ async fn test_has_text_selector_auto_conversion() {
             selector: r#"a:has-text("Click me")"#.to_string(),
             selector_type: Some(remix_browser::selectors::SelectorType::Css),
             button: None,
+            click_count: None,
+            modifiers: None,
         },
     )
     .await;
@@ -1664,6 +2045,8 @@ async fn test_auto_wait_click() {
             selector: "#delayed-btn".to_string(),
             selector_type: Some(remix_browser::selectors::SelectorType::Css),
             button: None,
+            click_count: None,
+            modifiers: None,
         },
     )
     .await;
@@ -1711,6 +2094,8 @@ async fn test_auto_wait_type() {
             text: "Hello Auto-Wait".to_string(),
             selector_type: Some(remix_browser::selectors::SelectorType::Css),
             clear_first: None,
+            per_character: None,
+            delay_ms: None,
         },
     )
     .await;
@@ -1933,3 +2318,2015 @@ async fn test_ref_resolution_in_page_js() {
     let refs = refs.unwrap();
     assert!(refs.contains_key("e0"), "Should have e0 ref");
 }
+
+// ── Hover and Drag Tests ────────────────────────────────────────────────
+
+fn drag_params(from: &str) -> remix_browser::tools::interaction::DragParams {
+    remix_browser::tools::interaction::DragParams {
+        from: Some(from.to_string()),
+        to: None,
+        selector_type: None,
+        from_x: None,
+        from_y: None,
+        to_x: None,
+        to_y: None,
+        steps: None,
+    }
+}
+
+#[tokio::test]
+async fn test_hover_applies_hover_styles() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("pointer.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    remix_browser::tools::interaction::hover(
+        &page,
+        &remix_browser::tools::interaction::HoverParams {
+            selector: "#hover-box".to_string(),
+            selector_type: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    let state: serde_json::Value = page
+        .evaluate(
+            "(() => {
+                const box = document.getElementById('hover-box');
+                return {
+                    hovered: box.matches(':hover'),
+                    background: getComputedStyle(box).backgroundColor,
+                    enters: document.getElementById('hover-count').textContent,
+                };
+            })()",
+        )
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(state["hovered"], true);
+    assert_eq!(state["background"], "rgb(0, 128, 0)");
+    assert_eq!(state["enters"], "1");
+}
+
+#[tokio::test]
+async fn test_drag_reorders_html5_list_on_scrolled_page() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("pointer.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    // Bringing #item-c into view scrolls #item-a up; the drag must start on
+    // where #item-a is afterwards, not where it was first measured.
+    let params = remix_browser::tools::interaction::DragParams {
+        to: Some("#item-c".to_string()),
+        ..drag_params("#item-a")
+    };
+    let result = remix_browser::tools::interaction::drag(&page, &params)
+        .await
+        .unwrap();
+    assert_eq!(result.method_used, "html5_drag");
+
+    let order: String = page
+        .evaluate("Array.from(document.querySelectorAll('#sortable li')).map(li => li.textContent).join('')")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(order, "BCA");
+}
+
+#[tokio::test]
+async fn test_drag_moves_mouse_driven_slider() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("pointer.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let track = remix_browser::tools::dom::get_bounding_box(
+        &page,
+        &remix_browser::tools::dom::GetBoundingBoxParams {
+            selector: "#slider-track".to_string(),
+            selector_type: None,
+            scroll_into_view: None,
+        },
+    )
+    .await
+    .unwrap();
+    let x = track["x"].as_f64().unwrap() + track["width"].as_f64().unwrap() * 0.75;
+    let y = track["center_y"].as_f64().unwrap();
+
+    let params = remix_browser::tools::interaction::DragParams {
+        to_x: Some(x),
+        to_y: Some(y),
+        ..drag_params("#slider-thumb")
+    };
+    let result = remix_browser::tools::interaction::drag(&page, &params)
+        .await
+        .unwrap();
+    assert_eq!(result.method_used, "cdp_mouse");
+
+    let value: String = page
+        .evaluate("document.getElementById('slider-value').textContent")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(value, "75");
+}
+
+// ── Coordinate Action Tests ─────────────────────────────────────────────
+
+#[tokio::test]
+async fn test_click_at_bounding_box_center() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("basic.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let bbox = remix_browser::tools::dom::get_bounding_box(
+        &page,
+        &remix_browser::tools::dom::GetBoundingBoxParams {
+            selector: "#test-link".to_string(),
+            selector_type: Some(remix_browser::selectors::SelectorType::Css),
+            scroll_into_view: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(bbox["in_viewport"], true);
+    let x = bbox["center_x"].as_f64().unwrap();
+    let y = bbox["center_y"].as_f64().unwrap();
+
+    remix_browser::tools::interaction::click_at(
+        &page,
+        &remix_browser::tools::interaction::ClickAtParams {
+            x,
+            y,
+            button: None,
+            click_count: None,
+            modifiers: None,
+        },
+    )
+    .await
+    .unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let click_result: String = page
+        .evaluate(r#"document.getElementById('click-result').textContent"#)
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+
+    assert_eq!(
+        click_result, "Link was clicked!",
+        "click_at on the bounding box center should hit the link"
+    );
+}
+
+#[tokio::test]
+async fn test_bounding_box_waits_and_normalizes_selectors() {
+    use remix_browser::tools::dom::{get_bounding_box, GetBoundingBoxParams};
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("dynamic.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    // The content shows up 500ms after the click
+    page.evaluate("document.getElementById('load-btn').click()")
+        .await
+        .unwrap();
+    let loaded = get_bounding_box(
+        &page,
+        &GetBoundingBoxParams {
+            selector: "#loaded-text".to_string(),
+            selector_type: None,
+            scroll_into_view: None,
+        },
+    )
+    .await
+    .expect("bounding box should wait for the element");
+    assert!(loaded["width"].as_f64().unwrap() > 0.0);
+
+    for (selector, selector_type) in [
+        ("Item 2", Some(remix_browser::selectors::SelectorType::Text)),
+        (r#"li:has-text("Item 3")"#, None),
+    ] {
+        let bbox = get_bounding_box(
+            &page,
+            &GetBoundingBoxParams {
+                selector: selector.to_string(),
+                selector_type,
+                scroll_into_view: None,
+            },
+        )
+        .await
+        .unwrap_or_else(|e| panic!("{}: {:#}", selector, e));
+        assert!(bbox["height"].as_f64().unwrap() > 0.0, "{}", selector);
+    }
+}
+
+#[tokio::test]
+async fn test_run_script_mouse_down_move_up_holds_button() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+
+    let console_log = remix_browser::tools::javascript::ConsoleLog::new();
+    let network_log = remix_browser::tools::network::NetworkLog::new();
+
+    // The slider only follows moves that report the left button as held
+    let script = format!(
+        r#"page.navigate('{}');
+        const thumb = page.boundingBox('#slider-thumb');
+        const track = page.boundingBox('#slider-track');
+        page.mouse.down(thumb.center_x, thumb.center_y);
+        page.mouse.move(track.x + track.width / 2, track.center_y);
+        page.mouse.up(track.x + track.width / 2, track.center_y);
+        console.log('value=' + page.getText('#slider-value'));"#,
+        fixture_url("pointer.html")
+    );
+
+    let params = remix_browser::tools::script::RunScriptParams { script };
+    let (result, _screenshots, _refs) =
+        remix_browser::tools::script::run_script(&page, &params, &console_log, &network_log, None)
+            .await
+            .unwrap();
+
+    assert!(result.success, "Script should succeed, error: {:?}", result.error);
+    assert!(result.output.contains("value=50"), "got: {}", result.output);
+}
+
+// ── Navigation Wait Strategy Tests ──────────────────────────────────────
+
+#[tokio::test]
+async fn test_navigate_wait_strategies() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+
+    for wait_until in ["commit", "domcontentloaded", "load", "networkidle"] {
+        let result = remix_browser::tools::navigation::navigate(
+            &page,
+            &remix_browser::tools::navigation::NavigateParams {
+                url: fixture_url("basic.html"),
+                wait_until: Some(wait_until.to_string()),
+                timeout_ms: Some(10_000),
+                fail_on_http_error: None,
+                include_snapshot: false,
+            },
+        )
+        .await
+        .unwrap_or_else(|e| panic!("navigate with {} failed: {:#}", wait_until, e));
+
+        assert!(result.url.contains("basic.html"), "{}: {}", wait_until, result.url);
+    }
+
+    let ready_state: String = page
+        .evaluate("document.readyState")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(ready_state, "complete", "networkidle should wait past load");
+}
+
+#[tokio::test]
+async fn test_navigate_reports_net_error() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+
+    let params = |fail_on_http_error| remix_browser::tools::navigation::NavigateParams {
+        url: fixture_url("does-not-exist.html"),
+        wait_until: None,
+        timeout_ms: Some(10_000),
+        fail_on_http_error,
+        include_snapshot: false,
+    };
+
+    let result = remix_browser::tools::navigation::navigate(&page, &params(None))
+        .await
+        .unwrap();
+    let error = result.error.clone().expect("missing file should report a net error");
+    assert!(error.contains("ERR_FILE_NOT_FOUND"), "got: {}", error);
+    assert!(result.summary().contains("failed: net::ERR_FILE_NOT_FOUND"));
+
+    let err = remix_browser::tools::navigation::navigate(&page, &params(Some(true)))
+        .await
+        .expect_err("fail_on_http_error should turn net errors into errors");
+    assert!(format!("{:#}", err).contains("ERR_FILE_NOT_FOUND"));
+}
+
+/// Serve a page whose image takes `delay_ms` to answer, so `load` fires late.
+async fn spawn_slow_subresource_server(delay_ms: u64) -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut buf = [0u8; 2048];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                if request.starts_with("GET /slow.png") {
+                    tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+                    let _ = stream
+                        .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                        .await;
+                } else {
+                    let body = "<html><head><title>Slow Page</title></head>\
+                                <body><img src=\"/slow.png\"></body></html>";
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                }
+            });
+        }
+    });
+    format!("http://{}/", addr)
+}
+
+#[tokio::test]
+async fn test_navigate_returns_before_slow_load() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+    let url = spawn_slow_subresource_server(5_000).await;
+
+    for wait_until in ["commit", "domcontentloaded"] {
+        let started = std::time::Instant::now();
+        let result = remix_browser::tools::navigation::navigate(
+            &page,
+            &remix_browser::tools::navigation::NavigateParams {
+                url: format!("{}?wait={}", url, wait_until),
+                wait_until: Some(wait_until.to_string()),
+                timeout_ms: Some(10_000),
+                fail_on_http_error: None,
+                include_snapshot: false,
+            },
+        )
+        .await
+        .unwrap_or_else(|e| panic!("navigate with {} failed: {:#}", wait_until, e));
+
+        assert!(
+            started.elapsed() < std::time::Duration::from_millis(3_000),
+            "{} waited for the slow image: {:?}",
+            wait_until,
+            started.elapsed()
+        );
+        assert_eq!(result.status, Some(200), "{}", wait_until);
+    }
+
+    let ready_state: String = page
+        .evaluate("document.readyState")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(ready_state, "interactive", "load has not fired yet");
+}
+
+#[tokio::test]
+async fn test_navigate_timeout_lists_pending_requests() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+    let url = spawn_slow_subresource_server(10_000).await;
+
+    let started = std::time::Instant::now();
+    let err = remix_browser::tools::navigation::navigate(
+        &page,
+        &remix_browser::tools::navigation::NavigateParams {
+            url,
+            wait_until: Some("load".to_string()),
+            timeout_ms: Some(1_000),
+            fail_on_http_error: None,
+            include_snapshot: false,
+        },
+    )
+    .await
+    .expect_err("load cannot fire while the image is pending");
+
+    // One deadline covers sending the navigation and waiting for load
+    assert!(
+        started.elapsed() < std::time::Duration::from_millis(2_500),
+        "took {:?}",
+        started.elapsed()
+    );
+    let message = format!("{:#}", err);
+    assert!(message.contains("timed out after 1000ms waiting for load"), "{}", message);
+    assert!(message.contains("still pending"), "{}", message);
+    assert!(message.contains("GET http://127.0.0.1"), "{}", message);
+    assert!(message.contains("/slow.png"), "{}", message);
+}
+
+#[tokio::test]
+async fn test_go_back_and_forward_with_history() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("basic.html").as_str())
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    page.goto(fixture_url("form.html").as_str()).await.unwrap();
+
+    let history = remix_browser::tools::navigation::get_history(&page)
+        .await
+        .unwrap();
+    assert!(history.last().unwrap().current);
+    assert!(history.last().unwrap().url.contains("form.html"));
+
+    let back = remix_browser::tools::navigation::go_back(&page).await.unwrap();
+    assert_eq!(back.title, "Basic Test Page");
+    assert!(back.url.contains("basic.html"));
+
+    let forward = remix_browser::tools::navigation::go_forward(&page).await.unwrap();
+    assert_eq!(forward.title, "Form Test Page");
+
+    let err = remix_browser::tools::navigation::go_forward(&page)
+        .await
+        .expect_err("no forward entry should be an error");
+    assert!(format!("{:#}", err).contains("no history entry"));
+}
+
+#[tokio::test]
+async fn test_network_log_details_and_response_body() {
+    use remix_browser::tools::network;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+
+    let log = network::NetworkLog::new();
+    log.enable(None).await;
+    network::start_listening(&page, log.clone()).await.unwrap();
+
+    page.goto(fixture_url("basic.html").as_str()).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let entries = log.get_log(Some("basic.html"), None, None).await;
+    let entry = entries.last().expect("should capture the document request");
+    assert_eq!(entry.resource_type.as_deref(), Some("Document"));
+    assert_eq!(entry.mime_type.as_deref(), Some("text/html"));
+    assert!(entry.request_headers.is_some());
+    assert!(entry.started_at > 0.0);
+
+    let params = |max_bytes| network::GetResponseBodyParams {
+        request_id: entry.request_id.clone(),
+        max_bytes,
+    };
+    let body = network::get_response_body_from_page(&page, &log, &params(None))
+        .await
+        .unwrap();
+    assert!(!body.base64_encoded);
+    assert!(!body.truncated);
+    assert!(body.body.contains("<html"), "got: {}", body.body);
+
+    let body = network::get_response_body_from_page(&page, &log, &params(Some(10)))
+        .await
+        .unwrap();
+    assert!(body.truncated);
+    assert_eq!(body.body.len(), 10);
+    assert!(body.size > 10);
+
+    let err = network::get_response_body_from_page(
+        &page,
+        &log,
+        &network::GetResponseBodyParams {
+            request_id: "missing".into(),
+            max_bytes: None,
+        },
+    )
+    .await
+    .expect_err("unknown request ids should error");
+    assert!(format!("{:#}", err).contains("get_network_log"));
+}
+
+#[tokio::test]
+async fn test_network_log_failed_only_filter() {
+    use remix_browser::tools::network;
+
+    let log = network::NetworkLog::new();
+    log.enable(None).await;
+    log.add(network::NetworkEntry {
+        request_id: "1".into(),
+        url: "https://example.com/ok".into(),
+        status: 200,
+        ..Default::default()
+    })
+    .await;
+    log.add(network::NetworkEntry {
+        request_id: "2".into(),
+        url: "https://blocked.example/ad.js".into(),
+        error: Some("net::ERR_BLOCKED_BY_CLIENT".into()),
+        blocked_reason: Some("inspector".into()),
+        ..Default::default()
+    })
+    .await;
+
+    let params = network::GetNetworkLogParams {
+        url_pattern: None,
+        method: None,
+        status: None,
+        tab_id: None,
+        failed_only: Some(true),
+        include_headers: None,
+        limit: None,
+    };
+    let result = network::get_network_log(&log, &params).await.unwrap();
+    let entries = result.as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["url"], "https://blocked.example/ad.js");
+    assert_eq!(entries[0]["blocked_reason"], "inspector");
+}
+
+#[tokio::test]
+async fn test_failed_request_is_logged_and_not_pending() {
+    use remix_browser::tools::network;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("basic.html").as_str())
+        .await
+        .unwrap();
+
+    let log = network::NetworkLog::new();
+    log.enable(None).await;
+    network::start_listening(&page, log.clone()).await.unwrap();
+
+    page.evaluate("fetch('missing-resource.json').catch(() => {})")
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let entries = log.get_log(Some("missing-resource.json"), None, None).await;
+    let entry = entries.last().expect("failed request should be logged");
+    assert_eq!(entry.status, 0);
+    assert!(entry.error.as_deref().unwrap_or("").contains("ERR_"), "got: {:?}", entry.error);
+    assert_eq!(log.pending_requests(), 0, "failed requests must not stay pending");
+}
+
+/// Serve `/start` → 302 → `/middle` → 301 → `/end` (200).
+async fn spawn_redirect_server() -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut buf = [0u8; 2048];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let response = if request.starts_with("GET /start") {
+                    "HTTP/1.1 302 Found\r\nLocation: /middle\r\nContent-Length: 0\r\n\r\n"
+                        .to_string()
+                } else if request.starts_with("GET /middle") {
+                    "HTTP/1.1 301 Moved Permanently\r\nLocation: /end\r\nContent-Length: 0\r\n\r\n"
+                        .to_string()
+                } else {
+                    let body = "<html><head><title>Redirected</title></head><body>end</body></html>";
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    format!("http://{}", addr)
+}
+
+/// Capture a navigation through the redirect chain of [`spawn_redirect_server`].
+async fn capture_redirect_chain(
+    page: &chromiumoxide::Page,
+) -> (remix_browser::tools::network::NetworkLog, String) {
+    use remix_browser::tools::network;
+
+    let base = spawn_redirect_server().await;
+    let log = network::NetworkLog::new();
+    log.enable(None).await;
+    network::start_listening(page, log.clone()).await.unwrap();
+    page.goto(format!("{}/start", base)).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    (log, base)
+}
+
+#[tokio::test]
+async fn test_redirect_hops_are_logged_separately() {
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+    let (log, base) = capture_redirect_chain(&page).await;
+
+    let hops: Vec<_> = log
+        .get_log(Some(base.as_str()), None, None)
+        .await
+        .into_iter()
+        .filter(|e| e.resource_type.as_deref() == Some("Document"))
+        .collect();
+    let summary: Vec<(String, u32, Option<String>)> = hops
+        .iter()
+        .map(|e| (e.url.replace(&base, ""), e.status, e.redirect_url.clone()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("/start".to_string(), 302, Some(format!("{}/middle", base))),
+            ("/middle".to_string(), 301, Some(format!("{}/end", base))),
+            ("/end".to_string(), 200, None),
+        ]
+    );
+    assert!(hops.iter().all(|e| e.request_id == hops[0].request_id));
+    assert!(hops.iter().all(|e| e.navigation));
+    // Completion lands on the final hop
+    assert!(hops[2].encoded_size.is_some());
+    assert_eq!(log.pending_requests(), 0);
+}
+
+#[tokio::test]
+async fn test_export_har_has_entry_per_redirect_hop() {
+    use remix_browser::tools::har;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+    let (log, base) = capture_redirect_chain(&page).await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let params = har::ExportHarParams {
+        filename: Some("redirects.har".into()),
+        include_bodies: Some(true),
+        max_body_bytes: None,
+    };
+    har::export_har(std::slice::from_ref(&page), &log, dir.path(), &params)
+        .await
+        .unwrap();
+    let har: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(dir.path().join("redirects.har")).unwrap(),
+    )
+    .unwrap();
+
+    let documents: Vec<&serde_json::Value> = har["log"]["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["_resourceType"] == "document")
+        .collect();
+    let hops: Vec<(String, u64, String)> = documents
+        .iter()
+        .map(|e| {
+            (
+                e["request"]["url"].as_str().unwrap().replace(&base, ""),
+                e["response"]["status"].as_u64().unwrap(),
+                e["response"]["redirectURL"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        hops,
+        vec![
+            ("/start".to_string(), 302, format!("{}/middle", base)),
+            ("/middle".to_string(), 301, format!("{}/end", base)),
+            ("/end".to_string(), 200, String::new()),
+        ]
+    );
+    assert!(documents.iter().all(|e| e["pageref"] == documents[0]["pageref"]));
+    assert!(documents[0]["response"]["content"]["text"].is_null());
+    assert!(documents[2]["response"]["content"]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Redirected"));
+}
+
+#[tokio::test]
+async fn test_export_har_writes_valid_archive() {
+    use remix_browser::tools::{har, network};
+
+    let log = network::NetworkLog::new();
+    log.enable(None).await;
+    log.add(network::NetworkEntry {
+        request_id: "1".into(),
+        tab_id: "tab".into(),
+        url: "https://example.com/".into(),
+        method: "GET".into(),
+        status: 200,
+        mime_type: Some("text/html".into()),
+        navigation: true,
+        started_at: 1_700_000_000.0,
+        timing_ms: 42.0,
+        ..Default::default()
+    })
+    .await;
+    log.add(network::NetworkEntry {
+        request_id: "2".into(),
+        tab_id: "tab".into(),
+        url: "https://example.com/api".into(),
+        method: "POST".into(),
+        post_data: Some("{\"q\":1}".into()),
+        request_headers: Some(serde_json::json!({ "Content-Type": "application/json" })),
+        error: Some("net::ERR_CONNECTION_REFUSED".into()),
+        ..Default::default()
+    })
+    .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let params = har::ExportHarParams {
+        filename: Some("trace.har".into()),
+        include_bodies: None,
+        max_body_bytes: None,
+    };
+    let export = har::export_har(&[], &log, dir.path(), &params).await.unwrap();
+    assert_eq!((export.entries, export.pages), (2, 1));
+
+    let har: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.path().join("trace.har")).unwrap())
+            .unwrap();
+    let entries = har["log"]["entries"].as_array().unwrap();
+    assert_eq!(entries[0]["startedDateTime"], "2023-11-14T22:13:20.000Z");
+    assert_eq!(entries[0]["time"], 42.0);
+    assert_eq!(entries[1]["request"]["postData"]["mimeType"], "application/json");
+    assert_eq!(entries[1]["response"]["_error"], "net::ERR_CONNECTION_REFUSED");
+    assert!(entries.iter().all(|e| e["pageref"] == "page_1"));
+}
+
+#[tokio::test]
+async fn test_route_fulfill_and_abort() {
+    use remix_browser::tools::route;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("basic.html").as_str())
+        .await
+        .unwrap();
+
+    let routes = route::Routes::new();
+    route::start_intercepting(&page, routes.clone()).await.unwrap();
+    let pages = [page.clone()];
+
+    let mock: route::RouteParams = serde_json::from_value(serde_json::json!({
+        "url_pattern": "*/api/user*",
+        "status": 201,
+        "body": "{\"name\":\"Ada\"}",
+    }))
+    .unwrap();
+    route::add_route(&pages, &routes, &mock).await.unwrap();
+    let blocked: route::RouteParams = serde_json::from_value(serde_json::json!({
+        "url_pattern": "*/api/fail",
+        "error_reason": "ConnectionRefused",
+    }))
+    .unwrap();
+    route::add_route(&pages, &routes, &blocked).await.unwrap();
+
+    let result: serde_json::Value = page
+        .evaluate(
+            "(async () => {
+                const r = await fetch('https://mock.test/api/user?id=1');
+                const failed = await fetch('https://mock.test/api/fail').then(() => false, () => true);
+                return { status: r.status, type: r.headers.get('content-type'), body: await r.json(), failed };
+            })()",
+        )
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert_eq!(result["status"], 201);
+    assert_eq!(result["type"], "application/json");
+    assert_eq!(result["body"]["name"], "Ada");
+    assert_eq!(result["failed"], true);
+    assert!(routes.list().await.iter().all(|r| r.hits == 1));
+
+    let unroute = route::UnrouteParams {
+        id: None,
+        url_pattern: None,
+        all: Some(true),
+    };
+    assert_eq!(route::remove_routes(&pages, &routes, &unroute).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn test_replay_har_serves_recorded_responses() {
+    use remix_browser::tools::route;
+
+    let dir = tempfile::tempdir().unwrap();
+    let har_path = dir.path().join("site.har");
+    let har = serde_json::json!({ "log": { "version": "1.2", "entries": [
+        { "request": { "method": "GET", "url": "https://replay.test/" },
+          "response": { "status": 200,
+                        "headers": [{ "name": "Content-Type", "value": "text/html" }],
+                        "content": { "mimeType": "text/html", "text": "<title>Recorded</title><h1>offline</h1>" } } }
+    ] } });
+    std::fs::write(&har_path, har.to_string()).unwrap();
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+    let routes = route::Routes::new();
+    route::start_intercepting(&page, routes.clone()).await.unwrap();
+
+    let params = route::ReplayHarParams {
+        path: Some(har_path.display().to_string()),
+        fallback: None,
+        stop: None,
+    };
+    let status = route::replay_har(std::slice::from_ref(&page), &routes, &params)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(status.entries, 1);
+
+    page.goto("https://replay.test/").await.unwrap();
+    let title = page.get_title().await.unwrap().unwrap_or_default();
+    assert_eq!(title, "Recorded");
+
+    let blocked: bool = page
+        .evaluate("fetch('https://replay.test/not-recorded').then(() => false, () => true)")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert!(blocked, "requests missing from the HAR should fail");
+    let status = routes.replay().await.unwrap().status();
+    assert_eq!((status.served, status.missed), (1, 1));
+}
+
+#[tokio::test]
+async fn test_block_resources_logs_blocked_requests() {
+    use remix_browser::tools::{block, network, route};
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("basic.html").as_str())
+        .await
+        .unwrap();
+
+    let log = network::NetworkLog::new();
+    log.enable(None).await;
+    network::start_listening(&page, log.clone()).await.unwrap();
+    let routes = route::Routes::new();
+    route::start_intercepting(&page, routes.clone()).await.unwrap();
+
+    let params = block::BlockResourcesParams {
+        profile: Some("no-media".into()),
+        resource_types: None,
+        url_patterns: Some(vec!["*/blocked-script.js".into()]),
+        clear: None,
+    };
+    block::block_resources(std::slice::from_ref(&page), &routes, &params)
+        .await
+        .unwrap();
+
+    page.evaluate(
+        "(() => {
+            const img = new Image(); img.src = 'https://example.com/photo.png';
+            document.body.appendChild(img);
+            fetch('https://example.com/blocked-script.js').catch(() => {});
+        })()",
+    )
+    .await
+    .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    for url in ["photo.png", "blocked-script.js"] {
+        let entries = log.get_log(Some(url), None, None).await;
+        let entry = entries.last().unwrap_or_else(|| panic!("{} should be logged", url));
+        assert_eq!(entry.blocked_reason.as_deref(), Some("client"), "{}", url);
+    }
+    assert_eq!(routes.blocking().await.unwrap().blocked.load(std::sync::atomic::Ordering::Relaxed), 2);
+}
+
+#[tokio::test]
+async fn test_emulate_network_offline_and_throttled() {
+    use remix_browser::tools::emulation;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("basic.html").as_str())
+        .await
+        .unwrap();
+    let emu = emulation::Emulation::new();
+
+    let offline = emulation::EmulateNetworkParams {
+        preset: Some("offline".into()),
+        ..Default::default()
+    };
+    emulation::emulate_network(std::slice::from_ref(&page), &emu, &offline)
+        .await
+        .unwrap();
+    let online: bool = page
+        .evaluate("navigator.onLine")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert!(!online, "offline preset should take the page offline");
+
+    // A tab entering the pool later picks up the current conditions
+    let second = browser.new_page("about:blank").await.unwrap();
+    emu.attach(&second).await.unwrap();
+    let online: bool = second
+        .evaluate("navigator.onLine")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert!(!online, "new tabs should inherit the emulated conditions");
+
+    let throttled = emulation::EmulateNetworkParams {
+        preset: Some("slow-3g".into()),
+        cpu_throttling: Some(4.0),
+        ..Default::default()
+    };
+    let state = emulation::emulate_network(&[page.clone(), second], &emu, &throttled)
+        .await
+        .unwrap();
+    assert_eq!(state.cpu_throttling, Some(4.0));
+    assert_eq!(state.network.unwrap().latency, 2000.0);
+    let online: bool = page
+        .evaluate("navigator.onLine")
+        .await
+        .unwrap()
+        .into_value()
+        .unwrap();
+    assert!(online);
+}
+
+#[tokio::test]
+async fn test_network_capture_spans_tabs_and_disables() {
+    use remix_browser::tools::network;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let first = browser
+        .new_page(fixture_url("basic.html").as_str())
+        .await
+        .unwrap();
+    let second = browser
+        .new_page(fixture_url("basic.html").as_str())
+        .await
+        .unwrap();
+
+    let log = network::NetworkLog::new();
+    let params = network::NetworkEnableParams { patterns: None };
+    let pages = [first.clone(), second.clone()];
+    assert_eq!(network::network_enable(&pages, &log, &params).await.unwrap(), 2);
+    // Enabling again must not spawn a second listener per tab
+    network::network_enable(&pages, &log, &params).await.unwrap();
+    network::start_listening(&first, log.clone()).await.unwrap();
+
+    for page in &pages {
+        page.evaluate("fetch('basic.html?capture=1').catch(() => {})")
+            .await
+            .unwrap();
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    let entries = log.get_log(Some("capture=1"), None, None).await;
+    assert_eq!(entries.len(), 2, "one entry per tab, no duplicates");
+    let second_id = second.target_id().as_ref().to_string();
+    assert!(entries.iter().any(|e| e.tab_id == second_id));
+
+    let filter = network::GetNetworkLogParams {
+        url_pattern: Some("capture=1".into()),
+        method: None,
+        status: None,
+        tab_id: Some(second_id.clone()),
+        failed_only: None,
+        include_headers: None,
+        limit: None,
+    };
+    let result = network::get_network_log(&log, &filter).await.unwrap();
+    assert_eq!(result.as_array().unwrap().len(), 1);
+
+    assert_eq!(log.disable().await, 2);
+    assert!(!log.is_listening(&second_id));
+    first
+        .evaluate("fetch('basic.html?capture=2').catch(() => {})")
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    assert!(log.get_log(Some("capture=2"), None, None).await.is_empty());
+    assert_eq!(log.pending_requests(), 0);
+}
+
+/// Serve a page that opens an EventSource, and the event stream itself.
+async fn spawn_sse_server() -> String {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut buf = [0u8; 2048];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                if request.starts_with("GET /events") {
+                    let _ = stream
+                        .write_all(
+                            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n\
+                              event: tick\nid: 1\ndata: {\"price\":3}\n\ndata: plain\n\n",
+                        )
+                        .await;
+                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                } else {
+                    let body = "<html><body><script>new EventSource('/events')</script></body></html>";
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = stream.write_all(response.as_bytes()).await;
+                }
+            });
+        }
+    });
+    format!("http://{}/", addr)
+}
+
+#[tokio::test]
+async fn test_event_source_messages_are_captured() {
+    use remix_browser::tools::{network, websocket};
+
+    let url = spawn_sse_server().await;
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser.new_page("about:blank").await.unwrap();
+
+    let log = network::NetworkLog::new();
+    let params = network::NetworkEnableParams { patterns: None };
+    network::network_enable(std::slice::from_ref(&page), &log, &params)
+        .await
+        .unwrap();
+    page.goto(url.as_str()).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(800)).await;
+
+    let params = websocket::GetWebSocketLogParams {
+        direction: Some("received".into()),
+        payload_regex: Some("price".into()),
+        ..Default::default()
+    };
+    let messages = websocket::get_websocket_log(&log.websockets, &params)
+        .await
+        .unwrap();
+    assert_eq!(messages.len(), 1, "only the matching SSE message");
+    assert_eq!(messages[0].protocol, "eventsource");
+    assert_eq!(messages[0].event_name.as_deref(), Some("tick"));
+    assert_eq!(messages[0].event_id.as_deref(), Some("1"));
+    assert!(messages[0].url.ends_with("/events"));
+
+    let all = websocket::get_websocket_log(&log.websockets, &Default::default())
+        .await
+        .unwrap();
+    assert_eq!(all.len(), 2);
+    assert!(all.iter().all(|m| m.connection_id == messages[0].connection_id));
+}
+
+#[tokio::test]
+async fn test_snapshot_refs_are_stable_and_go_stale() {
+    use remix_browser::tools::snapshot;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("form.html").as_str())
+        .await
+        .unwrap();
+    let params = snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+
+    let counter = snapshot::RefCounter::new();
+    let first = snapshot::snapshot_with_refs(&page, &params, &counter)
+        .await
+        .unwrap();
+    let submit_ref = ref_for(&page, "#submit-btn").await;
+
+    // Insert a button ahead of the form and take another snapshot
+    page.evaluate(
+        "(() => { const b = document.createElement('button'); b.textContent = 'New';
+            document.body.prepend(b); })()",
+    )
+    .await
+    .unwrap();
+    let second = snapshot::snapshot_with_refs(&page, &params, &counter)
+        .await
+        .unwrap();
+    assert_eq!(ref_for(&page, "#submit-btn").await, submit_ref);
+    assert_eq!(second.refs.len(), first.refs.len() + 1);
+    for ref_id in first.refs.keys() {
+        assert!(second.refs.contains_key(ref_id), "{} should survive", ref_id);
+    }
+
+    assert!(snapshot::ref_attached(&page, &submit_ref).await.unwrap());
+    page.evaluate("document.getElementById('submit-btn').remove()")
+        .await
+        .unwrap();
+    assert!(!snapshot::ref_attached(&page, &submit_ref).await.unwrap());
+    let third = snapshot::snapshot_with_refs(&page, &params, &counter)
+        .await
+        .unwrap();
+    assert!(!third.refs.contains_key(&submit_ref), "removed refs are never reissued");
+}
+
+#[tokio::test]
+async fn test_refs_stay_stale_after_link_navigation() {
+    use remix_browser::tools::snapshot;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("form.html").as_str())
+        .await
+        .unwrap();
+    page.evaluate(format!(
+        "(() => {{ const a = document.createElement('a'); a.id = 'next';
+            a.href = {:?}; a.textContent = 'Next'; document.body.append(a); }})()",
+        fixture_url("basic.html")
+    ))
+    .await
+    .unwrap();
+    let params = snapshot::SnapshotParams {
+        selector: None,
+        ..Default::default()
+    };
+    let counter = snapshot::RefCounter::new();
+    let before = snapshot::snapshot_with_refs(&page, &params, &counter)
+        .await
+        .unwrap();
+
+    // Leave the document without the navigate tool
+    page.evaluate("document.getElementById('next').click()")
+        .await
+        .unwrap();
+    for _ in 0..30 {
+        if page.get_title().await.unwrap().as_deref() == Some("Basic Test Page") {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let after = snapshot::snapshot_with_refs(&page, &params, &counter)
+        .await
+        .unwrap();
+    assert!(!after.refs.is_empty());
+    for ref_id in before.refs.keys() {
+        assert!(!after.refs.contains_key(ref_id), "{} was reissued", ref_id);
+        assert!(!snapshot::ref_attached(&page, ref_id).await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn test_accessibility_snapshot_uses_computed_names() {
+    use remix_browser::tools::snapshot;
+
+    let (browser, _handle, _tmp) = launch_test_browser().await;
+    let page = browser
+        .new_page(fixture_url("form.html").as_str())
+        .await
+        .unwrap();
+    page.evaluate(
+        "(() => { const b = document.createElement('button'); b.textContent = 'Hidden';
+            b.setAttribute('aria-hidden', 'true'); document.body.append(b); })()",
+    )
+    .await
+    .unwrap();
+
+    let params = snapshot::SnapshotParams {
+        selector: None,
+        mode: Some("accessibility".to_string()),
+    };
+    let result = snapshot::snapshot_with_refs(&page, &params, &Default::default())
+        .await
+        .unwrap();
+
+    // Names come from the <label for>, not the placeholder
+    assert!(result.text.contains("textbox \"Name:\""));
+    assert!(result.text.contains("button \"Submit\""));
+    assert!(!result.text.contains("Hidden"), "aria-hidden nodes are skipped");
+
+    // Refs are pinned the same way as in DOM mode and resolve to the element
+    let submit_ref = ref_for(&page, "#submit-btn").await;
+    assert!(result.refs.contains_key(&submit_ref));
+    assert!(snapshot::ref_attached(&page, &submit_ref).await.unwrap());
+    let dom = snapshot::snapshot_with_refs(
+        &page,
+        &snapshot::SnapshotParams {
+            selector: None,
+            ..Default::default()
+        },
+        &Default::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(dom.refs.get(&submit_ref), result.refs.get(&submit_ref));
+
+    let bad = snapshot::SnapshotParams {
+        selector: None,
+        mode: Some("pixels".to_string()),
+    };
+    assert!(
+        snapshot::snapshot_with_refs(&page, &bad, &Default::default())
+            .await
+            .is_err()
+    );
+}
+
+// ── Tab Pool Tests ──────────────────────────────────────────────────────
+
+async fn launch_test_session() -> remix_browser::browser::BrowserSession {
+    use remix_browser::tools::{emulation, network, route};
+
+    launch_test_session_with(
+        network::NetworkLog::new(),
+        route::Routes::new(),
+        emulation::Emulation::new(),
+    )
+    .await
+}
+
+/// A session sharing the given state, as the server hands it over at launch.
+async fn launch_test_session_with(
+    network_log: remix_browser::tools::network::NetworkLog,
+    routes: remix_browser::tools::route::Routes,
+    emulation: remix_browser::tools::emulation::Emulation,
+) -> remix_browser::browser::BrowserSession {
+    remix_browser::browser::BrowserSession::launch(
+        true,
+        remix_browser::tools::javascript::ConsoleLog::new(),
+        network_log,
+        routes,
+        emulation,
+    )
+    .await
+    .expect("Failed to launch browser session")
+}
+
+/// Tab ids in pool order, plus the active tab's id.
+async fn tab_ids(tabs: &remix_browser::browser::Tabs) -> (Vec<String>, String) {
+    let (pages, active) = tabs.pages().await;
+    let ids: Vec<String> = pages
+        .iter()
+        .map(|p| p.target_id().as_ref().to_string())
+        .collect();
+    let active_id = ids[active].clone();
+    (ids, active_id)
+}
+
+/// Poll until the pool holds `count` tabs, returning their ids.
+async fn wait_for_tab_count(tabs: &remix_browser::browser::Tabs, count: usize) -> Vec<String> {
+    for _ in 0..50 {
+        let (pages, _) = tabs.pages().await;
+        if pages.len() == count {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let (ids, _) = tab_ids(tabs).await;
+    assert_eq!(ids.len(), count, "expected {} tabs, have {:?}", count, ids);
+    ids
+}
+
+#[tokio::test]
+async fn test_close_tab_by_id_keeps_active_tab() {
+    use remix_browser::tools::page;
+
+    let session = launch_test_session().await;
+    let tabs = session.tabs();
+    let (initial, _) = tab_ids(&tabs).await;
+    let second = tabs.new_page(&fixture_url("basic.html")).await.unwrap();
+    let third = tabs.new_page(&fixture_url("form.html")).await.unwrap();
+    let third_id = third.target_id().as_ref().to_string();
+
+    // Closing an earlier tab must not shift the active tab onto a neighbour
+    let params = page::CloseTabParams {
+        tab_id: Some(initial[0].clone()),
+    };
+    assert!(page::close_tab(&tabs, &params).await.unwrap());
+    let (ids, active) = tab_ids(&tabs).await;
+    assert_eq!(ids.len(), 2);
+    assert_eq!(active, third_id);
+    let title = tabs.active_page().await.unwrap().get_title().await.unwrap();
+    assert_eq!(title.as_deref(), Some("Form Test Page"));
+
+    // The Chrome target is really gone, and a second close reports it
+    let second_id = second.target_id().as_ref().to_string();
+    let params = page::CloseTabParams {
+        tab_id: Some(second_id.clone()),
+    };
+    page::close_tab(&tabs, &params).await.unwrap();
+    assert!(page::close_tab(&tabs, &params).await.is_err());
+    assert!(tabs.get_page(&second_id).await.is_err());
+    let (ids, active) = tab_ids(&tabs).await;
+    assert_eq!(ids, vec![third_id.clone()]);
+    assert_eq!(active, third_id);
+}
+
+#[tokio::test]
+async fn test_window_open_popup_is_adopted_without_switching() {
+    let session = launch_test_session().await;
+    let tabs = session.tabs();
+    let opener = tabs.new_page(&fixture_url("basic.html")).await.unwrap();
+    let opener_id = opener.target_id().as_ref().to_string();
+    let (before, _) = tab_ids(&tabs).await;
+
+    opener
+        .evaluate("window.popup = window.open('basic.html?popup=1'); true")
+        .await
+        .unwrap();
+    let ids = wait_for_tab_count(&tabs, before.len() + 1).await;
+    let popup_id = ids.iter().find(|id| !before.contains(id)).unwrap().clone();
+    let (_, active) = tab_ids(&tabs).await;
+    assert_eq!(active, opener_id, "popups are adopted, not activated");
+
+    let popup = tabs.get_page(&popup_id).await.unwrap();
+    let mut url = String::new();
+    for _ in 0..20 {
+        url = popup.url().await.unwrap().unwrap_or_default();
+        if url.contains("popup=1") {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert!(url.contains("popup=1"), "popup url: {}", url);
+
+    // A popup closed by its opener leaves the pool
+    opener.evaluate("window.popup.close()").await.unwrap();
+    wait_for_tab_count(&tabs, before.len()).await;
+    assert!(tabs.get_page(&popup_id).await.is_err());
+}
+
+#[tokio::test]
+async fn test_target_blank_link_is_adopted() {
+    let session = launch_test_session().await;
+    let tabs = session.tabs();
+    let opener = tabs.new_page(&fixture_url("basic.html")).await.unwrap();
+    let (before, _) = tab_ids(&tabs).await;
+
+    opener
+        .evaluate(
+            "const a = document.createElement('a');\
+             a.href = 'form.html'; a.target = '_blank'; a.textContent = 'open';\
+             document.body.appendChild(a); a.click(); true",
+        )
+        .await
+        .unwrap();
+    let ids = wait_for_tab_count(&tabs, before.len() + 1).await;
+    let popup_id = ids.iter().find(|id| !before.contains(id)).unwrap();
+    let popup = tabs.get_page(popup_id).await.unwrap();
+    let mut title = None;
+    for _ in 0..20 {
+        title = popup.get_title().await.unwrap();
+        if title.as_deref() == Some("Form Test Page") {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(title.as_deref(), Some("Form Test Page"));
+}
+
+#[tokio::test]
+async fn test_popup_closed_immediately_is_not_adopted() {
+    let session = launch_test_session().await;
+    let tabs = session.tabs();
+    let opener = tabs.new_page(&fixture_url("basic.html")).await.unwrap();
+    let (before, active_before) = tab_ids(&tabs).await;
+
+    opener
+        .evaluate("window.open('basic.html?gone=1').close(); true")
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    let (ids, active) = tab_ids(&tabs).await;
+    assert_eq!(ids, before);
+    assert_eq!(active, active_before);
+}
+
+#[tokio::test]
+async fn test_switch_tab_by_index_and_id_updates_active() {
+    use remix_browser::tools::page;
+
+    let session = launch_test_session().await;
+    let tabs = session.tabs();
+    let (initial, _) = tab_ids(&tabs).await;
+    let basic = tabs.new_page(&fixture_url("basic.html")).await.unwrap();
+    let basic_id = basic.target_id().as_ref().to_string();
+    tabs.new_page(&fixture_url("form.html")).await.unwrap();
+
+    let by_index = page::SwitchTabParams {
+        tab_id: None,
+        index: Some(0),
+    };
+    let info = page::switch_tab(&tabs, &by_index).await.unwrap();
+    assert_eq!(info.index, 0);
+    assert_eq!(info.tab_id, initial[0]);
+    assert!(info.active);
+    let listed = page::list_tabs(&tabs).await.unwrap();
+    let active: Vec<usize> = listed.iter().filter(|t| t.active).map(|t| t.index).collect();
+    assert_eq!(active, vec![0]);
+
+    let by_id = page::SwitchTabParams {
+        tab_id: Some(basic_id.clone()),
+        index: None,
+    };
+    let info = page::switch_tab(&tabs, &by_id).await.unwrap();
+    assert_eq!(info.index, 1);
+    assert_eq!(info.title, "Basic Test Page");
+    let listed = page::list_tabs(&tabs).await.unwrap();
+    let active: Vec<&str> = listed
+        .iter()
+        .filter(|t| t.active)
+        .map(|t| t.tab_id.as_str())
+        .collect();
+    assert_eq!(active, vec![basic_id.as_str()]);
+    let current = tabs.active_page().await.unwrap();
+    assert_eq!(current.target_id().as_ref(), basic_id);
+
+    // Bad targets fail without moving the active tab
+    for bad in [
+        page::SwitchTabParams {
+            tab_id: None,
+            index: Some(5),
+        },
+        page::SwitchTabParams {
+            tab_id: Some("no-such-tab".into()),
+            index: None,
+        },
+        page::SwitchTabParams {
+            tab_id: None,
+            index: None,
+        },
+    ] {
+        assert!(page::switch_tab(&tabs, &bad).await.is_err());
+    }
+    let (_, active) = tab_ids(&tabs).await;
+    assert_eq!(active, basic_id);
+}
+
+#[tokio::test]
+async fn test_run_script_new_switch_and_close_tab() {
+    use remix_browser::tools::{javascript, network, script};
+
+    let session = launch_test_session().await;
+    let tabs = session.tabs();
+    tabs.active_page()
+        .await
+        .unwrap()
+        .goto(fixture_url("basic.html"))
+        .await
+        .unwrap();
+
+    let script = format!(
+        r#"const first = page.tabs()[0].tab_id;
+        const id = page.newTab('{}');
+        console.log('new:' + page.title());
+        console.log('new-active:' + (page.tabs().find(t => t.active).tab_id === id));
+        const info = page.switchTab(0);
+        console.log('by-index:' + (info.tab_id === first) + ':' + page.title());
+        page.switchTab(id);
+        console.log('by-id:' + page.title());
+        page.closeTab(id);
+        console.log('closed:' + page.tabs().length + ':' + page.title());"#,
+        fixture_url("form.html")
+    );
+    let params = script::RunScriptParams { script };
+    let (result, _screenshots, _refs) = script::run_script_with_tabs(
+        &tabs,
+        &params,
+        &javascript::ConsoleLog::new(),
+        &network::NetworkLog::new(),
+        &std::env::temp_dir(),
+        None,
+    )
+    .await
+    .unwrap();
+
+    assert!(result.success, "Script failed: {:?}", result.error);
+    for expected in [
+        "new:Form Test Page",
+        "new-active:true",
+        "by-index:true:Basic Test Page",
+        "by-id:Form Test Page",
+        "closed:1:Basic Test Page",
+    ] {
+        assert!(
+            result.output.contains(expected),
+            "missing {:?} in:\n{}",
+            expected,
+            result.output
+        );
+    }
+    assert!(result.url.contains("basic.html"));
+    assert_eq!(tab_ids(&tabs).await.0.len(), 1);
+}
+
+/// Call `window.open(url)` from `opener` and return the popup once the pool adopts it.
+async fn open_popup(
+    tabs: &remix_browser::browser::Tabs,
+    opener: &chromiumoxide::Page,
+    url: &str,
+) -> chromiumoxide::Page {
+    let (before, _) = tab_ids(tabs).await;
+    opener
+        .evaluate(format!("window.open('{}'); true", url))
+        .await
+        .unwrap();
+    let ids = wait_for_tab_count(tabs, before.len() + 1).await;
+    let popup_id = ids.iter().find(|id| !before.contains(id)).unwrap();
+    tabs.get_page(popup_id).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_routes_apply_to_first_load_of_new_tabs_and_popups() {
+    use remix_browser::tools::route;
+
+    let session = launch_test_session().await;
+    let tabs = session.tabs();
+    let mock: route::RouteParams = serde_json::from_value(serde_json::json!({
+        "url_pattern": "https://routed.test/*",
+        "body": "<html><head><title>Mocked</title></head><body>mocked</body></html>",
+        "content_type": "text/html",
+    }))
+    .unwrap();
+    let (pages, _) = tabs.pages().await;
+    route::add_route(&pages, tabs.routes(), &mock).await.unwrap();
+
+    // The tab is intercepted before its first navigation leaves the browser
+    let tab = tabs.new_page("https://routed.test/tab").await.unwrap();
+    assert_eq!(tab.get_title().await.unwrap().as_deref(), Some("Mocked"));
+
+    // A popup is held until its listeners are attached
+    let popup = open_popup(&tabs, &tab, "https://routed.test/popup").await;
+    let mut title = None;
+    for _ in 0..30 {
+        title = popup.get_title().await.unwrap();
+        if title.as_deref() == Some("Mocked") {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(title.as_deref(), Some("Mocked"));
+    assert!(tabs.routes().list().await.iter().all(|r| r.hits >= 2));
+}
+
+#[tokio::test]
+async fn test_replay_har_serves_first_load_of_new_tab() {
+    use remix_browser::tools::{emulation, network, route};
+
+    let dir = tempfile::tempdir().unwrap();
+    let har_path = dir.path().join("site.har");
+    let har = serde_json::json!({ "log": { "version": "1.2", "entries": [
+        { "request": { "method": "GET", "url": "https://replay.test/new-tab" },
+          "response": { "status": 200,
+                        "headers": [{ "name": "Content-Type", "value": "text/html" }],
+                        "content": { "mimeType": "text/html", "text": "<title>Recorded</title>" } } }
+    ] } });
+    std::fs::write(&har_path, har.to_string()).unwrap();
+
+    // Same order as --replay-har: replay is set up before any tab exists
+    let routes = route::Routes::new();
+    let replay = route::HarReplay::load(&har_path.display().to_string(), false).unwrap();
+    routes.set_replay(Some(replay)).await;
+    let session = launch_test_session_with(
+        network::NetworkLog::new(),
+        routes.clone(),
+        emulation::Emulation::new(),
+    )
+    .await;
+
+    let tab = session
+        .new_page("https://replay.test/new-tab")
+        .await
+        .unwrap();
+    assert_eq!(tab.get_title().await.unwrap().as_deref(), Some("Recorded"));
+    assert!(routes.replay().await.unwrap().status().served >= 1);
+}
+
+#[tokio::test]
+async fn test_block_resources_apply_to_first_load_of_new_tabs_and_popups() {
+    use remix_browser::tools::{block, emulation, network, route};
+
+    // Same order as --block-resources: rules are in place before any tab exists
+    let routes = route::Routes::new();
+    routes
+        .set_blocking(Some(block::BlockRules::parse("*/slow.png").unwrap()))
+        .await;
+    let session = launch_test_session_with(
+        network::NetworkLog::new(),
+        routes.clone(),
+        emulation::Emulation::new(),
+    )
+    .await;
+    let tabs = session.tabs();
+    let url = spawn_slow_subresource_server(0).await;
+    let blocked = || async {
+        routes
+            .blocking()
+            .await
+            .unwrap()
+            .blocked
+            .load(std::sync::atomic::Ordering::Relaxed)
+    };
+
+    let tab = tabs.new_page(&url).await.unwrap();
+    assert_eq!(tab.get_title().await.unwrap().as_deref(), Some("Slow Page"));
+    assert_eq!(blocked().await, 1);
+
+    open_popup(&tabs, &tab, &format!("{}?popup", url)).await;
+    for _ in 0..30 {
+        if blocked().await == 2 {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(blocked().await, 2);
+}
+
+/// Serve a small page, counting the connections Chrome opens.
+async fn spawn_counting_server() -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let hits = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let counter = hits.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut buf = [0u8; 2048];
+                let _ = stream.read(&mut buf).await;
+                let body = "<html><head><title>Online</title></head><body></body></html>";
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    (format!("http://{}/", addr), hits)
+}
+
+#[tokio::test]
+async fn test_emulation_applies_before_first_load_of_new_tabs_and_popups() {
+    use remix_browser::tools::{emulation, network, route};
+    use std::sync::atomic::Ordering;
+
+    let emu = emulation::Emulation::new();
+    let offline = emulation::EmulateNetworkParams {
+        preset: Some("offline".into()),
+        ..Default::default()
+    };
+    emulation::emulate_network(&[], &emu, &offline)
+        .await
+        .unwrap();
+    let session = launch_test_session_with(
+        network::NetworkLog::new(),
+        route::Routes::new(),
+        emu.clone(),
+    )
+    .await;
+    let tabs = session.tabs();
+    let (url, hits) = spawn_counting_server().await;
+
+    // Offline before the first request: nothing reaches the server
+    let _ = tabs.new_page(&url).await;
+    let (pages, _) = tabs.pages().await;
+    open_popup(&tabs, &pages[0], &format!("{}?popup", url)).await;
+    tokio::time::sleep(std::time::Duration::from_millis(1_000)).await;
+    assert_eq!(hits.load(Ordering::SeqCst), 0);
+
+    // Back online, new tabs load normally
+    let online = emulation::EmulateNetworkParams {
+        preset: Some("none".into()),
+        ..Default::default()
+    };
+    let (pages, _) = tabs.pages().await;
+    emulation::emulate_network(&pages, &emu, &online)
+        .await
+        .unwrap();
+    let tab = tabs.new_page(&url).await.unwrap();
+    assert_eq!(tab.get_title().await.unwrap().as_deref(), Some("Online"));
+    assert!(hits.load(Ordering::SeqCst) >= 1);
+}
+
+#[tokio::test]
+async fn test_network_log_captures_first_load_of_new_tabs_and_popups() {
+    use remix_browser::tools::{emulation, network, route};
+
+    let log = network::NetworkLog::new();
+    log.enable(None).await;
+    let session = launch_test_session_with(
+        log.clone(),
+        route::Routes::new(),
+        emulation::Emulation::new(),
+    )
+    .await;
+    let tabs = session.tabs();
+    let (url, _) = spawn_counting_server().await;
+    let document_of = |tab: &chromiumoxide::Page| {
+        let (log, tab_id) = (log.clone(), tab.target_id().as_ref().to_string());
+        async move {
+            log.get_log(None, None, None)
+                .await
+                .into_iter()
+                .find(|e| e.navigation && e.tab_id == tab_id)
+        }
+    };
+
+    // The document request is logged, not just what follows it
+    let tab = tabs.new_page(&url).await.unwrap();
+    let entry = document_of(&tab)
+        .await
+        .expect("new tab's document request should be logged");
+    assert_eq!(entry.url, url);
+    assert_eq!(entry.status, 200);
+
+    let popup = open_popup(&tabs, &tab, &format!("{}?popup", url)).await;
+    let mut entry = None;
+    for _ in 0..30 {
+        entry = document_of(&popup).await;
+        if entry.as_ref().is_some_and(|e| e.status == 200) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    let entry = entry.expect("popup's document request should be logged");
+    assert!(entry.url.ends_with("?popup"));
+    assert_eq!(entry.status, 200);
+}
+
+// ── Console Tests ───────────────────────────────────────────────────────
+
+/// Poll `read_console` until it returns `count` entries, returning them.
+async fn read_console_until(
+    log: &remix_browser::tools::javascript::ConsoleLog,
+    params: &remix_browser::tools::javascript::ReadConsoleParams,
+    count: usize,
+) -> Vec<serde_json::Value> {
+    let mut entries = Vec::new();
+    for _ in 0..30 {
+        let value = remix_browser::tools::javascript::read_console(log, params)
+            .await
+            .unwrap();
+        entries = value.as_array().cloned().unwrap_or_default();
+        if entries.len() >= count {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(entries.len(), count, "console entries: {:?}", entries);
+    entries
+}
+
+#[tokio::test]
+async fn test_console_calls_are_formatted_with_source_and_tab() {
+    use remix_browser::tools::javascript::{ConsoleLog, ReadConsoleParams};
+    use remix_browser::tools::{emulation, network, route};
+
+    let log = ConsoleLog::new();
+    let session = remix_browser::browser::BrowserSession::launch(
+        true,
+        log.clone(),
+        network::NetworkLog::new(),
+        route::Routes::new(),
+        emulation::Emulation::new(),
+    )
+    .await
+    .expect("Failed to launch browser session");
+    let tabs = session.tabs();
+    let url = fixture_url("console.html");
+    let first = tabs.new_page(&url).await.unwrap();
+    let second = tabs.new_page(&url).await.unwrap();
+    let first_id = first.target_id().as_ref().to_string();
+    let second_id = second.target_id().as_ref().to_string();
+
+    first.evaluate("logCart('cart', 2.7)").await.unwrap();
+    second.evaluate("warnStock('A-1')").await.unwrap();
+
+    let params = |tab_id: &str| ReadConsoleParams {
+        level: None,
+        clear: None,
+        pattern: None,
+        limit: None,
+        tab_id: Some(tab_id.to_string()),
+    };
+
+    // Format specifiers are substituted and the call site is the fixture line
+    let entries = read_console_until(&log, &params(&first_id), 1).await;
+    assert_eq!(entries[0]["level"], "log");
+    assert_eq!(entries[0]["text"], "cart has 2 items");
+    assert_eq!(entries[0]["url"], url.as_str());
+    assert_eq!(entries[0]["line"], 10);
+    assert_eq!(entries[0]["tab_id"], first_id.as_str());
+
+    // Each tab only sees its own entries
+    let entries = read_console_until(&log, &params(&second_id), 1).await;
+    assert_eq!(entries[0]["level"], "warn");
+    assert_eq!(entries[0]["text"], "low stock: A-1");
+    assert_eq!(entries[0]["line"], 13);
+    assert_eq!(entries[0]["tab_id"], second_id.as_str());
+
+    let all = ReadConsoleParams {
+        tab_id: None,
+        ..params(&first_id)
+    };
+    read_console_until(&log, &all, 2).await;
+}
+
+// ── Auto-Snapshot Tests ─────────────────────────────────────────────────
+
+/// The remix-browser binary as an MCP client sees it: JSON-RPC over stdio.
+struct McpProcess {
+    child: std::process::Child,
+    stdin: Option<std::process::ChildStdin>,
+    stdout: std::io::BufReader<std::process::ChildStdout>,
+    next_id: u64,
+}
+
+impl McpProcess {
+    fn start(args: &[&str]) -> Self {
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_remix-browser"))
+            .args(args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .expect("Failed to start remix-browser");
+        let mut server = Self {
+            stdin: child.stdin.take(),
+            stdout: std::io::BufReader::new(child.stdout.take().unwrap()),
+            child,
+            next_id: 0,
+        };
+        server.request(
+            "initialize",
+            serde_json::json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": { "name": "integration-tests", "version": "0" }
+            }),
+        );
+        server.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized"
+        }));
+        server
+    }
+
+    fn send(&mut self, message: serde_json::Value) {
+        use std::io::Write;
+
+        let stdin = self.stdin.as_mut().unwrap();
+        writeln!(stdin, "{}", message).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    fn request(&mut self, method: &str, params: serde_json::Value) -> serde_json::Value {
+        use std::io::BufRead;
+
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        }));
+        loop {
+            let mut line = String::new();
+            assert!(
+                self.stdout.read_line(&mut line).unwrap() > 0,
+                "server exited"
+            );
+            let message: serde_json::Value = serde_json::from_str(&line).unwrap();
+            if message["id"] == id {
+                return message;
+            }
+        }
+    }
+
+    /// Call a tool and return the text of its result.
+    fn call_tool(&mut self, name: &str, arguments: serde_json::Value) -> String {
+        let response = self.request(
+            "tools/call",
+            serde_json::json!({ "name": name, "arguments": arguments }),
+        );
+        response["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap_or_else(|| panic!("{} failed: {}", name, response))
+            .to_string()
+    }
+}
+
+impl Drop for McpProcess {
+    fn drop(&mut self) {
+        // Closing stdin ends the session; the server then shuts Chrome down
+        self.stdin.take();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_auto_snapshot_modes_and_snapshot_baseline() {
+    let mut server = McpProcess::start(&["--auto-snapshot", "diff"]);
+    let form = fixture_url("form.html");
+    let wait_for_submit = serde_json::json!({ "selector": "#submit-btn" });
+
+    // Nothing to compare against yet: the whole page
+    let text = server.call_tool("navigate", serde_json::json!({ "url": form }));
+    assert!(text.contains("Page state:"), "{}", text);
+
+    // The explicit snapshot becomes the baseline, so the added button is not repeated
+    server.call_tool(
+        "execute_js",
+        serde_json::json!({ "expression": "(() => { const b = document.createElement('button'); \
+            b.id = 'added'; b.textContent = 'Added'; document.body.append(b); return true; })()" }),
+    );
+    let text = server.call_tool("snapshot", serde_json::json!({}));
+    assert!(text.contains("Added"), "{}", text);
+    let text = server.call_tool("wait_for", wait_for_submit.clone());
+    assert!(text.ends_with("Page state: unchanged"), "{}", text);
+
+    // Same URL: only what changed
+    server.call_tool(
+        "execute_js",
+        serde_json::json!({ "expression": "document.getElementById('added').remove()" }),
+    );
+    let text = server.call_tool("wait_for", wait_for_submit);
+    assert!(text.contains("Page changes:"), "{}", text);
+    assert!(
+        text.lines()
+            .any(|l| l.starts_with("- ") && l.contains("Added")),
+        "{}",
+        text
+    );
+
+    // A new URL starts over with the whole page
+    let text = server.call_tool(
+        "navigate",
+        serde_json::json!({ "url": fixture_url("basic.html") }),
+    );
+    assert!(text.contains("Page state:"), "{}", text);
+    assert!(!text.contains("Page changes:"), "{}", text);
+
+    let mut server = McpProcess::start(&["--auto-snapshot", "off"]);
+    let text = server.call_tool("navigate", serde_json::json!({ "url": form }));
+    assert!(!text.contains("Page state"), "{}", text);
+    assert!(!text.contains("[ref="), "{}", text);
+}
+
+// ── Page Error Tests ────────────────────────────────────────────────────
+
+#[test]
+fn test_click_reports_thrown_errors_and_rejections() {
+    let mut server = McpProcess::start(&["--auto-snapshot", "off"]);
+    let url = fixture_url("console.html");
+    server.call_tool("navigate", serde_json::json!({ "url": url }));
+
+    // The error thrown by the handler comes back with the click itself
+    let text = server.call_tool("click", serde_json::json!({ "selector": "#throw" }));
+    assert!(text.contains("Page errors:"), "{}", text);
+    assert!(
+        text.contains(&format!("- Uncaught Error: checkout failed ({}:21)", url)),
+        "{}",
+        text
+    );
+
+    let text = server.call_tool("click", serde_json::json!({ "selector": "#reject" }));
+    assert!(
+        text.contains("- Uncaught (in promise) Error: payment declined"),
+        "{}",
+        text
+    );
+    assert!(!text.contains("checkout failed"), "{}", text);
+
+    // Both are kept as exceptions, with the stack that led to them
+    let text = server.call_tool("read_console", serde_json::json!({ "level": "exception" }));
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&text).unwrap();
+    assert_eq!(entries.len(), 2, "{}", text);
+    for (entry, (message, line)) in entries.iter().zip([
+        ("Uncaught Error: checkout failed", 21),
+        ("Uncaught (in promise) Error: payment declined", 24),
+    ]) {
+        assert_eq!(entry["level"], "exception");
+        assert_eq!(entry["text"], message);
+        assert_eq!(entry["line"], line);
+        let stack = entry["stack"]
+            .as_str()
+            .unwrap_or_else(|| panic!("no stack: {}", entry));
+        assert!(stack.contains(&format!("{}:{}:", url, line)), "{}", stack);
+    }
+}